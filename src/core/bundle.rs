@@ -0,0 +1,438 @@
+use crate::{
+    core::{
+        project::{get_projects, relocate_project, store_project},
+        prompt::{get_prompts, store_prompts},
+        scroll::{get_scrolls, relative_scroll_path, store_scroll},
+        tag::{get_marks, BranchLabel},
+    },
+    utils::{
+        error::AppError,
+        logger::{log_error, log_info},
+        structs::{Project, Prompt, Scroll},
+    },
+};
+use anyhow::Result;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteArguments, SqlitePool};
+use sqlx::{Sqlite, SqliteConnection};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Version of the bundle layout written by `write_bundle`.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// File extension used for project bundles.
+pub const BUNDLE_EXTENSION: &str = "legatio";
+
+/// A self-contained copy of a project: its row, its scrolls, all of its prompts
+/// and their merge parents, tags, stars and branch labels.
+///
+/// Scroll paths inside the project directory are stored relative to it, so the
+/// bundle can be imported under a different root on another machine.
+/// Bundles written before the marks were exported read them as empty.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProjectBundle {
+    pub version: u32,
+    pub created_at: String,
+    pub project: Project,
+    pub scrolls: Vec<Scroll>,
+    pub prompts: Vec<Prompt>,
+    #[serde(default)]
+    pub parents: Vec<BundleParent>,
+    #[serde(default)]
+    pub tags: Vec<BundleTag>,
+    #[serde(default)]
+    pub stars: Vec<String>,
+    #[serde(default)]
+    pub branches: Vec<BranchLabel>,
+}
+
+/// Second parent of a merge prompt, as stored in `prompt_parents`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleParent {
+    pub prompt_id: String,
+    pub parent_id: String,
+}
+
+/// A tag of a prompt, as stored in `prompt_tags`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleTag {
+    pub prompt_id: String,
+    pub tag: String,
+}
+
+/// Summary of what `import_bundle` inserted and what it skipped as duplicates.
+#[derive(Debug)]
+pub struct BundleImport {
+    pub project: Project,
+    pub new_project: bool,
+    pub prompts_added: usize,
+    pub prompts_skipped: usize,
+    pub scrolls_added: usize,
+    pub scrolls_skipped: usize,
+}
+
+/// Collects a project and everything attached to it into a bundle.
+pub async fn build_bundle(pool: &SqlitePool, project: &Project) -> Result<ProjectBundle> {
+    let prompts = get_prompts(pool, &project.project_id).await?;
+    let scrolls = get_scrolls(pool, &project.project_id)
+        .await?
+        .into_iter()
+        .map(|scroll| Scroll {
+            scroll_path: relative_scroll_path(&project.project_path, &scroll.scroll_path)
+                .unwrap_or(scroll.scroll_path),
+            ..scroll
+        })
+        .collect();
+
+    // Sorted, so exporting the same project twice writes the same file
+    let marks = get_marks(pool, &project.project_id).await?;
+    let mut parents: Vec<BundleParent> = marks
+        .merged
+        .into_iter()
+        .flat_map(|(prompt_id, parent_ids)| {
+            parent_ids.into_iter().map(move |parent_id| BundleParent {
+                prompt_id: prompt_id.clone(),
+                parent_id,
+            })
+        })
+        .collect();
+    parents.sort_by(|a, b| (&a.prompt_id, &a.parent_id).cmp(&(&b.prompt_id, &b.parent_id)));
+    let mut tags: Vec<BundleTag> = marks
+        .tags
+        .into_iter()
+        .flat_map(|(prompt_id, tags)| {
+            tags.into_iter().map(move |tag| BundleTag {
+                prompt_id: prompt_id.clone(),
+                tag,
+            })
+        })
+        .collect();
+    tags.sort_by(|a, b| (&a.prompt_id, &a.tag).cmp(&(&b.prompt_id, &b.tag)));
+    let mut stars: Vec<String> = marks.starred.into_iter().collect();
+    stars.sort();
+
+    Ok(ProjectBundle {
+        version: BUNDLE_VERSION,
+        created_at: Local::now().to_rfc3339(),
+        project: project.clone(),
+        scrolls,
+        prompts,
+        parents,
+        tags,
+        stars,
+        branches: marks.branches,
+    })
+}
+
+/// Writes a bundle as a single JSON archive file.
+pub fn write_bundle(bundle: &ProjectBundle, file_path: &Path) -> Result<()> {
+    let json = serde_json::to_string_pretty(bundle).map_err(|err| {
+        log_error(&format!("Failed to serialize bundle. Reason: {}", err));
+        AppError::ParseError(format!("Failed to serialize bundle. Reason: {}", err))
+    })?;
+
+    fs::write(file_path, json).map_err(|err| {
+        log_error(&format!(
+            "Failed to write bundle {}: {}",
+            file_path.to_string_lossy(),
+            err
+        ));
+        AppError::FileError(format!(
+            "Failed to write bundle {}: {}",
+            file_path.to_string_lossy(),
+            err
+        ))
+    })?;
+
+    Ok(())
+}
+
+/// Reads a bundle written by `write_bundle`.
+pub fn read_bundle(file_path: &Path) -> Result<ProjectBundle> {
+    let json = fs::read_to_string(file_path).map_err(|err| {
+        log_error(&format!(
+            "Failed to read bundle {}: {}",
+            file_path.to_string_lossy(),
+            err
+        ));
+        AppError::FileError(format!(
+            "Failed to read bundle {}: {}",
+            file_path.to_string_lossy(),
+            err
+        ))
+    })?;
+
+    let bundle: ProjectBundle = serde_json::from_str(&json).map_err(|err| {
+        log_error(&format!("Failed to parse bundle. Reason: {}", err));
+        AppError::ParseError(format!("Failed to parse bundle. Reason: {}", err))
+    })?;
+
+    if bundle.version > BUNDLE_VERSION {
+        return Err(AppError::ParseError(format!(
+            "Bundle version {} is newer than the supported version {}",
+            bundle.version, BUNDLE_VERSION
+        ))
+        .into());
+    }
+
+    Ok(bundle)
+}
+
+/// Exports a project into `<project_path>/<project name>.legatio`.
+///
+/// # Returns:
+/// - `Ok(PathBuf)` with the path of the written bundle.
+pub async fn export_bundle(pool: &SqlitePool, project: &Project) -> Result<PathBuf> {
+    let bundle = build_bundle(pool, project).await?;
+    let name = project
+        .project_path
+        .split('/')
+        .next_back()
+        .filter(|n| !n.is_empty())
+        .unwrap_or("project");
+    let file_path = Path::new(&project.project_path).join(format!("{}.{}", name, BUNDLE_EXTENSION));
+
+    write_bundle(&bundle, &file_path)?;
+    log_info(&format!(
+        "Exported bundle of project {} to {}",
+        project.project_id,
+        file_path.to_string_lossy()
+    ));
+    Ok(file_path)
+}
+
+/// Runs an insert of the marks of an imported prompt, mapping its error.
+async fn insert_mark<'q>(
+    conn: &mut SqliteConnection,
+    query: sqlx::query::Query<'q, Sqlite, SqliteArguments<'q>>,
+    table: &str,
+    prompt_id: &str,
+) -> Result<()> {
+    query.execute(conn).await.map_err(|err| {
+        log_error(&format!(
+            "FAILED :: INSERT {} of prompt_id: [{}], error: {}",
+            table, prompt_id, err
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to import the {} of prompt {}. Reason: {}",
+            table, prompt_id, err
+        ))
+    })?;
+    Ok(())
+}
+
+/// Imports a bundle with its project rooted at `root_path`.
+///
+/// Rows are matched by id: an existing project receives only the prompts and
+/// scrolls it does not have yet, and its path is remapped to `root_path`.
+/// Merge parents, tags, stars and branch labels follow their prompt when it is
+/// reused under another id; branch labels already in the project are kept.
+/// Relative scroll paths are kept as-is and thus resolve against `root_path`.
+/// The import runs in one transaction, so a failure leaves the database unchanged.
+///
+/// # Returns:
+/// - `Ok(BundleImport)` describing what was added and skipped.
+/// - `AppError::DatabaseError` if `root_path` already belongs to another project.
+pub async fn import_bundle(
+    pool: &SqlitePool,
+    bundle: &ProjectBundle,
+    root_path: &str,
+) -> Result<BundleImport> {
+    let mut tx = pool.begin().await.map_err(|err| {
+        log_error(&format!("Failed to start transaction. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to start transaction. Reason: {}", err))
+    })?;
+
+    let projects = get_projects(&mut *tx).await?;
+    if let Some(other) = projects
+        .iter()
+        .find(|p| p.project_path == root_path && p.project_id != bundle.project.project_id)
+    {
+        return Err(AppError::DatabaseError(format!(
+            "Path {} is already used by project {}",
+            root_path, other.project_id
+        ))
+        .into());
+    }
+
+    let project = Project {
+        project_path: root_path.to_string(),
+        ..bundle.project.clone()
+    };
+    let existing = projects.iter().find(|p| p.project_id == project.project_id);
+    let new_project = existing.is_none();
+
+    match existing {
+        Some(existing) => {
+            relocate_project(&mut *tx, existing, root_path).await?;
+        }
+        None => store_project(&mut *tx, &project).await?,
+    }
+
+    let known_prompts: HashSet<String> = get_prompts(&mut *tx, &project.project_id)
+        .await?
+        .into_iter()
+        .map(|p| p.prompt_id)
+        .collect();
+    let known_scrolls: HashSet<String> = get_scrolls(&mut *tx, &project.project_id)
+        .await?
+        .into_iter()
+        .map(|s| s.scroll_id)
+        .collect();
+
+    let mut report = BundleImport {
+        project: project.clone(),
+        new_project,
+        prompts_added: 0,
+        prompts_skipped: 0,
+        scrolls_added: 0,
+        scrolls_skipped: 0,
+    };
+
+    let new_prompts: Vec<Prompt> = bundle
+        .prompts
+        .iter()
+        .filter(|p| !known_prompts.contains(&p.prompt_id))
+        .map(|p| Prompt {
+            project_id: project.project_id.clone(),
+            ..p.clone()
+        })
+        .collect();
+    report.prompts_skipped += bundle.prompts.len() - new_prompts.len();
+
+    // Stored prompts come parents first, so the final id of a parent is known
+    // when its reused children are matched back to their bundle id
+    let mut by_content: HashMap<(&str, &str), Vec<&Prompt>> = HashMap::new();
+    for p in new_prompts.iter() {
+        by_content
+            .entry((p.content.as_str(), p.output.as_str()))
+            .or_default()
+            .push(p);
+    }
+    let mut final_ids: HashMap<String, String> = HashMap::new();
+    for (stored, result) in store_prompts(&mut *tx, &new_prompts).await? {
+        if !result.is_reused() {
+            report.prompts_added += 1;
+            continue;
+        }
+        report.prompts_skipped += 1;
+        let original = by_content
+            .get(&(stored.content.as_str(), stored.output.as_str()))
+            .into_iter()
+            .flatten()
+            .find(|p| {
+                !final_ids.contains_key(&p.prompt_id)
+                    && final_ids
+                        .get(&p.prev_prompt_id)
+                        .unwrap_or(&p.prev_prompt_id)
+                        == &stored.prev_prompt_id
+            });
+        if let Some(original) = original {
+            final_ids.insert(original.prompt_id.clone(), stored.prompt_id.clone());
+        }
+    }
+    let final_id = |id: &String| final_ids.get(id).unwrap_or(id).clone();
+
+    for parent in bundle.parents.iter() {
+        insert_mark(
+            &mut tx,
+            sqlx::query(
+                "INSERT OR IGNORE INTO prompt_parents (project_id, prompt_id, parent_id)
+                 VALUES ($1, $2, $3);",
+            )
+            .bind(&project.project_id)
+            .bind(final_id(&parent.prompt_id))
+            .bind(final_id(&parent.parent_id)),
+            "prompt_parents",
+            &parent.prompt_id,
+        )
+        .await?;
+    }
+    for tag in bundle.tags.iter() {
+        insert_mark(
+            &mut tx,
+            sqlx::query(
+                "INSERT OR IGNORE INTO prompt_tags (project_id, prompt_id, tag)
+                 VALUES ($1, $2, $3);",
+            )
+            .bind(&project.project_id)
+            .bind(final_id(&tag.prompt_id))
+            .bind(&tag.tag),
+            "prompt_tags",
+            &tag.prompt_id,
+        )
+        .await?;
+    }
+    for prompt_id in bundle.stars.iter() {
+        insert_mark(
+            &mut tx,
+            sqlx::query(
+                "INSERT OR IGNORE INTO prompt_stars (project_id, prompt_id) VALUES ($1, $2);",
+            )
+            .bind(&project.project_id)
+            .bind(final_id(prompt_id)),
+            "prompt_stars",
+            prompt_id,
+        )
+        .await?;
+    }
+    for branch in bundle.branches.iter() {
+        insert_mark(
+            &mut tx,
+            sqlx::query(
+                "INSERT OR IGNORE INTO branch_labels (project_id, name, prompt_id)
+                 VALUES ($1, $2, $3);",
+            )
+            .bind(&project.project_id)
+            .bind(&branch.name)
+            .bind(final_id(&branch.prompt_id)),
+            "branch_labels",
+            &branch.prompt_id,
+        )
+        .await?;
+    }
+
+    for scroll in bundle.scrolls.iter() {
+        if known_scrolls.contains(&scroll.scroll_id) {
+            report.scrolls_skipped += 1;
+            continue;
+        }
+        let result = store_scroll(
+            &mut *tx,
+            &Scroll {
+                project_id: project.project_id.clone(),
+                ..scroll.clone()
+            },
+        )
+        .await?;
+        if result.is_reused() {
+            report.scrolls_skipped += 1;
+        } else {
+            report.scrolls_added += 1;
+        }
+    }
+
+    tx.commit().await.map_err(|err| {
+        log_error(&format!(
+            "FAILED :: COMMIT import of project_id: [{}]",
+            project.project_id
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to import bundle of project {}. Reason: {}",
+            project.project_id, err
+        ))
+    })?;
+
+    log_info(&format!(
+        "Imported bundle of project {}: {} prompts added, {} skipped, {} scrolls added, {} skipped",
+        project.project_id,
+        report.prompts_added,
+        report.prompts_skipped,
+        report.scrolls_added,
+        report.scrolls_skipped
+    ));
+    Ok(report)
+}
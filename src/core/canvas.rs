@@ -1,4 +1,4 @@
-use crate::utils::logger::{log_error, log_info};
+use crate::utils::logger::log_error;
 use crate::{
     core::prompt::prompt_chain,
     utils::structs::{Project, Prompt},
@@ -40,7 +40,7 @@ pub fn chain_into_canvas(
         // Generate the prompt chain
         let mut chain = prompt_chain(prompts, prompt);
         chain.reverse(); // Reverse the chain order if needed
-        
+
         // Write each prompt and its output to the file manually (without writeln)
         for prompt in chain {
             let prompt_text = format!("# PROMPT {}\n{}\n", prompt.prompt_id, prompt.content);
@@ -0,0 +1,340 @@
+use crate::{
+    core::prompt::prompt_chain,
+    utils::{
+        error::AppError,
+        logger::{log_error, log_info},
+        structs::{Project, Prompt, Scroll},
+    },
+};
+use anyhow::Result;
+use chrono::Local;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+/// Output formats supported by the exporter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [
+        ExportFormat::Markdown,
+        ExportFormat::Html,
+        ExportFormat::Json,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "Markdown",
+            ExportFormat::Html => "HTML",
+            ExportFormat::Json => "JSON",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// What part of a project gets exported.
+///
+/// - `Branch`: the chain from the root down to the given leaf prompt.
+/// - `Tree`: every prompt of the project, nested by `prev_prompt_id`.
+/// - `Project`: the whole tree plus the project's scrolls.
+#[derive(Clone, Copy, Debug)]
+pub enum ExportScope<'a> {
+    Branch(&'a Prompt),
+    Tree,
+    Project,
+}
+
+impl ExportScope<'_> {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportScope::Branch(_) => "branch",
+            ExportScope::Tree => "tree",
+            ExportScope::Project => "project",
+        }
+    }
+}
+
+/// A prompt together with its descendants, as written to the JSON export.
+#[derive(Debug, Serialize)]
+pub struct ExportNode {
+    #[serde(flatten)]
+    pub prompt: Prompt,
+    pub children: Vec<ExportNode>,
+}
+
+/// The structured document written by the JSON exporter.
+#[derive(Debug, Serialize)]
+pub struct ExportDocument {
+    pub scope: String,
+    pub exported_at: String,
+    pub project: Project,
+    pub scrolls: Vec<Scroll>,
+    pub prompts: Vec<ExportNode>,
+}
+
+/// Prompts whose parent is not part of `prompts` (including those chained
+/// directly to the project) are the roots of the exported tree.
+fn root_prompts(prompts: &[Prompt]) -> Vec<&Prompt> {
+    let ids: HashSet<&str> = prompts.iter().map(|p| p.prompt_id.as_str()).collect();
+    prompts
+        .iter()
+        .filter(|p| !ids.contains(p.prev_prompt_id.as_str()))
+        .collect()
+}
+
+fn build_node(prompts: &[Prompt], prompt: &Prompt, visited: &mut HashSet<String>) -> ExportNode {
+    visited.insert(prompt.prompt_id.clone());
+    let children = prompts
+        .iter()
+        .filter(|p| p.prev_prompt_id == prompt.prompt_id && !visited.contains(&p.prompt_id))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|p| build_node(prompts, p, visited))
+        .collect();
+
+    ExportNode {
+        prompt: prompt.clone(),
+        children,
+    }
+}
+
+/// Builds the nodes to export for the given scope.
+///
+/// A branch is returned as a single path, from the first prompt down to the leaf.
+pub fn export_nodes(prompts: &[Prompt], scope: ExportScope) -> Vec<ExportNode> {
+    match scope {
+        ExportScope::Branch(leaf) => {
+            let chain = prompt_chain(prompts, leaf);
+            let mut node: Option<ExportNode> = None;
+            for prompt in chain {
+                node = Some(ExportNode {
+                    prompt,
+                    children: node.into_iter().collect(),
+                });
+            }
+            node.into_iter().collect()
+        }
+        ExportScope::Tree | ExportScope::Project => {
+            let mut visited = HashSet::new();
+            root_prompts(prompts)
+                .into_iter()
+                .map(|p| build_node(prompts, p, &mut visited))
+                .collect()
+        }
+    }
+}
+
+fn markdown_node(md: &mut String, node: &ExportNode, label: &str) {
+    let depth = label.matches('.').count();
+    let heading = "#".repeat((depth + 2).min(6));
+    md.push_str(&format!("{} {} Prompt\n\n", heading, label));
+    md.push_str(&format!("{}\n\n", node.prompt.content.trim()));
+    md.push_str(&format!("**Output**\n\n{}\n\n", node.prompt.output.trim()));
+
+    for (idx, child) in node.children.iter().enumerate() {
+        markdown_node(md, child, &format!("{}.{}", label, idx + 1));
+    }
+}
+
+/// Renders a self-contained Markdown document.
+pub fn export_markdown(
+    project: &Project,
+    prompts: &[Prompt],
+    scrolls: &[Scroll],
+    scope: ExportScope,
+) -> String {
+    let mut md = format!("# {}\n\n", project.name());
+    md.push_str(&format!(
+        "_Exported {} from `{}` on {}_\n\n",
+        scope.name(),
+        project.project_path,
+        Local::now().format("%Y-%m-%d %H:%M:%S")
+    ));
+
+    if matches!(scope, ExportScope::Project) && !scrolls.is_empty() {
+        md.push_str("## Scrolls\n\n");
+        for scroll in scrolls {
+            md.push_str(&format!(
+                "### {}\n\n````\n{}\n````\n\n",
+                scroll.scroll_path,
+                scroll.content.trim_end()
+            ));
+        }
+    }
+
+    for (idx, node) in export_nodes(prompts, scope).iter().enumerate() {
+        markdown_node(&mut md, node, &(idx + 1).to_string());
+    }
+
+    md
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn html_node(html: &mut String, node: &ExportNode) {
+    let summary = node.prompt.content.lines().next().unwrap_or("").trim();
+    html.push_str(&format!(
+        "<details open><summary>{} <small>({} replies)</small></summary>\n",
+        escape_html(summary),
+        node.children.len()
+    ));
+    html.push_str(&format!(
+        "<div class=\"prompt\"><pre>{}</pre></div>\n<div class=\"output\"><pre>{}</pre></div>\n",
+        escape_html(&node.prompt.content),
+        escape_html(&node.prompt.output)
+    ));
+    for child in node.children.iter() {
+        html_node(html, child);
+    }
+    html.push_str("</details>\n");
+}
+
+/// Renders a static HTML page in which every branch is a collapsible `<details>` block.
+pub fn export_html(
+    project: &Project,
+    prompts: &[Prompt],
+    scrolls: &[Scroll],
+    scope: ExportScope,
+) -> String {
+    let title = escape_html(project.name());
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>\n\
+         body {{ font-family: sans-serif; max-width: 60em; margin: auto; }}\n\
+         details {{ margin-left: 1em; border-left: 2px solid #ccc; padding-left: 0.5em; }}\n\
+         pre {{ white-space: pre-wrap; }}\n\
+         .prompt {{ background: #eef; }}\n\
+         .output {{ background: #efe; }}\n\
+         </style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        title, title
+    );
+    html.push_str(&format!(
+        "<p><em>Exported {} from <code>{}</code> on {}</em></p>\n",
+        scope.name(),
+        escape_html(&project.project_path),
+        Local::now().format("%Y-%m-%d %H:%M:%S")
+    ));
+
+    if matches!(scope, ExportScope::Project) && !scrolls.is_empty() {
+        html.push_str("<h2>Scrolls</h2>\n");
+        for scroll in scrolls {
+            html.push_str(&format!(
+                "<details><summary>{}</summary><pre>{}</pre></details>\n",
+                escape_html(&scroll.scroll_path),
+                escape_html(&scroll.content)
+            ));
+        }
+    }
+
+    html.push_str("<h2>Prompts</h2>\n");
+    for node in export_nodes(prompts, scope).iter() {
+        html_node(&mut html, node);
+    }
+    html.push_str("</body>\n</html>\n");
+
+    html
+}
+
+/// Renders the structured JSON document.
+pub fn export_json(
+    project: &Project,
+    prompts: &[Prompt],
+    scrolls: &[Scroll],
+    scope: ExportScope,
+) -> Result<String> {
+    let document = ExportDocument {
+        scope: scope.name().to_string(),
+        exported_at: Local::now().to_rfc3339(),
+        project: project.clone(),
+        scrolls: match scope {
+            ExportScope::Project => scrolls.to_vec(),
+            _ => vec![],
+        },
+        prompts: export_nodes(prompts, scope),
+    };
+
+    let json = serde_json::to_string_pretty(&document).map_err(|err| {
+        log_error(&format!("Failed to serialize export. Reason: {}", err));
+        AppError::ParseError(format!("Failed to serialize export. Reason: {}", err))
+    })?;
+
+    Ok(json)
+}
+
+/// Renders the export in the requested format.
+pub fn render_export(
+    project: &Project,
+    prompts: &[Prompt],
+    scrolls: &[Scroll],
+    scope: ExportScope,
+    format: ExportFormat,
+) -> Result<String> {
+    match format {
+        ExportFormat::Markdown => Ok(export_markdown(project, prompts, scrolls, scope)),
+        ExportFormat::Html => Ok(export_html(project, prompts, scrolls, scope)),
+        ExportFormat::Json => export_json(project, prompts, scrolls, scope),
+    }
+}
+
+/// Renders the export and writes it next to `legatio.md` in the project directory.
+///
+/// The file is named `legatio_<scope>_<timestamp>.<ext>` so that previous exports
+/// are never overwritten.
+///
+/// # Returns:
+/// - `Ok(PathBuf)` with the path of the written file.
+pub fn write_export(
+    project: &Project,
+    prompts: &[Prompt],
+    scrolls: &[Scroll],
+    scope: ExportScope,
+    format: ExportFormat,
+) -> Result<PathBuf> {
+    let content = render_export(project, prompts, scrolls, scope, format)?;
+
+    let file_name = format!(
+        "legatio_{}_{}.{}",
+        scope.name(),
+        Local::now().format("%Y%m%d_%H%M%S"),
+        format.extension()
+    );
+    let file_path = PathBuf::from(&project.project_path).join(file_name);
+
+    fs::write(&file_path, content).map_err(|err| {
+        log_error(&format!(
+            "Failed to write export {}: {}",
+            file_path.to_string_lossy(),
+            err
+        ));
+        AppError::FileError(format!(
+            "Failed to write export {}: {}",
+            file_path.to_string_lossy(),
+            err
+        ))
+    })?;
+
+    log_info(&format!(
+        "Exported {} to {}",
+        scope.name(),
+        file_path.to_string_lossy()
+    ));
+    Ok(file_path)
+}
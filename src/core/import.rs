@@ -0,0 +1,329 @@
+use crate::{
+    core::prompt::store_prompts,
+    utils::{
+        error::AppError,
+        logger::{log_error, log_info},
+        structs::{Project, Prompt},
+    },
+};
+use anyhow::Result;
+use serde_json::Value;
+use sqlx::sqlite::SqlitePool;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+/// Author of a transcript message. Everything that is neither the user nor
+/// the assistant (system prompts, tool calls, ...) is skipped on import.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    User,
+    Assistant,
+    Other,
+}
+
+impl Role {
+    fn from_name(name: &str) -> Role {
+        match name.to_lowercase().as_str() {
+            "user" | "human" => Role::User,
+            "assistant" | "model" | "ai" | "bot" | "gpt" => Role::Assistant,
+            _ => Role::Other,
+        }
+    }
+}
+
+/// A single message of a transcript, normalized from the supported formats.
+#[derive(Clone, Debug)]
+pub struct TranscriptMessage {
+    pub id: String,
+    pub parent: Option<String>,
+    pub role: Role,
+    pub content: String,
+}
+
+/// Extracts the text of a message content, which is either a plain string,
+/// a list of parts (`["..."]` or `[{"text": "..."}]`) or an object with `parts`.
+fn content_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.to_owned(),
+        Value::Array(parts) => parts
+            .iter()
+            .map(content_text)
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Object(obj) => {
+            if let Some(text) = obj.get("text") {
+                content_text(text)
+            } else if let Some(parts) = obj.get("parts") {
+                content_text(parts)
+            } else {
+                String::new()
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+fn str_field<'a>(value: &'a Value, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .find_map(|k| value.get(*k).and_then(|v| v.as_str()))
+}
+
+/// Keys naming the parent of a message in a plain list.
+const PARENT_KEYS: &[&str] = &["parent", "parent_id", "parentId", "parent_message_uuid"];
+
+/// Parses a message of a plain list. Messages without a parent key are chained
+/// to the previous message of the list, whether or not they have an `id`; a
+/// parent key set to `null` starts a new root.
+fn parse_list(messages: &[Value]) -> Vec<TranscriptMessage> {
+    let mut parsed: Vec<TranscriptMessage> = vec![];
+    for (idx, message) in messages.iter().enumerate() {
+        let role = str_field(message, &["role", "author", "from", "sender"]).unwrap_or("");
+        let id = str_field(message, &["id", "uuid"])
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| format!("message-{}", idx));
+        let parent = match PARENT_KEYS.iter().find_map(|k| message.get(*k)) {
+            Some(parent) => parent.as_str().map(|s| s.to_string()),
+            None => parsed.last().map(|m| m.id.clone()),
+        };
+
+        parsed.push(TranscriptMessage {
+            id,
+            parent,
+            role: Role::from_name(role),
+            content: content_text(
+                message
+                    .get("content")
+                    .or_else(|| message.get("text"))
+                    .unwrap_or(&Value::Null),
+            ),
+        });
+    }
+    parsed
+}
+
+/// Parses the `mapping` tree used by ChatGPT conversation exports.
+fn parse_mapping(mapping: &serde_json::Map<String, Value>) -> Vec<TranscriptMessage> {
+    mapping
+        .iter()
+        .map(|(id, node)| {
+            let message = node.get("message").unwrap_or(&Value::Null);
+            let role = message
+                .get("author")
+                .and_then(|a| a.get("role"))
+                .and_then(|r| r.as_str())
+                .unwrap_or("");
+            TranscriptMessage {
+                id: id.to_owned(),
+                parent: node
+                    .get("parent")
+                    .and_then(|p| p.as_str())
+                    .map(|s| s.to_string()),
+                role: Role::from_name(role),
+                content: content_text(message.get("content").unwrap_or(&Value::Null)),
+            }
+        })
+        .collect()
+}
+
+/// Parses a JSON transcript into a list of messages.
+///
+/// Supported layouts:
+/// - A list of `{"role", "content"}` messages, read as one linear conversation.
+/// - The same list wrapped in an object under `messages` or `chat_messages`
+///   (Claude exports, whose messages carry `uuid`, `sender` and `parent_message_uuid`).
+/// - Messages carrying `id` and `parent` fields, read as a conversation tree.
+/// - A `mapping` object of nodes with `parent` links (ChatGPT exports).
+///
+/// # Returns:
+/// - `Ok(Vec<TranscriptMessage>)` with the normalized messages.
+/// - `AppError::ParseError` if the document matches none of the layouts.
+pub fn parse_transcript(json: &str) -> Result<Vec<TranscriptMessage>> {
+    let value: Value = serde_json::from_str(json).map_err(|err| {
+        log_error(&format!("Failed to parse transcript. Reason: {}", err));
+        AppError::ParseError(format!("Failed to parse transcript. Reason: {}", err))
+    })?;
+
+    let messages = if let Some(list) = value.as_array() {
+        parse_list(list)
+    } else if let Some(mapping) = value.get("mapping").and_then(|m| m.as_object()) {
+        parse_mapping(mapping)
+    } else if let Some(list) = ["messages", "chat_messages"]
+        .iter()
+        .find_map(|k| value.get(*k).and_then(|m| m.as_array()))
+    {
+        parse_list(list)
+    } else {
+        return Err(AppError::ParseError(String::from(
+            "Transcript is neither a list of messages nor a conversation tree",
+        ))
+        .into());
+    };
+
+    Ok(messages)
+}
+
+/// Converts transcript messages into prompts with the correct `prev_prompt_id` links.
+///
+/// Every user message is paired with each assistant reply below it, so a user
+/// message answered twice becomes two sibling prompts. Unanswered user messages
+/// become prompts with an empty output. Messages of any other role are skipped.
+///
+/// # Parameters:
+/// - `messages`: The normalized transcript messages.
+/// - `project_id`: The project receiving the prompts.
+/// - `root_id`: The `prev_prompt_id` given to the first prompts of the conversation.
+pub fn transcript_to_prompts(
+    messages: &[TranscriptMessage],
+    project_id: &str,
+    root_id: &str,
+) -> Vec<Prompt> {
+    let by_id: HashMap<&str, &TranscriptMessage> =
+        messages.iter().map(|m| (m.id.as_str(), m)).collect();
+    let mut children: HashMap<&str, Vec<&TranscriptMessage>> = HashMap::new();
+    let mut roots: Vec<&TranscriptMessage> = vec![];
+    for message in messages {
+        match message.parent.as_deref() {
+            Some(parent) if by_id.contains_key(parent) => {
+                children.entry(parent).or_default().push(message)
+            }
+            _ => roots.push(message),
+        }
+    }
+
+    let mut prompts: Vec<Prompt> = vec![];
+    let mut visited: HashSet<&str> = HashSet::new();
+    // (message, prompt id the message hangs under)
+    let mut stack: Vec<(&TranscriptMessage, String)> = roots
+        .into_iter()
+        .rev()
+        .map(|m| (m, root_id.to_string()))
+        .collect();
+
+    while let Some((message, parent_prompt)) = stack.pop() {
+        if !visited.insert(message.id.as_str()) {
+            continue;
+        }
+        let kids = children
+            .get(message.id.as_str())
+            .cloned()
+            .unwrap_or_default();
+
+        match message.role {
+            Role::User => {
+                let replies: Vec<&TranscriptMessage> = kids
+                    .iter()
+                    .filter(|m| m.role == Role::Assistant)
+                    .cloned()
+                    .collect();
+
+                if replies.is_empty() {
+                    let prompt = Prompt::new(project_id, &message.content, "", &parent_prompt);
+                    for kid in kids.iter().rev() {
+                        stack.push((kid, prompt.prompt_id.clone()));
+                    }
+                    prompts.push(prompt);
+                } else {
+                    for reply in replies.iter().rev() {
+                        visited.insert(reply.id.as_str());
+                        let prompt = Prompt::new(
+                            project_id,
+                            &message.content,
+                            &reply.content,
+                            &parent_prompt,
+                        );
+                        if let Some(grand_kids) = children.get(reply.id.as_str()) {
+                            for kid in grand_kids.iter().rev() {
+                                stack.push((kid, prompt.prompt_id.clone()));
+                            }
+                        }
+                        prompts.push(prompt);
+                    }
+                    // Non-assistant children of the user message stay on its first reply
+                    let first = prompts[prompts.len() - 1].prompt_id.clone();
+                    for kid in kids.iter().rev().filter(|m| m.role != Role::Assistant) {
+                        stack.push((kid, first.clone()));
+                    }
+                }
+            }
+            Role::Assistant => {
+                // Reply without a question, e.g. a greeting opening the conversation
+                let prompt = Prompt::new(project_id, "", &message.content, &parent_prompt);
+                for kid in kids.iter().rev() {
+                    stack.push((kid, prompt.prompt_id.clone()));
+                }
+                prompts.push(prompt);
+            }
+            Role::Other => {
+                for kid in kids.iter().rev() {
+                    stack.push((kid, parent_prompt.clone()));
+                }
+            }
+        }
+    }
+
+    prompts
+}
+
+/// Imports a JSON transcript file into a project.
+///
+/// The conversation is attached under `parent` when given, otherwise it becomes
+/// a new branch starting at the project root. Either all prompts are stored or none.
+/// Messages already stored under the same parent are reused, so importing the same
+/// transcript twice does not duplicate it.
+///
+/// # Returns:
+/// - `Ok(Vec<Prompt>)` with the stored prompts and their final ids, parents before children.
+/// - `AppError::ParseError` if no message of the user or the assistant was recognized.
+pub async fn import_transcript(
+    pool: &SqlitePool,
+    project: &Project,
+    file_path: &str,
+    parent: Option<&Prompt>,
+) -> Result<Vec<Prompt>> {
+    let json = fs::read_to_string(file_path).map_err(|err| {
+        log_error(&format!("Failed to read transcript {}: {}", file_path, err));
+        AppError::FileError(format!("Failed to read transcript {}: {}", file_path, err))
+    })?;
+
+    let messages = parse_transcript(&json)?;
+    if messages.iter().all(|m| m.role == Role::Other) {
+        log_error(&format!(
+            "No user or assistant message found in transcript {}",
+            file_path
+        ));
+        return Err(AppError::ParseError(format!(
+            "No user or assistant message found in transcript {}",
+            file_path
+        ))
+        .into());
+    }
+    let root_id = parent.map_or(project.project_id.as_str(), |p| p.prompt_id.as_str());
+    let prompts = transcript_to_prompts(&messages, &project.project_id, root_id);
+
+    let mut tx = pool.begin().await.map_err(|err| {
+        log_error(&format!("Failed to start transaction. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to start transaction. Reason: {}", err))
+    })?;
+    let stored = store_prompts(&mut *tx, &prompts).await?;
+    tx.commit().await.map_err(|err| {
+        log_error(&format!(
+            "Failed to import transcript {}: {}",
+            file_path, err
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to import transcript {}: {}",
+            file_path, err
+        ))
+    })?;
+
+    let reused = stored.iter().filter(|(_, r)| r.is_reused()).count();
+    log_info(&format!(
+        "Imported {} prompts ({} already stored) from {} into project {}",
+        stored.len(),
+        reused,
+        file_path,
+        project.project_id
+    ));
+    Ok(stored.into_iter().map(|(prompt, _)| prompt).collect())
+}
@@ -0,0 +1,509 @@
+use crate::{
+    core::scroll::resolve_scroll_path,
+    utils::{
+        db_utils::{count_quarantined, table_exists},
+        error::AppError,
+        logger::{log_error, log_info},
+    },
+};
+use anyhow::Result;
+use sqlx::{Acquire, FromRow, Sqlite};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use uuid::Uuid;
+
+/// A problem found by `check_integrity`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IntegrityIssue {
+    /// A prompt whose parent is neither its project nor a prompt of that project.
+    /// It is not reachable from the prompt tree.
+    OrphanPrompt {
+        prompt_id: String,
+        project_id: String,
+        missing_parent: String,
+    },
+    /// Prompts whose parents link back to one another.
+    PromptCycle {
+        project_id: String,
+        prompt_ids: Vec<String>,
+    },
+    /// Several prompt rows sharing the same id.
+    DuplicatePromptId { prompt_id: String, count: usize },
+    /// Several scroll rows sharing the same id.
+    DuplicateScrollId { scroll_id: String, count: usize },
+    /// A scroll whose file cannot be found.
+    MissingScrollFile {
+        scroll_id: String,
+        project_id: String,
+        path: String,
+    },
+    /// A prompt of a project that no longer exists.
+    PromptOfDeletedProject {
+        prompt_id: String,
+        project_id: String,
+    },
+    /// A scroll of a project that no longer exists.
+    ScrollOfDeletedProject {
+        scroll_id: String,
+        project_id: String,
+    },
+    /// Rows of deleted projects or repeating an id, set aside by the schema
+    /// upgrade adding foreign keys.
+    QuarantinedRows { table: String, count: usize },
+    /// Tags, stars, branch labels or merge parents of a prompt that is neither
+    /// stored nor in the trash.
+    DanglingMark { table: String, prompt_id: String },
+}
+
+/// Tables marking prompts, with the columns holding prompt ids.
+const MARK_TABLES: &[(&str, &[&str])] = &[
+    ("prompt_tags", &["prompt_id"]),
+    ("prompt_stars", &["prompt_id"]),
+    ("branch_labels", &["prompt_id"]),
+    ("prompt_parents", &["prompt_id", "parent_id"]),
+];
+
+impl IntegrityIssue {
+    /// Whether `repair_integrity` fixes this issue. Missing scroll files are only
+    /// reported, as the project may just need to be relocated, and quarantined
+    /// rows are left for the user to recover.
+    pub fn is_repairable(&self) -> bool {
+        !matches!(
+            self,
+            IntegrityIssue::MissingScrollFile { .. } | IntegrityIssue::QuarantinedRows { .. }
+        )
+    }
+}
+
+impl fmt::Display for IntegrityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityIssue::OrphanPrompt {
+                prompt_id,
+                project_id,
+                missing_parent,
+            } => write!(
+                f,
+                "Orphan prompt {} in project {}: parent {} does not exist",
+                prompt_id, project_id, missing_parent
+            ),
+            IntegrityIssue::PromptCycle {
+                project_id,
+                prompt_ids,
+            } => write!(
+                f,
+                "Cycle of prompts in project {}: {}",
+                project_id,
+                prompt_ids.join(" -> ")
+            ),
+            IntegrityIssue::DuplicatePromptId { prompt_id, count } => {
+                write!(f, "Prompt id {} is used by {} rows", prompt_id, count)
+            }
+            IntegrityIssue::DuplicateScrollId { scroll_id, count } => {
+                write!(f, "Scroll id {} is used by {} rows", scroll_id, count)
+            }
+            IntegrityIssue::MissingScrollFile {
+                scroll_id,
+                project_id,
+                path,
+            } => write!(
+                f,
+                "Scroll {} of project {} points at missing file {}",
+                scroll_id, project_id, path
+            ),
+            IntegrityIssue::PromptOfDeletedProject {
+                prompt_id,
+                project_id,
+            } => write!(
+                f,
+                "Prompt {} belongs to deleted project {}",
+                prompt_id, project_id
+            ),
+            IntegrityIssue::ScrollOfDeletedProject {
+                scroll_id,
+                project_id,
+            } => write!(
+                f,
+                "Scroll {} belongs to deleted project {}",
+                scroll_id, project_id
+            ),
+            IntegrityIssue::QuarantinedRows { table, count } => write!(
+                f,
+                "{} row(s) of deleted projects or with duplicate ids were set aside in table {} by a schema upgrade",
+                count, table
+            ),
+            IntegrityIssue::DanglingMark { table, prompt_id } => write!(
+                f,
+                "Rows of {} point at missing prompt {}",
+                table, prompt_id
+            ),
+        }
+    }
+}
+
+/// Number of issues fixed by `repair_integrity`, by kind.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RepairSummary {
+    pub rerooted: usize,
+    pub renamed: usize,
+    pub deleted: usize,
+}
+
+#[derive(FromRow)]
+struct PromptLink {
+    prompt_id: String,
+    project_id: String,
+    prev_prompt_id: String,
+}
+
+#[derive(FromRow)]
+struct ScrollLink {
+    scroll_id: String,
+    project_id: String,
+    scroll_path: String,
+}
+
+/// Finds the cycles of a parent map, each reported once starting from its smallest id.
+fn find_cycles(parents: &HashMap<&str, &str>) -> Vec<Vec<String>> {
+    let mut cycles = vec![];
+    let mut done: HashSet<&str> = HashSet::new();
+
+    let mut starts: Vec<&str> = parents.keys().copied().collect();
+    starts.sort();
+    for start in starts {
+        let mut path: Vec<&str> = vec![];
+        let mut on_path: HashSet<&str> = HashSet::new();
+        let mut current = Some(start);
+
+        while let Some(id) = current {
+            if done.contains(id) {
+                break;
+            }
+            if on_path.contains(id) {
+                let from = path.iter().position(|p| *p == id).unwrap();
+                let mut cycle: Vec<String> = path[from..].iter().map(|s| s.to_string()).collect();
+                let min = (0..cycle.len()).min_by_key(|i| &cycle[*i]).unwrap();
+                cycle.rotate_left(min);
+                cycles.push(cycle);
+                break;
+            }
+            on_path.insert(id);
+            path.push(id);
+            current = parents.get(id).copied();
+        }
+        done.extend(path);
+    }
+
+    cycles
+}
+
+/// Checks the prompt graph and the scrolls of every project.
+///
+/// # Returns:
+/// - `Ok(Vec<IntegrityIssue>)` with every problem found; empty for a healthy database.
+pub async fn check_integrity<'a, A>(conn: A) -> Result<Vec<IntegrityIssue>>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut conn = conn.acquire().await.map_err(|err| {
+        log_error(&format!("Failed to acquire connection. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to acquire connection. Reason: {}", err))
+    })?;
+
+    let db_error = |err: sqlx::Error| {
+        log_error(&format!("Failed to check integrity. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to check integrity. Reason: {}", err))
+    };
+    let projects: HashMap<String, String> =
+        sqlx::query_as::<_, (String, String)>("SELECT project_id, project_path FROM projects;")
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .collect();
+    let prompts = sqlx::query_as::<_, PromptLink>(
+        "SELECT prompt_id, project_id, prev_prompt_id FROM prompts ORDER BY rowid;",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_error)?;
+    let scrolls = sqlx::query_as::<_, ScrollLink>(
+        "SELECT scroll_id, project_id, scroll_path FROM scrolls ORDER BY rowid;",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_error)?;
+
+    let mut issues = vec![];
+
+    // Rows the schema upgrade could not keep
+    for (table, count) in count_quarantined(&mut conn).await? {
+        issues.push(IntegrityIssue::QuarantinedRows {
+            table: table.to_string(),
+            count,
+        });
+    }
+
+    // Duplicate ids
+    let mut prompt_counts: HashMap<&str, usize> = HashMap::new();
+    for p in prompts.iter() {
+        *prompt_counts.entry(&p.prompt_id).or_default() += 1;
+    }
+    let mut scroll_counts: HashMap<&str, usize> = HashMap::new();
+    for s in scrolls.iter() {
+        *scroll_counts.entry(&s.scroll_id).or_default() += 1;
+    }
+    let mut seen: HashSet<&str> = HashSet::new();
+    for p in prompts.iter() {
+        let count = prompt_counts[p.prompt_id.as_str()];
+        if count > 1 && seen.insert(&p.prompt_id) {
+            issues.push(IntegrityIssue::DuplicatePromptId {
+                prompt_id: p.prompt_id.clone(),
+                count,
+            });
+        }
+    }
+    let mut seen: HashSet<&str> = HashSet::new();
+    for s in scrolls.iter() {
+        let count = scroll_counts[s.scroll_id.as_str()];
+        if count > 1 && seen.insert(&s.scroll_id) {
+            issues.push(IntegrityIssue::DuplicateScrollId {
+                scroll_id: s.scroll_id.clone(),
+                count,
+            });
+        }
+    }
+
+    // Rows of deleted projects
+    for p in prompts
+        .iter()
+        .filter(|p| !projects.contains_key(&p.project_id))
+    {
+        issues.push(IntegrityIssue::PromptOfDeletedProject {
+            prompt_id: p.prompt_id.clone(),
+            project_id: p.project_id.clone(),
+        });
+    }
+    for s in scrolls.iter() {
+        match projects.get(&s.project_id) {
+            None => issues.push(IntegrityIssue::ScrollOfDeletedProject {
+                scroll_id: s.scroll_id.clone(),
+                project_id: s.project_id.clone(),
+            }),
+            Some(project_path) => {
+                if !resolve_scroll_path(project_path, &s.scroll_path).is_file() {
+                    issues.push(IntegrityIssue::MissingScrollFile {
+                        scroll_id: s.scroll_id.clone(),
+                        project_id: s.project_id.clone(),
+                        path: s.scroll_path.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    // Orphans and cycles, per project
+    let mut by_project: HashMap<&str, Vec<&PromptLink>> = HashMap::new();
+    for p in prompts
+        .iter()
+        .filter(|p| projects.contains_key(&p.project_id))
+    {
+        by_project.entry(&p.project_id).or_default().push(p);
+    }
+    let mut project_ids: Vec<&str> = by_project.keys().copied().collect();
+    project_ids.sort();
+    for project_id in project_ids {
+        let links = &by_project[project_id];
+        let ids: HashSet<&str> = links.iter().map(|p| p.prompt_id.as_str()).collect();
+        let mut parents: HashMap<&str, &str> = HashMap::new();
+        for p in links.iter() {
+            if p.prev_prompt_id == p.project_id {
+                continue;
+            }
+            if ids.contains(p.prev_prompt_id.as_str()) {
+                parents.insert(&p.prompt_id, &p.prev_prompt_id);
+            } else {
+                issues.push(IntegrityIssue::OrphanPrompt {
+                    prompt_id: p.prompt_id.clone(),
+                    project_id: p.project_id.clone(),
+                    missing_parent: p.prev_prompt_id.clone(),
+                });
+            }
+        }
+        for cycle in find_cycles(&parents) {
+            issues.push(IntegrityIssue::PromptCycle {
+                project_id: project_id.to_string(),
+                prompt_ids: cycle,
+            });
+        }
+    }
+
+    // Marks of missing prompts; trashed prompts keep theirs until purged
+    let mut known: HashSet<String> = prompts.iter().map(|p| p.prompt_id.clone()).collect();
+    if table_exists(&mut conn, "prompt_trash").await? {
+        let trashed: Vec<String> = sqlx::query_scalar("SELECT prompt_id FROM prompt_trash;")
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(db_error)?;
+        known.extend(trashed);
+    }
+    for (table, columns) in MARK_TABLES.iter() {
+        if !table_exists(&mut conn, table).await? {
+            continue;
+        }
+        let mut missing = BTreeSet::new();
+        for column in columns.iter() {
+            let ids: Vec<String> =
+                sqlx::query_scalar(&format!("SELECT {} FROM {};", column, table))
+                    .fetch_all(&mut *conn)
+                    .await
+                    .map_err(db_error)?;
+            missing.extend(ids.into_iter().filter(|id| !known.contains(id)));
+        }
+        for prompt_id in missing {
+            issues.push(IntegrityIssue::DanglingMark {
+                table: table.to_string(),
+                prompt_id,
+            });
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Repairs the issues found by `check_integrity`, in one transaction.
+///
+/// - Orphan prompts are re-rooted at their project.
+/// - Cycles are broken by re-rooting their first prompt.
+/// - Duplicate ids are kept by the first row; the others get a new id.
+/// - Prompts and scrolls of deleted projects are deleted.
+/// - Marks of missing prompts are deleted.
+/// - Missing scroll files are left untouched.
+pub async fn repair_integrity<'a, A>(conn: A, issues: &[IntegrityIssue]) -> Result<RepairSummary>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut tx = conn.begin().await.map_err(|err| {
+        log_error(&format!("Failed to start transaction. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to start transaction. Reason: {}", err))
+    })?;
+    let db_error = |err: sqlx::Error| {
+        log_error(&format!("Failed to repair integrity. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to repair integrity. Reason: {}", err))
+    };
+
+    let mut summary = RepairSummary::default();
+    for issue in issues.iter() {
+        match issue {
+            IntegrityIssue::OrphanPrompt {
+                prompt_id,
+                project_id,
+                ..
+            } => {
+                sqlx::query("UPDATE prompts SET prev_prompt_id = $1 WHERE prompt_id = $2")
+                    .bind(project_id)
+                    .bind(prompt_id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(db_error)?;
+                summary.rerooted += 1;
+            }
+            IntegrityIssue::PromptCycle {
+                project_id,
+                prompt_ids,
+            } => {
+                sqlx::query("UPDATE prompts SET prev_prompt_id = $1 WHERE prompt_id = $2")
+                    .bind(project_id)
+                    .bind(&prompt_ids[0])
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(db_error)?;
+                summary.rerooted += 1;
+            }
+            IntegrityIssue::DuplicatePromptId { prompt_id, .. } => {
+                let rows: Vec<i64> = sqlx::query_scalar(
+                    "SELECT rowid FROM prompts WHERE prompt_id = $1 ORDER BY rowid;",
+                )
+                .bind(prompt_id)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(db_error)?;
+                for rowid in rows.iter().skip(1) {
+                    sqlx::query("UPDATE prompts SET prompt_id = $1 WHERE rowid = $2")
+                        .bind(Uuid::new_v4().to_string())
+                        .bind(rowid)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(db_error)?;
+                    summary.renamed += 1;
+                }
+            }
+            IntegrityIssue::DuplicateScrollId { scroll_id, .. } => {
+                let rows: Vec<i64> = sqlx::query_scalar(
+                    "SELECT rowid FROM scrolls WHERE scroll_id = $1 ORDER BY rowid;",
+                )
+                .bind(scroll_id)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(db_error)?;
+                for rowid in rows.iter().skip(1) {
+                    sqlx::query("UPDATE scrolls SET scroll_id = $1 WHERE rowid = $2")
+                        .bind(Uuid::new_v4().to_string())
+                        .bind(rowid)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(db_error)?;
+                    summary.renamed += 1;
+                }
+            }
+            IntegrityIssue::PromptOfDeletedProject {
+                prompt_id,
+                project_id,
+            } => {
+                sqlx::query("DELETE FROM prompts WHERE prompt_id = $1 AND project_id = $2")
+                    .bind(prompt_id)
+                    .bind(project_id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(db_error)?;
+                summary.deleted += 1;
+            }
+            IntegrityIssue::ScrollOfDeletedProject {
+                scroll_id,
+                project_id,
+            } => {
+                sqlx::query("DELETE FROM scrolls WHERE scroll_id = $1 AND project_id = $2")
+                    .bind(scroll_id)
+                    .bind(project_id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(db_error)?;
+                summary.deleted += 1;
+            }
+            IntegrityIssue::DanglingMark { table, prompt_id } => {
+                // Only the tables of marks are touched, whatever the issue names
+                let Some((table, columns)) = MARK_TABLES.iter().find(|(name, _)| name == table)
+                else {
+                    continue;
+                };
+                for column in columns.iter() {
+                    sqlx::query(&format!("DELETE FROM {} WHERE {} = $1", table, column))
+                        .bind(prompt_id)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(db_error)?;
+                }
+                summary.deleted += 1;
+            }
+            IntegrityIssue::MissingScrollFile { .. } | IntegrityIssue::QuarantinedRows { .. } => {}
+        }
+    }
+
+    tx.commit().await.map_err(db_error)?;
+    log_info(&format!(
+        "Repaired database: {} prompts re-rooted, {} ids renamed, {} rows deleted",
+        summary.rerooted, summary.renamed, summary.deleted
+    ));
+
+    Ok(summary)
+}
@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use sqlx::{Acquire, Sqlite};
+
+use crate::{
+    core::{prompt::store_prompt, tag::ProjectMarks},
+    utils::{
+        error::AppError,
+        logger::log_error,
+        structs::{Prompt, StoreResult},
+    },
+};
+
+/// How the branches of a merge are handed to the model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeMode {
+    /// The first branch is the conversation, the second one is quoted in full.
+    Full,
+    /// Both branches are summarized first, and only the summaries are sent.
+    Summary,
+}
+
+/// Request asking the model to summarize a branch before a merge.
+pub const SUMMARY_REQUEST: &str = "Summarize this conversation for someone who will continue it: \
+the problem explored, the ideas tried and the conclusions reached. Keep the code that matters.";
+
+/// The prompts that can be merged: named branch heads first, then the prompts
+/// without children, each listed once.
+pub fn branch_tips(prompts: &[Prompt], marks: &ProjectMarks) -> Vec<Prompt> {
+    let parents: HashSet<&str> = prompts.iter().map(|p| p.prev_prompt_id.as_str()).collect();
+    let mut tips: Vec<Prompt> = marks.heads(prompts).into_iter().map(|(_, p)| p).collect();
+    for prompt in prompts.iter() {
+        let listed = tips.iter().any(|t| t.prompt_id == prompt.prompt_id);
+        if !listed && !parents.contains(prompt.prompt_id.as_str()) {
+            tips.push(prompt.clone());
+        }
+    }
+    tips
+}
+
+/// Renders a branch as Markdown, oldest prompt first.
+///
+/// # Parameters:
+/// - `chain`: The branch as returned by `prompt_chain`, newest prompt first.
+pub fn format_branch(chain: &[Prompt]) -> String {
+    chain
+        .iter()
+        .rev()
+        .map(|p| {
+            format!(
+                "### Prompt\n\n{}\n\n### Output\n\n{}\n",
+                p.content, p.output
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Builds the request asking the model to reconcile two branches.
+///
+/// # Parameters:
+/// - `first`: The first branch, or `None` when it is sent as the conversation.
+/// - `second`: The second branch.
+pub fn merge_request(first: Option<&str>, second: &str) -> String {
+    let branches = match first {
+        Some(first) => format!(
+            "Two branches of a conversation explored different ideas.\n\n\
+             ## First branch\n\n{}\n\n## Second branch\n\n{}",
+            first.trim(),
+            second.trim()
+        ),
+        None => format!(
+            "The conversation so far is one branch. Another branch diverged from it \
+             and explored different ideas.\n\n## Other branch\n\n{}",
+            second.trim()
+        ),
+    };
+    format!(
+        "{}\n\nReconcile both branches into a single continuation: keep what they agree on, \
+         resolve where they conflict and say which ideas you kept and why.",
+        branches
+    )
+}
+
+/// Stores the prompt of a merge and records its second parent, in one transaction.
+///
+/// `prompt.prev_prompt_id` holds the first parent, so chains keep following it.
+///
+/// # Returns:
+/// - `Ok(StoreResult)` as returned by `store_prompt`.
+pub async fn store_merge<'a, A>(
+    conn: A,
+    prompt: &Prompt,
+    second_parent_id: &str,
+) -> Result<StoreResult>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut tx = conn.begin().await.map_err(|err| {
+        log_error(&format!("Failed to start transaction. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to start transaction. Reason: {}", err))
+    })?;
+
+    let stored = store_prompt(&mut *tx, prompt).await?;
+    sqlx::query(
+        "INSERT OR IGNORE INTO prompt_parents (project_id, prompt_id, parent_id)
+         VALUES ($1, $2, $3);",
+    )
+    .bind(&prompt.project_id)
+    .bind(stored.id())
+    .bind(second_parent_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| {
+        log_error(&format!(
+            "FAILED :: INSERT parent [{}] of prompt_id: [{}], error: {}",
+            second_parent_id,
+            stored.id(),
+            err
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to store the parents of prompt {}. Reason: {}",
+            stored.id(),
+            err
+        ))
+    })?;
+
+    tx.commit().await.map_err(|err| {
+        log_error(&format!(
+            "FAILED :: COMMIT MERGE prompt_id: [{}]",
+            prompt.prompt_id,
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to store merge {}. Reason: {}",
+            prompt.prompt_id, err
+        ))
+    })?;
+
+    Ok(stored)
+}
@@ -1,4 +1,12 @@
+pub mod bundle;
 pub mod canvas;
+pub mod export;
+pub mod import;
+pub mod integrity;
+pub mod merge;
 pub mod project;
 pub mod prompt;
 pub mod scroll;
+pub mod session;
+pub mod tag;
+pub mod trash;
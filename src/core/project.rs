@@ -1,20 +1,40 @@
-use crate::utils::{db_utils::delete_module, error::AppError, logger::log_error, structs::Project};
+use crate::{
+    core::scroll::{missing_scrolls, relativize_scroll_paths},
+    utils::{
+        db_utils::delete_module,
+        error::AppError,
+        logger::{log_error, log_info},
+        structs::{Project, Scroll},
+    },
+};
 use anyhow::Result;
-use ratatui::text::Line;
-use sqlx::sqlite::SqlitePool;
+use ratatui::{
+    style::{Color, Style},
+    text::Line,
+};
+use serde::Deserialize;
+use sqlx::{Acquire, Executor, Sqlite};
+use std::str::FromStr;
 
 /// Inserts a project into the database.
-pub async fn store_project(pool: &SqlitePool, project: &Project) -> Result<()> {
+pub async fn store_project<'e, E>(executor: E, project: &Project) -> Result<()>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
     sqlx::query(
-        "INSERT INTO projects (project_id, project_path) 
-         SELECT $1, $2
+        "INSERT INTO projects (project_id, project_path, display_name, description, color, icon)
+         SELECT $1, $2, $3, $4, $5, $6
          WHERE NOT EXISTS (
              SELECT 1 FROM projects WHERE project_path = $2
          )",
     )
     .bind(&project.project_id)
     .bind(&project.project_path)
-    .execute(pool)
+    .bind(&project.display_name)
+    .bind(&project.description)
+    .bind(&project.color)
+    .bind(&project.icon)
+    .execute(executor)
     .await
     .map_err(|err| {
         log_error(&format!(
@@ -31,9 +51,12 @@ pub async fn store_project(pool: &SqlitePool, project: &Project) -> Result<()> {
 }
 
 /// Fetches all projects from the database.
-pub async fn get_projects(pool: &SqlitePool) -> Result<Vec<Project>> {
+pub async fn get_projects<'e, E>(executor: E) -> Result<Vec<Project>>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
     let result = sqlx::query_as::<_, Project>("SELECT * FROM projects;")
-        .fetch_all(pool)
+        .fetch_all(executor)
         .await
         .map_err(|err| {
             log_error(&format!("Failed to get projects. Reason: {}", err));
@@ -43,43 +66,280 @@ pub async fn get_projects(pool: &SqlitePool) -> Result<Vec<Project>> {
     Ok(result)
 }
 
-pub async fn delete_project(pool: &SqlitePool, project_id: &str) -> Result<()> {
-    let col_name = "project_id";
-    delete_module(pool, "projects", col_name, project_id)
+/// Points a project at a new directory.
+pub async fn update_project_path<'e, E>(executor: E, project_id: &str, path: &str) -> Result<()>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query("UPDATE projects SET project_path = $1 WHERE project_id = $2")
+        .bind(path)
+        .bind(project_id)
+        .execute(executor)
         .await
-        .expect("Error in project deletion");
+        .map_err(|err| {
+            log_error(&format!("FAILED :: UPDATE project_id: [{}]", project_id));
+            AppError::DatabaseError(format!(
+                "Failed to update path of project {}. Reason: {}",
+                project_id, err
+            ))
+        })?;
 
-    delete_module(pool, "prompts", col_name, project_id)
-        .await
-        .expect("Error in prompts deletion");
+    Ok(())
+}
 
-    delete_module(pool, "scrolls", col_name, project_id)
-        .await
-        .expect("Error in scoll deletion");
+/// Stores the display name, description, color and icon of a project.
+pub async fn update_project_details<'e, E>(executor: E, project: &Project) -> Result<()>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query(
+        "UPDATE projects SET display_name = $1, description = $2, color = $3, icon = $4
+         WHERE project_id = $5",
+    )
+    .bind(&project.display_name)
+    .bind(&project.description)
+    .bind(&project.color)
+    .bind(&project.icon)
+    .bind(&project.project_id)
+    .execute(executor)
+    .await
+    .map_err(|err| {
+        log_error(&format!(
+            "FAILED :: UPDATE details of project_id: [{}]",
+            project.project_id
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to update details of project {}. Reason: {}",
+            project.project_id, err
+        ))
+    })?;
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct ProjectDetails {
+    #[serde(default)]
+    display_name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    color: String,
+    #[serde(default)]
+    icon: String,
+}
+
+/// Renders the editable details of a project as a TOML document.
+pub fn project_details_template(project: &Project) -> String {
+    let field =
+        |value: &Option<String>| toml::Value::String(value.clone().unwrap_or_default()).to_string();
+    format!(
+        "# Details of the project at {}\n\
+         # Leave a value empty to clear it.\n\
+         \n\
+         display_name = {}\n\
+         description = {}\n\
+         # Named color (e.g. \"cyan\") or hex value (e.g. \"#ff8800\")\n\
+         color = {}\n\
+         icon = {}\n",
+        project.project_path,
+        field(&project.display_name),
+        field(&project.description),
+        field(&project.color),
+        field(&project.icon),
+    )
+}
+
+/// Applies an edited `project_details_template` to a project.
+///
+/// # Returns:
+/// - `Ok(Project)` with the new details; empty values become `None`.
+/// - `AppError::ParseError` if the document is not valid TOML or the color is unknown.
+pub fn parse_project_details(project: &Project, details: &str) -> Result<Project> {
+    let details: ProjectDetails = toml::from_str(details).map_err(|err| {
+        log_error(&format!("Failed to parse project details. Reason: {}", err));
+        AppError::ParseError(format!("Failed to parse project details. Reason: {}", err))
+    })?;
+
+    let non_empty = |value: String| {
+        let value = value.trim().to_string();
+        if value.is_empty() {
+            None
+        } else {
+            Some(value)
+        }
+    };
+
+    let color = non_empty(details.color);
+    if let Some(color) = &color {
+        Color::from_str(color).map_err(|_| {
+            log_error(&format!("Invalid project color: {}", color));
+            AppError::ParseError(format!("Invalid project color: {}", color))
+        })?;
+    }
+
+    Ok(Project {
+        display_name: non_empty(details.display_name),
+        description: non_empty(details.description),
+        color,
+        icon: non_empty(details.icon),
+        ..project.clone()
+    })
+}
+
+/// Moves a project to `new_path` after its repository was moved or renamed.
+///
+/// Scroll paths inside the old directory are first made relative, so they follow
+/// the project. Scrolls are then re-validated against the new directory; missing
+/// ones are reported instead of deleted.
+///
+/// # Returns:
+/// - `Ok((Project, Vec<Scroll>))` with the relocated project and its missing scrolls.
+/// - `AppError::DatabaseError` if `new_path` already belongs to another project.
+pub async fn relocate_project<'a, A>(
+    conn: A,
+    project: &Project,
+    new_path: &str,
+) -> Result<(Project, Vec<Scroll>)>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut tx = conn.begin().await.map_err(|err| {
+        log_error(&format!("Failed to start transaction. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to start transaction. Reason: {}", err))
+    })?;
+
+    let projects = get_projects(&mut *tx).await?;
+    if let Some(other) = projects
+        .iter()
+        .find(|p| p.project_path == new_path && p.project_id != project.project_id)
+    {
+        return Err(AppError::DatabaseError(format!(
+            "Path {} is already used by project {}",
+            new_path, other.project_id
+        ))
+        .into());
+    }
+
+    relativize_scroll_paths(&mut *tx, project).await?;
+    update_project_path(&mut *tx, &project.project_id, new_path).await?;
+
+    let relocated = Project {
+        project_path: new_path.to_string(),
+        ..project.clone()
+    };
+    let missing = missing_scrolls(&mut *tx, &relocated).await?;
+    tx.commit().await.map_err(|err| {
+        log_error(&format!(
+            "FAILED :: COMMIT relocation of project_id: [{}]",
+            project.project_id
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to relocate project {}. Reason: {}",
+            project.project_id, err
+        ))
+    })?;
+
+    for scroll in missing.iter() {
+        log_error(&format!(
+            "Scroll {} not found after relocating project {} to {}",
+            scroll.scroll_path, project.project_id, new_path
+        ));
+    }
+    log_info(&format!(
+        "Relocated project {} from {} to {}",
+        project.project_id, project.project_path, new_path
+    ));
+
+    Ok((relocated, missing))
+}
+
+/// Deletes a project together with its prompts, scrolls, trash, tags, stars,
+/// branch labels and merge parents, in one transaction.
+///
+/// The schema also deletes them in cascade; they are removed explicitly so
+/// databases without foreign key enforcement are left consistent as well.
+pub async fn delete_project<'a, A>(conn: A, project_id: &str) -> Result<()>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut tx = conn.begin().await.map_err(|err| {
+        log_error(&format!("Failed to start transaction. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to start transaction. Reason: {}", err))
+    })?;
+
+    let col_name = "project_id";
+    for table in [
+        "prompt_trash",
+        "trash_batches",
+        "prompt_tags",
+        "prompt_stars",
+        "branch_labels",
+        "prompt_parents",
+        "prompts",
+        "scrolls",
+        "projects",
+    ] {
+        delete_module(&mut *tx, table, col_name, project_id).await?;
+    }
+
+    tx.commit().await.map_err(|err| {
+        log_error(&format!(
+            "FAILED :: COMMIT DELETE project_id: [{}]",
+            project_id
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to delete project {}. Reason: {}",
+            project_id, err
+        ))
+    })?;
+    log_info(&format!("Deleted project {}", project_id));
 
     Ok(())
 }
 
 pub fn format_project_title(current_project: &Option<Project>) -> String {
     match current_project {
-        Some(project) => format!(
-            "[ Current Project: {} ]",
-            project.project_path.split('/').next_back().unwrap_or("")
-        ),
+        Some(project) => format!("[ Current Project: {} ]", project_label(project)),
         None => "[ Projects ]".to_string(),
     }
 }
 
+/// The project name, prefixed with its icon when one is set.
+pub fn project_label(project: &Project) -> String {
+    match project.icon.as_deref() {
+        Some(icon) => format!("{} {}", icon, project.name()),
+        None => project.name().to_string(),
+    }
+}
+
+/// Builds the project list and the matching picker entries.
+///
+/// Each entry shows the project name and description. Projects sharing a name
+/// also show their path so they can be told apart. The path and description are
+/// part of the picker entry, which makes them searchable.
 pub fn build_select_project(projects: &[Project]) -> (Vec<Line<'static>>, Vec<String>) {
     let mut proj_items: Vec<Line> = vec![];
     let mut str_items: Vec<String> = vec![];
     for project in projects {
-        let proj_name = format!(
-            " -[ {} ]-",
-            project.project_path.split("/").last().unwrap_or("")
-        );
+        let mut proj_name = format!(" -[ {} ]-", project_label(project));
+        if let Some(description) = &project.description {
+            proj_name.push_str(&format!(" {}", description));
+        }
+        if projects
+            .iter()
+            .any(|p| p.project_id != project.project_id && p.name() == project.name())
+        {
+            proj_name.push_str(&format!(" ({})", project.project_path));
+        }
+
+        let style = project
+            .color
+            .as_deref()
+            .and_then(|c| Color::from_str(c).ok())
+            .map_or(Style::default(), |c| Style::default().fg(c));
         str_items.push(proj_name.to_owned());
-        proj_items.push(Line::from(proj_name));
+        proj_items.push(Line::styled(proj_name, style));
     }
     (proj_items, str_items)
 }
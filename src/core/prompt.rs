@@ -2,19 +2,35 @@ use crate::utils::{
     db_utils::delete_module,
     error::AppError,
     logger::log_error,
-    structs::{Prompt, Scroll},
+    structs::{Prompt, Scroll, StoreResult},
 };
 use anyhow::{Ok, Result};
-use sqlx::sqlite::SqlitePool;
-use std::collections::HashMap;
+use sqlx::{Acquire, Executor, Sqlite};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 /// Stores a prompt into the database.
-pub async fn store_prompt(pool: &SqlitePool, prompt: &Prompt) -> Result<()> {
-    sqlx::query(
+///
+/// A prompt with the same content and output under the same parent of the same
+/// project is not inserted twice; the existing row is reused instead.
+///
+/// # Returns:
+/// - `Ok(StoreResult::Inserted)` with the id of `prompt`.
+/// - `Ok(StoreResult::Reused)` with the id of the existing prompt.
+pub async fn store_prompt<'a, A>(conn: A, prompt: &Prompt) -> Result<StoreResult>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut conn = conn.acquire().await.map_err(|err| {
+        log_error(&format!("Failed to acquire connection. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to acquire connection. Reason: {}", err))
+    })?;
+
+    let inserted = sqlx::query(
         "INSERT INTO prompts (prompt_id, project_id, prev_prompt_id, content, output) 
          SELECT $1, $2, $3, $4, $5
          WHERE NOT EXISTS (
-             SELECT 1 FROM prompts WHERE content = $4 AND output = $5
+             SELECT 1 FROM prompts
+             WHERE project_id = $2 AND prev_prompt_id = $3 AND content = $4 AND output = $5
          )",
     )
     .bind(&prompt.prompt_id) // Values to insert
@@ -22,7 +38,7 @@ pub async fn store_prompt(pool: &SqlitePool, prompt: &Prompt) -> Result<()> {
     .bind(&prompt.prev_prompt_id)
     .bind(&prompt.content)
     .bind(&prompt.output)
-    .execute(pool)
+    .execute(&mut *conn)
     .await
     .map_err(|err| {
         log_error(&format!(
@@ -33,20 +49,118 @@ pub async fn store_prompt(pool: &SqlitePool, prompt: &Prompt) -> Result<()> {
             "Failed to store prompt with ID {}. Reason: {}",
             prompt.prompt_id, err
         ))
+    })?
+    .rows_affected();
+
+    if inserted > 0 {
+        return Ok(StoreResult::Inserted(prompt.prompt_id.clone()));
+    }
+
+    let existing: String = sqlx::query_scalar(
+        "SELECT prompt_id FROM prompts
+         WHERE project_id = $1 AND prev_prompt_id = $2 AND content = $3 AND output = $4
+         LIMIT 1;",
+    )
+    .bind(&prompt.project_id)
+    .bind(&prompt.prev_prompt_id)
+    .bind(&prompt.content)
+    .bind(&prompt.output)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|err| {
+        log_error(&format!(
+            "FAILED :: SELECT duplicate of prompt_id = {}, error: {}",
+            prompt.prompt_id, err
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to find the duplicate of prompt {}. Reason: {}",
+            prompt.prompt_id, err
+        ))
     })?;
 
-    Ok(())
+    Ok(StoreResult::Reused(existing))
+}
+
+/// Stores a set of prompts, parents before children.
+///
+/// When a prompt is reused instead of inserted, its children are linked to the
+/// reused row, so a set imported twice is not duplicated.
+///
+/// # Returns:
+/// - `Ok(Vec<(Prompt, StoreResult)>)` with each prompt as stored, carrying its final ids.
+pub async fn store_prompts<'a, A>(conn: A, prompts: &[Prompt]) -> Result<Vec<(Prompt, StoreResult)>>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut conn = conn.acquire().await.map_err(|err| {
+        log_error(&format!("Failed to acquire connection. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to acquire connection. Reason: {}", err))
+    })?;
+
+    // Parents first, so their final id is known when their children are stored
+    let ids: HashSet<&str> = prompts.iter().map(|p| p.prompt_id.as_ref()).collect();
+    let mut children: HashMap<&str, Vec<&Prompt>> = HashMap::new();
+    for p in prompts.iter() {
+        children
+            .entry(p.prev_prompt_id.as_ref())
+            .or_default()
+            .push(p);
+    }
+    let mut ordered: Vec<&Prompt> = vec![];
+    let mut visited: HashSet<&str> = HashSet::new();
+    let mut queue: VecDeque<&Prompt> = prompts
+        .iter()
+        .filter(|p| !ids.contains(p.prev_prompt_id.as_str()))
+        .collect();
+    while let Some(p) = queue.pop_front() {
+        if visited.insert(p.prompt_id.as_ref()) {
+            ordered.push(p);
+            queue.extend(children.get(p.prompt_id.as_str()).into_iter().flatten());
+        }
+    }
+    // Prompts caught in a cycle keep their original order
+    ordered.extend(
+        prompts
+            .iter()
+            .filter(|p| !visited.contains(p.prompt_id.as_str())),
+    );
+
+    let mut final_ids: HashMap<String, String> = HashMap::new();
+    let mut stored = vec![];
+    for p in ordered {
+        let prompt = Prompt {
+            prev_prompt_id: final_ids
+                .get(&p.prev_prompt_id)
+                .cloned()
+                .unwrap_or_else(|| p.prev_prompt_id.clone()),
+            ..p.clone()
+        };
+        let result = store_prompt(&mut *conn, &prompt).await?;
+        final_ids.insert(prompt.prompt_id.clone(), result.id().to_string());
+        stored.push((
+            Prompt {
+                prompt_id: result.id().to_string(),
+                ..prompt
+            },
+            result,
+        ));
+    }
+
+    Ok(stored)
 }
 
 // Sorted from first to last prompt on the list
-pub async fn get_prompts(pool: &SqlitePool, project_id: &str) -> Result<Vec<Prompt>> {
+pub async fn get_prompts<'e, E>(executor: E, project_id: &str) -> Result<Vec<Prompt>>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
     let prompts = sqlx::query_as::<_, Prompt>(
         "SELECT * 
         FROM prompts
         WHERE project_id = $1;",
     )
     .bind(project_id)
-    .fetch_all(pool)
+    .fetch_all(executor)
     .await
     .map_err(|err| {
         log_error(&format!(
@@ -64,13 +178,16 @@ pub async fn get_prompts(pool: &SqlitePool, project_id: &str) -> Result<Vec<Prom
     Ok(prompts)
 }
 
-pub async fn update_prompt(
-    pool: &SqlitePool,
+pub async fn update_prompt<'e, E>(
+    executor: E,
     col_set_name: &str,
     new_value: &str,
     col_comp_name: &str,
     col_comp_val: &str,
-) -> Result<()> {
+) -> Result<()>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
     let query = format!(
         "UPDATE prompts SET {} = ? WHERE {} = ?",
         &col_set_name, &col_comp_name,
@@ -79,7 +196,7 @@ pub async fn update_prompt(
     sqlx::query(&query)
         .bind(new_value)
         .bind(col_comp_val)
-        .execute(pool)
+        .execute(executor)
         .await
         .map_err(|err| {
             log_error(&format!(
@@ -94,8 +211,17 @@ pub async fn update_prompt(
     Ok(())
 }
 
-pub async fn delete_prompt(pool: &SqlitePool, prompt: &Prompt) -> Result<()> {
-    if let Err(error) = delete_module(pool, "prompts", "prompt_id", &prompt.prompt_id).await {
+/// Deletes a prompt and links its children to its parent, in one transaction.
+pub async fn delete_prompt<'a, A>(conn: A, prompt: &Prompt) -> Result<()>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut tx = conn.begin().await.map_err(|err| {
+        log_error(&format!("Failed to start transaction. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to start transaction. Reason: {}", err))
+    })?;
+
+    if let Err(error) = delete_module(&mut *tx, "prompts", "prompt_id", &prompt.prompt_id).await {
         log_error(&format!(
             "FAILED :: DELETE prompt_id: [{}]",
             prompt.prompt_id,
@@ -104,7 +230,7 @@ pub async fn delete_prompt(pool: &SqlitePool, prompt: &Prompt) -> Result<()> {
     }
 
     if let Err(error) = update_prompt(
-        pool,
+        &mut *tx,
         "prev_prompt_id",
         &prompt.prev_prompt_id,
         "prev_prompt_id",
@@ -119,9 +245,193 @@ pub async fn delete_prompt(pool: &SqlitePool, prompt: &Prompt) -> Result<()> {
         return Err(error);
     }
 
+    tx.commit().await.map_err(|err| {
+        log_error(&format!(
+            "FAILED :: COMMIT DELETE prompt_id: [{}]",
+            prompt.prompt_id,
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to delete prompt {}. Reason: {}",
+            prompt.prompt_id, err
+        ))
+    })?;
+
     Ok(())
 }
 
+/// How much of the tree below a prompt a move or copy takes along.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PromptScope {
+    /// Only the prompt; on a move its children are linked to its old parent.
+    Single,
+    /// The prompt and every prompt below it.
+    Subtree,
+}
+
+/// Returns the prompts `prompt` can be moved under without creating a cycle.
+///
+/// The project root, `prompt.project_id`, is always a valid target as well.
+pub fn move_targets(prompts: &[Prompt], prompt: &Prompt, scope: PromptScope) -> Vec<Prompt> {
+    let mut excluded: HashSet<String> = HashSet::from([prompt.prompt_id.clone()]);
+    if scope == PromptScope::Subtree {
+        excluded.extend(
+            prompt_descendants(prompts, prompt)
+                .into_iter()
+                .map(|p| p.prompt_id),
+        );
+    }
+
+    prompts
+        .iter()
+        .filter(|p| !excluded.contains(&p.prompt_id))
+        .cloned()
+        .collect()
+}
+
+/// Moves a prompt under a new parent, in one transaction.
+///
+/// Moving a subtree under the project id detaches it into a new root.
+///
+/// # Parameters:
+/// - `conn`: The database connection.
+/// - `prompt`: The prompt to move.
+/// - `new_parent_id`: A prompt of the same project, or the project id.
+/// - `scope`: Whether the children of `prompt` move along.
+///
+/// # Returns:
+/// - `Err(AppError::ParseError)` when the move would put `prompt` below itself
+///   or the parent is not part of the project.
+pub async fn move_prompt<'a, A>(
+    conn: A,
+    prompt: &Prompt,
+    new_parent_id: &str,
+    scope: PromptScope,
+) -> Result<()>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut tx = conn.begin().await.map_err(|err| {
+        log_error(&format!("Failed to start transaction. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to start transaction. Reason: {}", err))
+    })?;
+
+    let prompts = get_prompts(&mut *tx, &prompt.project_id).await?;
+    let valid = new_parent_id == prompt.project_id
+        || move_targets(&prompts, prompt, scope)
+            .iter()
+            .any(|p| p.prompt_id == new_parent_id);
+    if !valid {
+        return Err(AppError::ParseError(format!(
+            "Cannot move prompt {} under {}",
+            prompt.prompt_id, new_parent_id
+        ))
+        .into());
+    }
+
+    if scope == PromptScope::Single {
+        update_prompt(
+            &mut *tx,
+            "prev_prompt_id",
+            &prompt.prev_prompt_id,
+            "prev_prompt_id",
+            &prompt.prompt_id,
+        )
+        .await?;
+    }
+    update_prompt(
+        &mut *tx,
+        "prev_prompt_id",
+        new_parent_id,
+        "prompt_id",
+        &prompt.prompt_id,
+    )
+    .await?;
+
+    tx.commit().await.map_err(|err| {
+        log_error(&format!(
+            "FAILED :: COMMIT MOVE prompt_id: [{}]",
+            prompt.prompt_id,
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to move prompt {}. Reason: {}",
+            prompt.prompt_id, err
+        ))
+    })?;
+
+    Ok(())
+}
+
+/// Copies a prompt onto another parent, possibly of another project.
+///
+/// The copies get new ids; a copy identical to an existing child of its new
+/// parent reuses that child, as [`store_prompt`] does.
+///
+/// # Parameters:
+/// - `conn`: The database connection.
+/// - `prompt`: The prompt to copy.
+/// - `scope`: Whether the prompts below `prompt` are copied too.
+/// - `project_id`: The project receiving the copies.
+/// - `parent_id`: A prompt of that project, or `project_id` for a new root.
+///
+/// # Returns:
+/// - `Ok(Vec<Prompt>)` with the copies, `prompt`'s copy first.
+pub async fn copy_prompt<'a, A>(
+    conn: A,
+    prompt: &Prompt,
+    scope: PromptScope,
+    project_id: &str,
+    parent_id: &str,
+) -> Result<Vec<Prompt>>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut tx = conn.begin().await.map_err(|err| {
+        log_error(&format!("Failed to start transaction. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to start transaction. Reason: {}", err))
+    })?;
+
+    let targets = get_prompts(&mut *tx, project_id).await?;
+    if parent_id != project_id && !targets.iter().any(|p| p.prompt_id == parent_id) {
+        return Err(AppError::ParseError(format!(
+            "Cannot copy prompt {} under {}",
+            prompt.prompt_id, parent_id
+        ))
+        .into());
+    }
+
+    let mut sources = vec![prompt.clone()];
+    if scope == PromptScope::Subtree {
+        let prompts = get_prompts(&mut *tx, &prompt.project_id).await?;
+        sources.extend(prompt_descendants(&prompts, prompt));
+    }
+
+    // Parents come first, so their copy is known when their children are copied
+    let mut new_ids: HashMap<&str, String> = HashMap::new();
+    let mut copies = vec![];
+    for source in sources.iter() {
+        let prev_prompt_id = new_ids
+            .get(source.prev_prompt_id.as_str())
+            .map_or(parent_id, |id| id.as_str());
+        let copy = Prompt::new(project_id, &source.content, &source.output, prev_prompt_id);
+        new_ids.insert(&source.prompt_id, copy.prompt_id.clone());
+        copies.push(copy);
+    }
+    let stored = store_prompts(&mut *tx, &copies).await?;
+
+    tx.commit().await.map_err(|err| {
+        log_error(&format!(
+            "FAILED :: COMMIT COPY prompt_id: [{}]",
+            prompt.prompt_id,
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to copy prompt {}. Reason: {}",
+            prompt.prompt_id, err
+        ))
+    })?;
+
+    Ok(stored.into_iter().map(|(p, _)| p).collect())
+}
+
 pub async fn system_prompt(scrolls: &[Scroll]) -> String {
     let mut system_prompt = String::new();
 
@@ -159,24 +469,43 @@ pub fn prompt_chain(prompts: &[Prompt], prompt: &Prompt) -> Vec<Prompt> {
     chain
 }
 
+/// Returns every prompt below `prompt`, parents before children.
+pub fn prompt_descendants(prompts: &[Prompt], prompt: &Prompt) -> Vec<Prompt> {
+    let mut children: HashMap<&str, Vec<&Prompt>> = HashMap::new();
+    for p in prompts.iter() {
+        children
+            .entry(p.prev_prompt_id.as_ref())
+            .or_default()
+            .push(p);
+    }
+
+    let mut descendants = Vec::<Prompt>::new();
+    let mut visited: HashSet<&str> = HashSet::from([prompt.prompt_id.as_ref()]);
+    let mut queue: VecDeque<&str> = VecDeque::from([prompt.prompt_id.as_ref()]);
+    while let Some(id) = queue.pop_front() {
+        for child in children.get(id).into_iter().flatten() {
+            if visited.insert(child.prompt_id.as_ref()) {
+                descendants.push((*child).to_owned());
+                queue.push_back(child.prompt_id.as_ref());
+            }
+        }
+    }
+
+    descendants
+}
+
 pub fn format_prompt(p: &Prompt) -> (String, String) {
-    let p_str = format!(" |- Prompt: {}", p.content.replace('\n', " ").to_string());
+    let p_str = format!(" |- Prompt: {}", p.content.replace('\n', " "));
 
-    let o_str = format!(" |  Output: {}", p.output.replace('\n', " ").to_string());
+    let o_str = format!(" |  Output: {}", p.output.replace('\n', " "));
 
     (p_str, o_str)
 }
 
 pub fn format_prompt_depth(p: &Prompt, b_depth: &str) -> (String, String) {
-    let p_str = format!(
-        "{b_depth}> Prompt: {}",
-        p.content.replace('\n', " ").to_string()
-    );
+    let p_str = format!("{b_depth}> Prompt: {}", p.content.replace('\n', " "));
 
-    let o_str = format!(
-        "{b_depth}> Output: {}",
-        p.output.replace('\n', " ").to_string()
-    );
+    let o_str = format!("{b_depth}> Output: {}", p.output.replace('\n', " "));
 
     (p_str, o_str)
 }
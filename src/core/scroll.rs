@@ -1,22 +1,43 @@
-use crate::utils::{db_utils::delete_module, error::AppError, logger::log_error, structs::Scroll};
+use crate::utils::{
+    db_utils::delete_module,
+    error::AppError,
+    logger::log_error,
+    structs::{Project, Scroll, StoreResult},
+};
 use anyhow::Result;
-use sqlx::sqlite::SqlitePool;
+use sqlx::{sqlite::SqlitePool, Acquire, Executor, Sqlite};
 use std::fs;
+use std::path::{Path, PathBuf};
 
 /// Inserts a scroll into the database.
-pub async fn store_scroll(pool: &SqlitePool, scroll: &Scroll) -> Result<()> {
-    sqlx::query(
+///
+/// A project holds a single scroll per path; storing a path it already has
+/// reuses the existing scroll instead.
+///
+/// # Returns:
+/// - `Ok(StoreResult::Inserted)` with the id of `scroll`.
+/// - `Ok(StoreResult::Reused)` with the id of the existing scroll.
+pub async fn store_scroll<'a, A>(conn: A, scroll: &Scroll) -> Result<StoreResult>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut conn = conn.acquire().await.map_err(|err| {
+        log_error(&format!("Failed to acquire connection. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to acquire connection. Reason: {}", err))
+    })?;
+
+    let inserted = sqlx::query(
         "INSERT INTO scrolls (scroll_id, scroll_path, content, project_id) 
          SELECT $1, $2, $3, $4
          WHERE NOT EXISTS (
-             SELECT 1 FROM scrolls WHERE scroll_path = $2 AND content = $3
+             SELECT 1 FROM scrolls WHERE project_id = $4 AND scroll_path = $2
          )",
     )
     .bind(&scroll.scroll_id)
     .bind(&scroll.scroll_path)
     .bind(&scroll.content)
     .bind(&scroll.project_id)
-    .execute(pool)
+    .execute(&mut *conn)
     .await
     .map_err(|err| {
         log_error(&format!(
@@ -27,19 +48,45 @@ pub async fn store_scroll(pool: &SqlitePool, scroll: &Scroll) -> Result<()> {
             "Failed to store scroll with ID {}. Reason: {}",
             scroll.scroll_id, err
         ))
+    })?
+    .rows_affected();
+
+    if inserted > 0 {
+        return Ok(StoreResult::Inserted(scroll.scroll_id.clone()));
+    }
+
+    let existing: String = sqlx::query_scalar(
+        "SELECT scroll_id FROM scrolls WHERE project_id = $1 AND scroll_path = $2 LIMIT 1;",
+    )
+    .bind(&scroll.project_id)
+    .bind(&scroll.scroll_path)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|err| {
+        log_error(&format!(
+            "FAILED :: SELECT duplicate of scroll_id: [{}]",
+            scroll.scroll_id,
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to find the duplicate of scroll {}. Reason: {}",
+            scroll.scroll_id, err
+        ))
     })?;
 
-    Ok(())
+    Ok(StoreResult::Reused(existing))
 }
 
-pub async fn get_scrolls(pool: &SqlitePool, project_id: &str) -> Result<Vec<Scroll>> {
+pub async fn get_scrolls<'e, E>(executor: E, project_id: &str) -> Result<Vec<Scroll>>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
     let scrolls_result: Vec<Scroll> = sqlx::query_as::<_, Scroll>(
         "SELECT *
         FROM scrolls 
         WHERE project_id = $1;",
     )
     .bind(project_id)
-    .fetch_all(pool)
+    .fetch_all(executor)
     .await
     .map_err(|err| {
         log_error(&format!(
@@ -58,14 +105,25 @@ pub async fn get_scrolls(pool: &SqlitePool, project_id: &str) -> Result<Vec<Scro
 }
 
 pub async fn delete_scroll(pool: &SqlitePool, scroll_id: &str) -> Result<()> {
-    delete_module(pool, "scrolls", "scroll_id", scroll_id)
-        .await
-        .expect("Error in scroll deletion");
+    delete_module(pool, "scrolls", "scroll_id", scroll_id).await?;
     Ok(())
 }
 
-pub async fn update_scroll_content(pool: &SqlitePool, scroll: &Scroll) -> Result<Scroll> {
-    let new_scroll_result = read_file(&scroll.scroll_path, &scroll.project_id, Some(scroll));
+/// Re-reads the file behind a scroll and stores its current content.
+///
+/// Relative scroll paths are resolved against `project_path`. A missing file is
+/// returned as an error and the scroll is kept.
+pub async fn update_scroll_content(
+    pool: &SqlitePool,
+    project_path: &str,
+    scroll: &Scroll,
+) -> Result<Scroll> {
+    let file_path = resolve_scroll_path(project_path, &scroll.scroll_path);
+    let new_scroll_result = read_file(
+        &file_path.to_string_lossy(),
+        &scroll.project_id,
+        Some(scroll),
+    );
 
     // Match on the result of reading the file
     match new_scroll_result {
@@ -94,26 +152,7 @@ pub async fn update_scroll_content(pool: &SqlitePool, scroll: &Scroll) -> Result
             Ok(new_scroll)
         }
         Err(err) => {
-            // Check if the error corresponds to a missing file
-            if let Some(err_str) = err.downcast_ref::<std::io::Error>() {
-                if err_str.kind() == std::io::ErrorKind::NotFound {
-                    // Log that we're deleting the scroll because the file was not found
-                    log_error(&format!(
-                        "File not found for scroll_id: {}, deleting scroll from database",
-                        scroll.scroll_id
-                    ));
-
-                    // Delete the scroll from the database
-                    delete_scroll(pool, &scroll.scroll_id).await?;
-                    return Err(AppError::FileError(format!(
-                        "File not found at '{}', scroll deleted from database.",
-                        scroll.scroll_path
-                    ))
-                    .into());
-                }
-            }
-
-            // If the error is not because of a missing file, propagate it
+            // A missing file is reported, the scroll is kept until the user deletes it
             Err(AppError::FileError(format!(
                 "Failed to read file '{}': {}",
                 scroll.scroll_path, err
@@ -155,3 +194,93 @@ pub fn read_file(file_path: &str, project_id: &str, scroll: Option<&Scroll>) ->
         }
     }
 }
+
+/// Reads a file of a project into a new scroll.
+///
+/// Files inside the project directory get a path relative to it, so the scroll
+/// keeps working when the project is moved; other files keep their absolute path.
+pub fn read_project_file(project: &Project, file_path: &str) -> Result<Scroll> {
+    let mut scroll = read_file(file_path, &project.project_id, None)?;
+    if let Some(relative) = relative_scroll_path(&project.project_path, file_path) {
+        scroll.scroll_path = relative;
+    }
+    Ok(scroll)
+}
+
+/// Rewrites the absolute scroll paths of a project that live inside its directory
+/// as paths relative to it.
+///
+/// # Returns:
+/// - `Ok(usize)` with the number of scrolls that were rewritten.
+pub async fn relativize_scroll_paths<'a, A>(conn: A, project: &Project) -> Result<usize>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut conn = conn.acquire().await.map_err(|err| {
+        log_error(&format!("Failed to acquire connection. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to acquire connection. Reason: {}", err))
+    })?;
+    let scrolls = get_scrolls(&mut *conn, &project.project_id).await?;
+    let mut updated = 0;
+
+    for scroll in scrolls.iter() {
+        if !Path::new(&scroll.scroll_path).is_absolute() {
+            continue;
+        }
+        let Some(relative) = relative_scroll_path(&project.project_path, &scroll.scroll_path)
+        else {
+            continue;
+        };
+
+        sqlx::query("UPDATE scrolls SET scroll_path = $1 WHERE scroll_id = $2")
+            .bind(&relative)
+            .bind(&scroll.scroll_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|err| {
+                log_error(&format!(
+                    "FAILED :: UPDATE scroll_id: {}, error: {}",
+                    scroll.scroll_id, err
+                ));
+                AppError::DatabaseError(format!(
+                    "Failed to update path of scroll {}. Reason: {}",
+                    scroll.scroll_id, err
+                ))
+            })?;
+        updated += 1;
+    }
+
+    Ok(updated)
+}
+
+/// Returns the scrolls of a project whose file cannot be found, resolving
+/// relative paths against the project directory.
+pub async fn missing_scrolls<'e, E>(executor: E, project: &Project) -> Result<Vec<Scroll>>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let scrolls = get_scrolls(executor, &project.project_id).await?;
+    Ok(scrolls
+        .into_iter()
+        .filter(|s| !resolve_scroll_path(&project.project_path, &s.scroll_path).is_file())
+        .collect())
+}
+
+/// Returns `path` relative to `project_path` when the file lives inside the project,
+/// or `None` when it lives outside of it.
+pub fn relative_scroll_path(project_path: &str, path: &str) -> Option<String> {
+    Path::new(path)
+        .strip_prefix(project_path)
+        .ok()
+        .map(|rel| rel.to_string_lossy().to_string())
+}
+
+/// Joins a relative scroll path onto `project_path`; absolute paths are returned unchanged.
+pub fn resolve_scroll_path(project_path: &str, path: &str) -> PathBuf {
+    let scroll_path = Path::new(path);
+    if scroll_path.is_absolute() {
+        scroll_path.to_path_buf()
+    } else {
+        PathBuf::from(project_path).join(scroll_path)
+    }
+}
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use sqlx::{Executor, FromRow, Sqlite};
+
+use crate::utils::{error::AppError, logger::log_error};
+
+/// Where the user left off: the active project, branch and prompt.
+#[derive(Clone, Debug, Default, FromRow, PartialEq, Eq)]
+pub struct Session {
+    pub project_id: Option<String>,
+    pub branch: Option<String>,
+    pub prompt_id: Option<String>,
+}
+
+/// Fetches the stored session.
+///
+/// # Returns:
+/// - `Ok(Session)`, empty when none was stored yet.
+pub async fn get_session<'e, E>(executor: E) -> Result<Session>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let session: Option<Session> =
+        sqlx::query_as("SELECT project_id, branch, prompt_id FROM session WHERE id = 1;")
+            .fetch_optional(executor)
+            .await
+            .map_err(|err| {
+                log_error(&format!("FAILED :: SELECT session, error: {}", err));
+                AppError::DatabaseError(format!("Failed to fetch the session. Reason: {}", err))
+            })?;
+
+    Ok(session.unwrap_or_default())
+}
+
+/// Stores the session, replacing the previous one.
+pub async fn store_session<'e, E>(executor: E, session: &Session) -> Result<()>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query(
+        "INSERT OR REPLACE INTO session (id, project_id, branch, prompt_id)
+         VALUES (1, $1, $2, $3);",
+    )
+    .bind(&session.project_id)
+    .bind(&session.branch)
+    .bind(&session.prompt_id)
+    .execute(executor)
+    .await
+    .map_err(|err| {
+        log_error(&format!("FAILED :: INSERT session, error: {}", err));
+        AppError::DatabaseError(format!("Failed to store the session. Reason: {}", err))
+    })?;
+
+    Ok(())
+}
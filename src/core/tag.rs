@@ -0,0 +1,411 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::{Acquire, Executor, FromRow, Sqlite, SqliteConnection};
+
+use crate::utils::{
+    error::AppError,
+    logger::{log_error, log_info},
+    structs::Prompt,
+};
+
+/// A named branch head, like a git branch label.
+#[derive(Clone, Debug, FromRow, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchLabel {
+    pub project_id: String,
+    pub name: String,
+    pub prompt_id: String,
+}
+
+/// Tags, star and branch labels of a single prompt.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+pub struct PromptMarks {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub starred: bool,
+    #[serde(default)]
+    pub branches: Vec<String>,
+}
+
+/// Tags, stars, branch labels and merge parents of all the prompts of a project.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProjectMarks {
+    /// Tags of each prompt, keyed by prompt id.
+    pub tags: HashMap<String, Vec<String>>,
+    pub starred: HashSet<String>,
+    /// Branch labels, sorted by name.
+    pub branches: Vec<BranchLabel>,
+    /// Second parents of merge prompts, keyed by prompt id.
+    pub merged: HashMap<String, Vec<String>>,
+}
+
+impl ProjectMarks {
+    /// The marks of a single prompt.
+    pub fn prompt(&self, prompt_id: &str) -> PromptMarks {
+        PromptMarks {
+            tags: self.tags.get(prompt_id).cloned().unwrap_or_default(),
+            starred: self.starred.contains(prompt_id),
+            branches: self
+                .branches
+                .iter()
+                .filter(|b| b.prompt_id == prompt_id)
+                .map(|b| b.name.clone())
+                .collect(),
+        }
+    }
+
+    /// Every tag used in the project, sorted.
+    pub fn all_tags(&self) -> Vec<String> {
+        let tags: BTreeSet<&String> = self.tags.values().flatten().collect();
+        tags.into_iter().cloned().collect()
+    }
+
+    /// The prompts carrying `tag`, in the order of `prompts`.
+    pub fn tagged(&self, prompts: &[Prompt], tag: &str) -> Vec<Prompt> {
+        prompts
+            .iter()
+            .filter(|p| {
+                self.tags
+                    .get(&p.prompt_id)
+                    .is_some_and(|tags| tags.iter().any(|t| t == tag))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// The starred prompts, in the order of `prompts`.
+    pub fn starred_prompts(&self, prompts: &[Prompt]) -> Vec<Prompt> {
+        prompts
+            .iter()
+            .filter(|p| self.starred.contains(&p.prompt_id))
+            .cloned()
+            .collect()
+    }
+
+    /// The branch labels with the prompt they point to. Labels of prompts that
+    /// are not in `prompts`, e.g. trashed ones, are left out.
+    pub fn heads(&self, prompts: &[Prompt]) -> Vec<(BranchLabel, Prompt)> {
+        self.branches
+            .iter()
+            .filter_map(|b| {
+                prompts
+                    .iter()
+                    .find(|p| p.prompt_id == b.prompt_id)
+                    .map(|p| (b.clone(), p.clone()))
+            })
+            .collect()
+    }
+
+    /// The first branch whose head is `prompt_id`, if any.
+    pub fn branch_of(&self, prompt_id: &str) -> Option<String> {
+        self.branches
+            .iter()
+            .find(|b| b.prompt_id == prompt_id)
+            .map(|b| b.name.clone())
+    }
+
+    /// Short suffix describing the marks of a prompt, e.g. ` * [main] #good`.
+    /// Empty when the prompt has no marks.
+    pub fn label(&self, prompt_id: &str) -> String {
+        let marks = self.prompt(prompt_id);
+        let mut label = String::new();
+        if marks.starred {
+            label.push_str(" *");
+        }
+        for branch in marks.branches.iter() {
+            label.push_str(&format!(" [{}]", branch));
+        }
+        for tag in marks.tags.iter() {
+            label.push_str(&format!(" #{}", tag));
+        }
+        label
+    }
+}
+
+/// Checks a tag or branch name: non-empty, without whitespace, `#`, `[` or `]`.
+///
+/// # Returns:
+/// - `Ok(String)` with the trimmed name.
+/// - `AppError::ParseError` when the name is invalid.
+pub fn validate_mark_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().any(|c| c.is_whitespace() || "#[]".contains(c)) {
+        return Err(AppError::ParseError(format!(
+            "Invalid tag or branch name [{}], it cannot be empty nor hold spaces, '#', '[' or ']'",
+            name
+        ))
+        .into());
+    }
+    Ok(name.to_string())
+}
+
+/// Fetches the tags, stars, branch labels and merge parents of a project.
+pub async fn get_marks<'a, A>(conn: A, project_id: &str) -> Result<ProjectMarks>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut conn = conn.acquire().await.map_err(|err| {
+        log_error(&format!("Failed to acquire connection. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to acquire connection. Reason: {}", err))
+    })?;
+    let fetch_err = |table: &str, err: sqlx::Error| -> anyhow::Error {
+        log_error(&format!(
+            "FAILED :: SELECT {} of project_id: [{}], error: {}",
+            table, project_id, err
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to fetch {} of project {}. Reason: {}",
+            table, project_id, err
+        ))
+        .into()
+    };
+
+    let tag_rows: Vec<(String, String)> = sqlx::query_as(
+        "SELECT prompt_id, tag FROM prompt_tags WHERE project_id = $1 ORDER BY tag;",
+    )
+    .bind(project_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|err| fetch_err("prompt_tags", err))?;
+
+    let starred: Vec<String> =
+        sqlx::query_scalar("SELECT prompt_id FROM prompt_stars WHERE project_id = $1;")
+            .bind(project_id)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|err| fetch_err("prompt_stars", err))?;
+
+    let branches: Vec<BranchLabel> = sqlx::query_as(
+        "SELECT project_id, name, prompt_id FROM branch_labels
+         WHERE project_id = $1 ORDER BY name;",
+    )
+    .bind(project_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|err| fetch_err("branch_labels", err))?;
+
+    let parent_rows: Vec<(String, String)> =
+        sqlx::query_as("SELECT prompt_id, parent_id FROM prompt_parents WHERE project_id = $1;")
+            .bind(project_id)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|err| fetch_err("prompt_parents", err))?;
+
+    let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+    for (prompt_id, tag) in tag_rows {
+        tags.entry(prompt_id).or_default().push(tag);
+    }
+    let mut merged: HashMap<String, Vec<String>> = HashMap::new();
+    for (prompt_id, parent_id) in parent_rows {
+        merged.entry(prompt_id).or_default().push(parent_id);
+    }
+
+    Ok(ProjectMarks {
+        tags,
+        starred: starred.into_iter().collect(),
+        branches,
+        merged,
+    })
+}
+
+/// Runs a statement of `set_prompt_marks`, mapping its error.
+async fn execute_mark<'q>(
+    conn: &mut SqliteConnection,
+    query: sqlx::query::Query<'q, Sqlite, sqlx::sqlite::SqliteArguments<'q>>,
+    prompt_id: &str,
+) -> Result<()> {
+    query.execute(conn).await.map_err(|err| {
+        log_error(&format!(
+            "FAILED :: UPDATE marks of prompt_id: [{}], error: {}",
+            prompt_id, err
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to update the marks of prompt {}. Reason: {}",
+            prompt_id, err
+        ))
+    })?;
+    Ok(())
+}
+
+/// Replaces the tags, star and branch labels of a prompt.
+///
+/// A branch label already pointing at another prompt of the project is moved
+/// to `prompt`, the way `git branch -f` does.
+pub async fn set_prompt_marks<'a, A>(conn: A, prompt: &Prompt, marks: &PromptMarks) -> Result<()>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut tx = conn.begin().await.map_err(|err| {
+        log_error(&format!("Failed to start transaction. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to start transaction. Reason: {}", err))
+    })?;
+    let id = &prompt.prompt_id;
+
+    execute_mark(
+        &mut tx,
+        sqlx::query("DELETE FROM prompt_tags WHERE prompt_id = $1;").bind(id),
+        id,
+    )
+    .await?;
+    for tag in marks.tags.iter() {
+        execute_mark(
+            &mut tx,
+            sqlx::query(
+                "INSERT OR IGNORE INTO prompt_tags (project_id, prompt_id, tag)
+                 VALUES ($1, $2, $3);",
+            )
+            .bind(&prompt.project_id)
+            .bind(id)
+            .bind(validate_mark_name(tag)?),
+            id,
+        )
+        .await?;
+    }
+
+    execute_mark(
+        &mut tx,
+        sqlx::query("DELETE FROM prompt_stars WHERE prompt_id = $1;").bind(id),
+        id,
+    )
+    .await?;
+    if marks.starred {
+        execute_mark(
+            &mut tx,
+            sqlx::query("INSERT INTO prompt_stars (project_id, prompt_id) VALUES ($1, $2);")
+                .bind(&prompt.project_id)
+                .bind(id),
+            id,
+        )
+        .await?;
+    }
+
+    execute_mark(
+        &mut tx,
+        sqlx::query("DELETE FROM branch_labels WHERE prompt_id = $1;").bind(id),
+        id,
+    )
+    .await?;
+    for branch in marks.branches.iter() {
+        execute_mark(
+            &mut tx,
+            sqlx::query(
+                "INSERT OR REPLACE INTO branch_labels (project_id, name, prompt_id)
+                 VALUES ($1, $2, $3);",
+            )
+            .bind(&prompt.project_id)
+            .bind(validate_mark_name(branch)?)
+            .bind(id),
+            id,
+        )
+        .await?;
+    }
+
+    tx.commit().await.map_err(|err| {
+        log_error(&format!(
+            "FAILED :: COMMIT marks of prompt_id: [{}], error: {}",
+            id, err
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to update the marks of prompt {}. Reason: {}",
+            id, err
+        ))
+    })?;
+    log_info(&format!("Updated the marks of prompt {}", id));
+
+    Ok(())
+}
+
+/// Moves the head of branch `name` from `from_prompt_id` to `to_prompt_id`, as
+/// asking a question on the head of a branch does.
+///
+/// # Returns:
+/// - `Ok(true)` when the branch was advanced.
+/// - `Ok(false)` when the branch is gone or its head is not `from_prompt_id`.
+pub async fn advance_branch<'e, E>(
+    executor: E,
+    project_id: &str,
+    name: &str,
+    from_prompt_id: &str,
+    to_prompt_id: &str,
+) -> Result<bool>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let result = sqlx::query(
+        "UPDATE branch_labels SET prompt_id = $4
+         WHERE project_id = $1 AND name = $2 AND prompt_id = $3;",
+    )
+    .bind(project_id)
+    .bind(name)
+    .bind(from_prompt_id)
+    .bind(to_prompt_id)
+    .execute(executor)
+    .await
+    .map_err(|err| {
+        log_error(&format!(
+            "FAILED :: UPDATE branch [{}] of project_id: [{}], error: {}",
+            name, project_id, err
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to advance branch {}. Reason: {}",
+            name, err
+        ))
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Builds the TOML document edited in `$EDITOR` to change the marks of a prompt.
+pub fn prompt_marks_template(prompt: &Prompt, marks: &PromptMarks) -> String {
+    let list = |items: &[String]| {
+        items
+            .iter()
+            .map(|i| format!("{:?}", i))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let first_line = prompt.content.lines().next().unwrap_or("").trim();
+
+    format!(
+        "# Marks of the prompt: {}\n\
+         # Tags and branch names cannot hold spaces, '#', '[' or ']'.\n\
+         tags = [{}]\n\
+         # Starred prompts can be listed with the tag filter.\n\
+         starred = {}\n\
+         # Branch labels naming this prompt as the head of a branch.\n\
+         branches = [{}]\n",
+        first_line,
+        list(&marks.tags),
+        marks.starred,
+        list(&marks.branches)
+    )
+}
+
+/// Parses the document built by [`prompt_marks_template`].
+///
+/// # Returns:
+/// - `Ok(PromptMarks)` with trimmed, deduplicated names.
+/// - `AppError::ParseError` for invalid TOML or names.
+pub fn parse_prompt_marks(text: &str) -> Result<PromptMarks> {
+    let marks: PromptMarks = toml::from_str(text)
+        .map_err(|err| AppError::ParseError(format!("Invalid prompt marks: {}", err)))?;
+
+    let dedup = |names: &[String]| -> Result<Vec<String>> {
+        let mut seen = vec![];
+        for name in names.iter() {
+            let name = validate_mark_name(name)?;
+            if !seen.contains(&name) {
+                seen.push(name);
+            }
+        }
+        Ok(seen)
+    };
+
+    Ok(PromptMarks {
+        tags: dedup(&marks.tags)?,
+        starred: marks.starred,
+        branches: dedup(&marks.branches)?,
+    })
+}
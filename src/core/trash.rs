@@ -0,0 +1,463 @@
+use crate::{
+    core::prompt::{get_prompts, prompt_descendants, update_prompt},
+    utils::{
+        db_utils::delete_module,
+        error::AppError,
+        logger::{log_error, log_info},
+        structs::Prompt,
+    },
+};
+use anyhow::Result;
+use chrono::Local;
+use sqlx::{Acquire, FromRow, Sqlite, SqliteConnection};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// How `trash_prompt` treats the prompts below the deleted one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeleteMode {
+    /// Remove only the prompt and link its children to its parent.
+    Splice,
+    /// Remove the prompt together with all of its descendants.
+    Subtree,
+}
+
+impl DeleteMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeleteMode::Splice => "splice",
+            DeleteMode::Subtree => "subtree",
+        }
+    }
+}
+
+/// A group of prompts removed by a single `trash_prompt` call.
+#[derive(Clone, FromRow, Debug)]
+pub struct TrashBatch {
+    pub batch_id: String,
+    pub project_id: String,
+    pub deleted_at: String,
+    pub mode: String,
+    pub spliced_children: String, // JSON list of the children linked to the parent on splice
+}
+
+/// A trash batch with the prompts it holds, parents before children.
+#[derive(Clone, Debug)]
+pub struct TrashEntry {
+    pub batch: TrashBatch,
+    pub prompts: Vec<Prompt>,
+}
+
+async fn begin<'a, A>(conn: A) -> Result<sqlx::Transaction<'a, Sqlite>>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let tx = conn.begin().await.map_err(|err| {
+        log_error(&format!("Failed to start transaction. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to start transaction. Reason: {}", err))
+    })?;
+    Ok(tx)
+}
+
+async fn commit(tx: sqlx::Transaction<'_, Sqlite>, action: &str, batch_id: &str) -> Result<()> {
+    tx.commit().await.map_err(|err| {
+        log_error(&format!(
+            "FAILED :: COMMIT {} batch_id: [{}]",
+            action, batch_id
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to {} trash batch {}. Reason: {}",
+            action, batch_id, err
+        ))
+    })?;
+    Ok(())
+}
+
+async fn get_trash_prompts(conn: &mut SqliteConnection, batch_id: &str) -> Result<Vec<Prompt>> {
+    let prompts = sqlx::query_as::<_, Prompt>(
+        "SELECT prompt_id, project_id, content, output, prev_prompt_id
+         FROM prompt_trash
+         WHERE batch_id = $1
+         ORDER BY position;",
+    )
+    .bind(batch_id)
+    .fetch_all(conn)
+    .await
+    .map_err(|err| {
+        log_error(&format!(
+            "Failed to get prompts of trash batch {}. Reason: {}",
+            batch_id, err
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to get prompts of trash batch {}. Reason: {}",
+            batch_id, err
+        ))
+    })?;
+
+    Ok(prompts)
+}
+
+/// Moves a prompt into the trash.
+///
+/// With `DeleteMode::Splice` only `prompt` is trashed and its children are linked
+/// to its parent. With `DeleteMode::Subtree` all of its descendants are trashed too.
+///
+/// # Returns:
+/// - `Ok(TrashEntry)` with the new trash batch and the prompts it holds.
+pub async fn trash_prompt<'a, A>(conn: A, prompt: &Prompt, mode: DeleteMode) -> Result<TrashEntry>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut tx = begin(conn).await?;
+
+    let prompts = get_prompts(&mut *tx, &prompt.project_id).await?;
+    let mut trashed = vec![prompt.clone()];
+    let mut spliced: Vec<String> = vec![];
+    match mode {
+        DeleteMode::Splice => {
+            spliced = prompts
+                .iter()
+                .filter(|p| p.prev_prompt_id == prompt.prompt_id)
+                .map(|p| p.prompt_id.clone())
+                .collect();
+        }
+        DeleteMode::Subtree => trashed.extend(prompt_descendants(&prompts, prompt)),
+    }
+
+    let batch = TrashBatch {
+        batch_id: Uuid::new_v4().to_string(),
+        project_id: prompt.project_id.clone(),
+        deleted_at: Local::now().to_rfc3339(),
+        mode: mode.name().to_string(),
+        spliced_children: serde_json::to_string(&spliced)?,
+    };
+
+    sqlx::query(
+        "INSERT INTO trash_batches (batch_id, project_id, deleted_at, mode, spliced_children)
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(&batch.batch_id)
+    .bind(&batch.project_id)
+    .bind(&batch.deleted_at)
+    .bind(&batch.mode)
+    .bind(&batch.spliced_children)
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| {
+        log_error(&format!(
+            "FAILED :: INSERT batch_id: [{}], error: {}",
+            batch.batch_id, err
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to create trash batch for prompt {}. Reason: {}",
+            prompt.prompt_id, err
+        ))
+    })?;
+
+    for (position, p) in trashed.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO prompt_trash
+                (batch_id, position, prompt_id, project_id, content, output, prev_prompt_id)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(&batch.batch_id)
+        .bind(position as i64)
+        .bind(&p.prompt_id)
+        .bind(&p.project_id)
+        .bind(&p.content)
+        .bind(&p.output)
+        .bind(&p.prev_prompt_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| {
+            log_error(&format!(
+                "FAILED :: INSERT trashed prompt_id: [{}], error: {}",
+                p.prompt_id, err
+            ));
+            AppError::DatabaseError(format!(
+                "Failed to move prompt {} to the trash. Reason: {}",
+                p.prompt_id, err
+            ))
+        })?;
+        delete_module(&mut *tx, "prompts", "prompt_id", &p.prompt_id).await?;
+    }
+
+    if mode == DeleteMode::Splice {
+        update_prompt(
+            &mut *tx,
+            "prev_prompt_id",
+            &prompt.prev_prompt_id,
+            "prev_prompt_id",
+            &prompt.prompt_id,
+        )
+        .await?;
+    }
+
+    commit(tx, "create", &batch.batch_id).await?;
+    log_info(&format!(
+        "Moved {} prompts to trash batch {} ({})",
+        trashed.len(),
+        batch.batch_id,
+        batch.mode
+    ));
+
+    Ok(TrashEntry {
+        batch,
+        prompts: trashed,
+    })
+}
+
+/// Lists the trash of a project, most recently deleted first.
+pub async fn get_trash<'a, A>(conn: A, project_id: &str) -> Result<Vec<TrashEntry>>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut conn = conn.acquire().await.map_err(|err| {
+        log_error(&format!("Failed to acquire connection. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to acquire connection. Reason: {}", err))
+    })?;
+
+    let batches = sqlx::query_as::<_, TrashBatch>(
+        "SELECT * FROM trash_batches
+         WHERE project_id = $1
+         ORDER BY deleted_at DESC;",
+    )
+    .bind(project_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|err| {
+        log_error(&format!(
+            "Failed to get trash for project_id {}. Reason: {}",
+            project_id, err
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to get trash for project_id {}. Reason: {}",
+            project_id, err
+        ))
+    })?;
+
+    let mut entries = vec![];
+    for batch in batches {
+        let prompts = get_trash_prompts(&mut conn, &batch.batch_id).await?;
+        entries.push(TrashEntry { batch, prompts });
+    }
+
+    Ok(entries)
+}
+
+/// Puts the prompts of a trash batch back into the prompt tree.
+///
+/// When the parent of the batch was deleted in the meantime, the restored prompts
+/// start a new branch at the project root. Spliced children that still hang under
+/// the former parent are linked back to the restored prompt.
+///
+/// # Returns:
+/// - `Ok(Vec<Prompt>)` with the restored prompts.
+/// - `AppError::DatabaseError` if the batch does not exist.
+pub async fn restore_trash<'a, A>(conn: A, batch_id: &str) -> Result<Vec<Prompt>>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut tx = begin(conn).await?;
+
+    let batch = sqlx::query_as::<_, TrashBatch>("SELECT * FROM trash_batches WHERE batch_id = $1;")
+        .bind(batch_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|err| {
+            log_error(&format!(
+                "Failed to get trash batch {}. Reason: {}",
+                batch_id, err
+            ));
+            AppError::DatabaseError(format!(
+                "Failed to get trash batch {}. Reason: {}",
+                batch_id, err
+            ))
+        })?
+        .ok_or_else(|| AppError::DatabaseError(format!("Trash batch {} not found", batch_id)))?;
+
+    let mut prompts = get_trash_prompts(&mut tx, batch_id).await?;
+    let existing: HashSet<String> = get_prompts(&mut *tx, &batch.project_id)
+        .await?
+        .into_iter()
+        .map(|p| p.prompt_id)
+        .collect();
+
+    // The first prompt of a batch is the one that was deleted
+    let Some(root) = prompts.first_mut() else {
+        return Err(AppError::DatabaseError(format!("Trash batch {} is empty", batch_id)).into());
+    };
+    let former_parent = root.prev_prompt_id.clone();
+    if former_parent != batch.project_id && !existing.contains(&former_parent) {
+        log_info(&format!(
+            "Parent of restored prompt {} is gone, restoring it at the project root",
+            root.prompt_id
+        ));
+        root.prev_prompt_id = batch.project_id.clone();
+    }
+    let root_id = root.prompt_id.clone();
+
+    for p in prompts.iter() {
+        sqlx::query(
+            "INSERT INTO prompts (prompt_id, project_id, prev_prompt_id, content, output)
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(&p.prompt_id)
+        .bind(&p.project_id)
+        .bind(&p.prev_prompt_id)
+        .bind(&p.content)
+        .bind(&p.output)
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| {
+            log_error(&format!(
+                "FAILED :: RESTORE prompt_id: [{}], error: {}",
+                p.prompt_id, err
+            ));
+            AppError::DatabaseError(format!(
+                "Failed to restore prompt {}. Reason: {}",
+                p.prompt_id, err
+            ))
+        })?;
+    }
+
+    let spliced: Vec<String> = serde_json::from_str(&batch.spliced_children)?;
+    for child in spliced.iter() {
+        sqlx::query(
+            "UPDATE prompts SET prev_prompt_id = $1
+             WHERE prompt_id = $2 AND prev_prompt_id = $3",
+        )
+        .bind(&root_id)
+        .bind(child)
+        .bind(&former_parent)
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| {
+            log_error(&format!("FAILED :: UPDATE prompt_id: [{}]", child));
+            AppError::DatabaseError(format!(
+                "Failed to link prompt {} to restored prompt {}. Reason: {}",
+                child, root_id, err
+            ))
+        })?;
+    }
+
+    delete_module(&mut *tx, "prompt_trash", "batch_id", batch_id).await?;
+    delete_module(&mut *tx, "trash_batches", "batch_id", batch_id).await?;
+    commit(tx, "restore", batch_id).await?;
+    log_info(&format!(
+        "Restored {} prompts from trash batch {}",
+        prompts.len(),
+        batch_id
+    ));
+
+    Ok(prompts)
+}
+
+/// Deletes the tags, stars, branch labels and merge parents of the prompts held by the trash
+/// batches whose `column` equals `value`, and their rows as the parent of a merge.
+async fn delete_trashed_marks(
+    conn: &mut SqliteConnection,
+    column: &str,
+    value: &str,
+) -> Result<()> {
+    // A merge also loses the parents that were trashed
+    for (table, key) in [
+        ("prompt_tags", "prompt_id"),
+        ("prompt_stars", "prompt_id"),
+        ("branch_labels", "prompt_id"),
+        ("prompt_parents", "prompt_id"),
+        ("prompt_parents", "parent_id"),
+    ] {
+        let query = format!(
+            "DELETE FROM {} WHERE {} IN (
+                 SELECT prompt_id FROM prompt_trash WHERE batch_id IN (
+                     SELECT batch_id FROM trash_batches WHERE {} = $1
+                 )
+             )",
+            table, key, column
+        );
+        sqlx::query(&query)
+            .bind(value)
+            .execute(&mut *conn)
+            .await
+            .map_err(|err| {
+                log_error(&format!(
+                    "Failed to delete {} of trashed prompts, {} = [{}]: {}",
+                    table, column, value, err
+                ));
+                AppError::DatabaseError(format!(
+                    "Failed to delete {} of trashed prompts. Reason: {}",
+                    table, err
+                ))
+            })?;
+    }
+    Ok(())
+}
+
+/// Permanently deletes a trash batch and its prompts.
+pub async fn purge_trash<'a, A>(conn: A, batch_id: &str) -> Result<()>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut tx = begin(conn).await?;
+    delete_trashed_marks(&mut tx, "batch_id", batch_id).await?;
+    delete_module(&mut *tx, "prompt_trash", "batch_id", batch_id).await?;
+    delete_module(&mut *tx, "trash_batches", "batch_id", batch_id).await?;
+    commit(tx, "purge", batch_id).await?;
+    log_info(&format!("Purged trash batch {}", batch_id));
+
+    Ok(())
+}
+
+/// Permanently deletes the whole trash of a project.
+pub async fn empty_trash<'a, A>(conn: A, project_id: &str) -> Result<()>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut tx = begin(conn).await?;
+    delete_trashed_marks(&mut tx, "project_id", project_id).await?;
+    sqlx::query(
+        "DELETE FROM prompt_trash WHERE batch_id IN (
+             SELECT batch_id FROM trash_batches WHERE project_id = $1
+         )",
+    )
+    .bind(project_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| {
+        log_error(&format!(
+            "Failed to empty trash of project_id {}. Reason: {}",
+            project_id, err
+        ));
+        AppError::DatabaseError(format!(
+            "Failed to empty trash of project_id {}. Reason: {}",
+            project_id, err
+        ))
+    })?;
+    delete_module(&mut *tx, "trash_batches", "project_id", project_id).await?;
+    commit(tx, "empty", project_id).await?;
+    log_info(&format!("Emptied trash of project {}", project_id));
+
+    Ok(())
+}
+
+/// One-line summary of a trash batch, used in the trash picker.
+pub fn format_trash_entry(entry: &TrashEntry) -> String {
+    let deleted_at = chrono::DateTime::parse_from_rfc3339(&entry.batch.deleted_at)
+        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| entry.batch.deleted_at.clone());
+    let first_line = entry
+        .prompts
+        .first()
+        .and_then(|p| p.content.lines().find(|l| !l.trim().is_empty()))
+        .unwrap_or("")
+        .trim();
+
+    format!(
+        " -[ {} ]- {} prompt(s), {} :: {}",
+        deleted_at,
+        entry.prompts.len(),
+        entry.batch.mode,
+        first_line
+    )
+}
@@ -30,36 +30,104 @@
 ///
 /// ### Navigating in the Terminal UI
 ///
-/// Once the application launches, use the following keyboard shortcuts to navigate between different operations:
+/// Legatio reopens the project, branch and prompt of your last session. Once the application launches,
+/// use the following keyboard shortcuts to navigate between different operations:
+///
+/// The project, prompt and scroll panes are lists: move through them with `[j]`/`[k]` or the arrow keys,
+/// press `[/]` to filter them and `[Enter]` to open the highlighted project or prompt. Pickers opened by
+/// the keys below start on the highlighted item and filter as you type.
+///
+/// The prompt pane is a tree: `[Left]`/`[Right]` collapse and expand a subtree or go to the parent and
+/// first reply, `[J]`/`[K]` jump between siblings and `[Space]` folds a subtree. Each prompt shows how many
+/// replies it has, and the chain leading to the current prompt is highlighted.
+///
+/// Next to the prompt tree, and next to the chain while asking the AI model, a preview renders the prompt
+/// and its output as Markdown with highlighted code blocks. `[Ctrl-d]`/`[Ctrl-u]` page through it,
+/// `[Ctrl-f]` searches it and `[n]`/`[N]` jump between the matches.
 ///
 /// - **Project Selection:**
 ///   - `[s]`: Select a project.
 ///   - `[n]`: Create a new project by selecting a folder.
 ///   - `[d]`: Delete a selected project.
+///   - `[x]`: Export a project with its scrolls and prompts into a `.legatio` bundle.
+///   - `[i]`: Import a `.legatio` bundle under a new project directory.
+///   - `[r]`: Relocate a project after its directory was moved or renamed.
+///   - `[e]`: Edit the display name, description, color and icon of a project.
 ///   - `[q]`: Quit the application.
 ///
 /// - **Prompt Management:**
 ///   - `[s]`: Select a prompt for asking the AI model.
-///   - `[d]`: Delete a selected prompt.
+///   - `[d]`: Move a prompt, alone or with its descendants, to the trash.
 ///   - `[e]`: Edit associated scrolls.
 ///   - `[p]`: Go back to project selection.
+///   - `[x]`: Export the current branch, the prompt tree or the whole project.
+///   - `[i]`: Import a JSON transcript as a new branch of the project.
+///   - `[t]`: Restore or purge deleted prompts from the trash.
+///   - `[m]`: Tag or star a prompt, or name it as the head of a branch.
+///   - `[f]`: List the starred prompts or the prompts carrying a tag.
+///   - `[h]`: Jump to a named branch head; new questions asked there advance the branch.
+///   - `[r]`: Move a prompt or subtree under another parent, copy it onto another branch or
+///     project, or detach a subtree into a new root.
+///   - `[g]`: Merge two branch heads into a new prompt that asks the AI model to reconcile them.
 ///   - `[q]`: Quit the application.
 ///
 /// - **Scroll Management:**
 ///   - `[n]`: Add a new scroll (select a file).
 ///   - `[d]`: Delete a selected scroll.
 ///   - `[a]`: Ask the AI model with the current scroll/prompt context.
-///   - `[s]`: Switch back to prompt selection.
+///   - `[b]`: Switch back to prompt selection.
 ///   - `[q]`: Quit the application.
 ///
 /// - **Asking AI Models:**
 ///   - `[a]`: Send the current prompt chain and context to the AI model.
+///   - `[w]`: Write the next question in a box inside the application; `[Ctrl-s]` sends it,
+///     `[Ctrl-o]` continues it in `$EDITOR` and `[Esc]` keeps it as a draft.
+///   - `[o]`: Write the next question in `$EDITOR`; the saved text is sent to the AI model.
 ///   - `[b]`: Go back to prompt selection.
 ///   - `[e]`: Edit associated scrolls.
 ///   - `[p]`: Change the current project.
+///   - `[x]`: Export the current branch to Markdown, HTML or JSON.
 ///   - `[y]`: Confirm an AI query.
 ///   - `[n]`: Cancel an AI query.
 ///
+/// ### Command Line
+///
+/// - `legatio`: Start the terminal UI.
+/// - `legatio fsck`: Check the database for orphaned prompts, prompt cycles, duplicate ids,
+///   missing scroll files and rows of deleted projects.
+/// - `legatio fsck --repair`: Also re-root orphans, break cycles, rename duplicate ids and
+///   delete the rows of deleted projects.
+/// - `legatio restore`: List the database snapshots with their dates and project counts.
+/// - `legatio restore <NUMBER>`: Replace the database with one of the listed snapshots.
+/// - `--db <NAME>`: Open the database `NAME`, e.g. `work` or `personal`, creating it if
+///   missing. Without it the terminal UI asks which one to open when there are several.
+/// - `--data-dir <DIR>`: Keep the databases in `DIR`. `$LEGATIO_HOME` does the same, and
+///   both default to `$XDG_DATA_HOME/legatio`.
+///
+/// The database is snapshotted on startup, before its schema is upgraded and before
+/// projects are deleted, the trash is purged or `fsck --repair` runs.
+/// `backup_retention` in `config.toml` sets how many snapshots are kept (10 by default,
+/// 0 disables them).
+///
+/// `[?]` lists every action of the current view with its key, and `[:]` opens a command
+/// palette that fuzzy-searches the actions of the view by name, including the ones
+/// without a key such as `refresh`, and runs the picked one.
+///
+/// The status bar on the last row shows errors that do not end the session, such as a
+/// missing scroll file, a failed model request or a cancelled picker, for a few seconds;
+/// `[!]` lists every notification of the session.
+///
+/// The terminal UI runs on the alternate screen and restores the terminal on exit, on a
+/// panic and on `SIGINT`, `SIGTERM`, `SIGHUP` or `SIGQUIT`; `[Ctrl-c]` quits from every
+/// view. Panics are written with a backtrace to `logs/` in the config directory, and a
+/// question left unsent or waiting for the model is kept in `drafts/` in the data
+/// directory until the project is opened again.
+///
+/// The keys of each view can be changed in the `[keys]` section of `config.toml`, e.g.
+/// `merge = "ctrl-g"` under `[keys.prompts]`. Invalid keys, two actions sharing a key and
+/// keys used to navigate a view are refused on startup, and the help text shows the
+/// active bindings.
+///
 /// ### Project Structure
 ///
 /// Each project created in `Legatio` is structured with the following components:
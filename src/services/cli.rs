@@ -0,0 +1,226 @@
+use crate::{
+    core::integrity::{check_integrity, repair_integrity},
+    services::{config::get_data_dir, search::item_selector},
+    utils::{
+        backup::{backup_database, get_backup_dir, list_snapshots, restore_snapshot},
+        db_utils::{list_databases, DEFAULT_DATABASE},
+        error::AppError,
+    },
+};
+use anyhow::Result;
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+
+pub const USAGE: &str = "\
+Usage: legatio [OPTIONS] [COMMAND]
+
+Options:
+  --data-dir <DIR>  Keep the databases in DIR instead of $LEGATIO_HOME or the
+                    XDG data directory
+  --db <NAME>       Open the database NAME, creating it if missing. Without it the
+                    terminal UI asks which database to open when there are several.
+
+Commands:
+  (none)            Start the terminal UI
+  fsck [--repair]   Check the database for orphaned prompts, cycles, duplicate ids,
+                    missing scroll files and rows of deleted projects.
+                    With --repair, fix what can be fixed automatically.
+  restore [NUMBER]  List the database snapshots, or restore the given one.
+                    The current database is snapshotted before it is replaced.
+  help              Print this message";
+
+/// What the binary was asked to do.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Tui,
+    Fsck {
+        repair: bool,
+    },
+    /// Lists the snapshots, or restores the given one (1 is the newest).
+    Restore {
+        snapshot: Option<usize>,
+    },
+    Help,
+}
+
+/// The parsed command line.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Args {
+    pub data_dir: Option<PathBuf>,
+    pub database: Option<String>,
+    pub command: Command,
+}
+
+/// Parses the command line arguments, without the program name.
+///
+/// The options may appear before or after the command.
+///
+/// # Returns:
+/// - `Ok(Args)` to run.
+/// - `AppError::ParseError` for unknown commands or flags, or options missing their value.
+pub fn parse_args<I>(args: I) -> Result<Args>
+where
+    I: IntoIterator<Item = String>,
+{
+    let unknown = |arg: &str| -> anyhow::Error {
+        AppError::ParseError(format!("Unknown argument: {}\n\n{}", arg, USAGE)).into()
+    };
+
+    let mut data_dir = None;
+    let mut database = None;
+    let mut rest = vec![];
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--data-dir" | "--db" => args.next().ok_or_else(|| {
+                AppError::ParseError(format!("{} expects a value\n\n{}", arg, USAGE))
+            })?,
+            _ => {
+                rest.push(arg);
+                continue;
+            }
+        };
+        if arg == "--data-dir" {
+            data_dir = Some(PathBuf::from(value));
+        } else {
+            database = Some(value);
+        }
+    }
+
+    Ok(Args {
+        data_dir,
+        database,
+        command: parse_command(&rest).map_err(|arg| unknown(&arg))?,
+    })
+}
+
+/// Parses the command and its flags, returning the first argument it does not know.
+fn parse_command(args: &[String]) -> Result<Command, String> {
+    match args.first().map(String::as_str) {
+        None => Ok(Command::Tui),
+        Some("help") | Some("--help") | Some("-h") => Ok(Command::Help),
+        Some("fsck") => {
+            let mut repair = false;
+            for arg in args.iter().skip(1) {
+                match arg.as_str() {
+                    "--repair" => repair = true,
+                    other => return Err(other.to_string()),
+                }
+            }
+            Ok(Command::Fsck { repair })
+        }
+        Some("restore") => match &args[1..] {
+            [] => Ok(Command::Restore { snapshot: None }),
+            [number] => match number.parse::<usize>() {
+                Ok(snapshot) if snapshot > 0 => Ok(Command::Restore {
+                    snapshot: Some(snapshot),
+                }),
+                _ => Err(number.to_string()),
+            },
+            [_, other, ..] => Err(other.to_string()),
+        },
+        Some(other) => Err(other.to_string()),
+    }
+}
+
+/// Picks the database to open when none was given on the command line.
+///
+/// # Returns:
+/// - `Ok(Some(String))` with the only database, or the one chosen by the user.
+/// - `Ok(None)` when the user cancelled the selection.
+pub fn select_database() -> Result<Option<String>> {
+    let databases = list_databases(&get_data_dir()?)?;
+    if databases.len() <= 1 {
+        return Ok(Some(
+            databases
+                .into_iter()
+                .next()
+                .unwrap_or(String::from(DEFAULT_DATABASE)),
+        ));
+    }
+    item_selector(databases)
+}
+
+/// Runs the integrity check and prints its report, repairing the issues when asked.
+/// The database is snapshotted before it is repaired, keeping `retention` snapshots.
+pub async fn run_fsck(pool: &SqlitePool, repair: bool, retention: usize) -> Result<()> {
+    let issues = check_integrity(pool).await?;
+    if issues.is_empty() {
+        println!("No issues found.");
+        return Ok(());
+    }
+
+    for issue in issues.iter() {
+        println!("- {}", issue);
+    }
+    let repairable = issues.iter().filter(|i| i.is_repairable()).count();
+    println!(
+        "\n{} issue(s) found, {} can be repaired automatically.",
+        issues.len(),
+        repairable
+    );
+
+    if repair {
+        backup_database(pool, "fsck-repair", retention).await?;
+        let summary = repair_integrity(pool, &issues).await?;
+        println!(
+            "Repaired: {} prompt(s) re-rooted, {} id(s) renamed, {} row(s) deleted.",
+            summary.rerooted, summary.renamed, summary.deleted
+        );
+    } else if repairable > 0 {
+        println!("Run `legatio fsck --repair` to repair them.");
+    }
+
+    Ok(())
+}
+
+/// Prints the snapshots of the database at `db_file`, newest first, or restores
+/// the `snapshot`-th one.
+///
+/// Must run before the database pool is opened.
+pub async fn run_restore(db_file: &Path, snapshot: Option<usize>) -> Result<()> {
+    let backup_dir = get_backup_dir(db_file)?;
+    let snapshots = list_snapshots(&backup_dir).await?;
+
+    let Some(number) = snapshot else {
+        if snapshots.is_empty() {
+            println!("No snapshots in {}.", backup_dir.to_string_lossy());
+            return Ok(());
+        }
+        for (idx, snapshot) in snapshots.iter().enumerate() {
+            let projects = snapshot
+                .projects
+                .map_or(String::from("unreadable"), |n| format!("{} project(s)", n));
+            println!(
+                "{:>3}  {}  {:<16}  {}",
+                idx + 1,
+                snapshot.created_at.format("%Y-%m-%d %H:%M:%S"),
+                projects,
+                snapshot.reason
+            );
+        }
+        println!("\nRun `legatio restore <NUMBER>` to restore a snapshot.");
+        return Ok(());
+    };
+
+    let Some(chosen) = snapshots.get(number - 1) else {
+        return Err(AppError::ParseError(format!(
+            "There is no snapshot {}, run `legatio restore` to list them.",
+            number
+        ))
+        .into());
+    };
+    let previous = restore_snapshot(&chosen.path, db_file, &backup_dir).await?;
+    println!(
+        "Restored the snapshot of {}.",
+        chosen.created_at.format("%Y-%m-%d %H:%M:%S")
+    );
+    if let Some(previous) = previous {
+        println!(
+            "The replaced database was saved to {}.",
+            previous.to_string_lossy()
+        );
+    }
+
+    Ok(())
+}
@@ -1,21 +1,51 @@
 use anyhow::Result;
 use ask_ai::config::AiConfig;
 use ask_ai::config::Framework;
-use dirs_next::config_dir;
+use dirs_next::{config_dir, data_dir};
 use serde::Deserialize;
 use serde::Serialize;
 use std::fs;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
+use std::sync::OnceLock;
 
-use crate::utils::{error::AppError, logger::log_error};
+use crate::{
+    services::keys::KeyConfig,
+    utils::{db_utils::move_legacy_database, error::AppError, logger::log_error},
+};
 
 #[derive(Debug, Deserialize, Serialize)] // Add Serialize to support serialization
 pub struct UserConfig {
     pub ai_conf: AiConfig,
     pub theme: String,
     pub ask_conf: bool,
+    /// Number of database snapshots to keep, 0 disables the backups.
+    #[serde(default = "default_backup_retention")]
+    pub backup_retention: usize,
+    /// Keys replacing the default bindings, see [`KeyConfig`].
+    #[serde(default, skip_serializing_if = "KeyConfig::is_empty")]
+    pub keys: KeyConfig,
+}
+
+fn default_backup_retention() -> usize {
+    10
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        UserConfig {
+            ai_conf: AiConfig {
+                llm: Framework::OpenAI,
+                model: String::from("chatgpt-4o-latest"),
+                max_token: None,
+            },
+            theme: String::from("Tokyo Storm"),
+            ask_conf: true,
+            backup_retention: default_backup_retention(),
+            keys: KeyConfig::default(),
+        }
+    }
 }
 
 /// Get the Legatio configuration directory inside `$HOME/.config/legatio`.
@@ -43,6 +73,48 @@ pub fn get_config_dir() -> Result<PathBuf, AppError> {
     Ok(conf_dir)
 }
 
+/// Data directory given on the command line with `--data-dir`.
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Overrides the data directory for the rest of the run.
+pub fn set_data_dir(path: PathBuf) {
+    let _ = DATA_DIR.set(path);
+}
+
+/// Get the Legatio data directory holding the databases and their backups.
+///
+/// `--data-dir` takes precedence over `$LEGATIO_HOME`, which takes precedence over
+/// `$XDG_DATA_HOME/legatio`. Creates the directory if it doesn’t exist.
+pub fn get_data_dir() -> Result<PathBuf, AppError> {
+    let data_dir = if let Some(dir) = DATA_DIR.get() {
+        dir.clone()
+    } else if let Some(dir) = std::env::var_os("LEGATIO_HOME").filter(|d| !d.is_empty()) {
+        PathBuf::from(dir)
+    } else {
+        let Some(mut dir) = data_dir() else {
+            return Err(AppError::FileError(String::from(
+                "Could not find the data directory",
+            )));
+        };
+        dir.push("legatio");
+        // Older releases kept the database next to the config
+        move_legacy_database(&get_config_dir()?, &dir)?;
+        dir
+    };
+
+    if !data_dir.exists() {
+        fs::create_dir_all(&data_dir).map_err(|e| {
+            AppError::FileError(format!(
+                "Failed to create data directory {}: {}",
+                &data_dir.to_string_lossy(),
+                e
+            ))
+        })?;
+    }
+
+    Ok(data_dir)
+}
+
 /// Reads the `config.toml` file from the Legatio config directory and parses it into `UserConfig`.
 pub fn read_config() -> Result<UserConfig> {
     let config_dir = get_config_dir()?;
@@ -124,16 +196,7 @@ pub fn check_config_files() -> Result<(), AppError> {
     // Copy default config if missing in config dir
     if !config_dir.join("config.toml").exists() {
         // Default config for user
-        let default_config = UserConfig {
-            ai_conf: AiConfig {
-                llm: Framework::OpenAI,
-                model: String::from("chatgpt-4o-latest"),
-                max_token: None,
-            },
-            theme: String::from("Tokyo Storm"),
-            ask_conf: true,
-        };
-        store_config(&default_config).expect("Failed to store default config");
+        store_config(&UserConfig::default()).expect("Failed to store default config");
     };
 
     // Copy default themes if missing in config dir
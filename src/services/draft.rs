@@ -0,0 +1,139 @@
+use std::{fs, path::PathBuf, sync::Mutex};
+
+use anyhow::Result;
+
+use crate::{
+    services::config::get_data_dir,
+    utils::{error::AppError, logger::log_error},
+};
+
+/// Project and text of the question waiting for the model, if any.
+static IN_FLIGHT: Mutex<Option<(String, String)>> = Mutex::new(None);
+
+/// Marks a question as sent to the model until dropped, so a crash or a signal
+/// meanwhile saves it as a draft.
+pub struct InFlight;
+
+impl InFlight {
+    pub fn start(project_id: &str, question: &str) -> InFlight {
+        if let Ok(mut in_flight) = IN_FLIGHT.lock() {
+            *in_flight = Some((project_id.to_string(), question.to_string()));
+        }
+        InFlight
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if let Ok(mut in_flight) = IN_FLIGHT.lock() {
+            *in_flight = None;
+        }
+    }
+}
+
+/// Saves the question waiting for the model as the draft of its project.
+///
+/// Meant for the panic hook and the signal handlers, so it never blocks.
+///
+/// # Returns:
+/// - The path of the draft, `None` if no question was in flight or it could not be saved.
+pub fn save_in_flight() -> Option<PathBuf> {
+    let (project_id, question) = IN_FLIGHT.try_lock().ok()?.take()?;
+    save_draft(&project_id, &question).ok()
+}
+
+/// Path of the draft of a project, inside `drafts/` in the data directory.
+pub fn draft_path(project_id: &str) -> Result<PathBuf> {
+    let drafts_dir = get_data_dir()?.join("drafts");
+    fs::create_dir_all(&drafts_dir).map_err(|err| {
+        log_error(&format!(
+            "Failed to create directory {}. Reason: {}",
+            drafts_dir.display(),
+            err
+        ));
+        AppError::FileError(format!(
+            "Failed to create directory {}. Reason: {}",
+            drafts_dir.display(),
+            err
+        ))
+    })?;
+    Ok(drafts_dir.join(format!("{}.md", project_id)))
+}
+
+/// Keeps an unsent question for the next time the project is opened.
+///
+/// # Returns:
+/// - The path of the draft.
+pub fn save_draft(project_id: &str, text: &str) -> Result<PathBuf> {
+    let path = draft_path(project_id)?;
+    fs::write(&path, text).map_err(|err| {
+        log_error(&format!(
+            "Failed to write draft {}. Reason: {}",
+            path.display(),
+            err
+        ));
+        AppError::FileError(format!(
+            "Failed to write draft {}. Reason: {}",
+            path.display(),
+            err
+        ))
+    })?;
+    Ok(path)
+}
+
+/// Reads and removes the draft of a project.
+///
+/// # Returns:
+/// - `Ok(Some(String))` with the draft, `Ok(None)` if the project has none.
+pub fn take_draft(project_id: &str) -> Result<Option<String>> {
+    let path = draft_path(project_id)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = fs::read_to_string(&path).map_err(|err| {
+        log_error(&format!(
+            "Failed to read draft {}. Reason: {}",
+            path.display(),
+            err
+        ));
+        AppError::FileError(format!(
+            "Failed to read draft {}. Reason: {}",
+            path.display(),
+            err
+        ))
+    })?;
+    fs::remove_file(&path).map_err(|err| {
+        log_error(&format!(
+            "Failed to remove draft {}. Reason: {}",
+            path.display(),
+            err
+        ));
+        AppError::FileError(format!(
+            "Failed to remove draft {}. Reason: {}",
+            path.display(),
+            err
+        ))
+    })?;
+    Ok(Some(text))
+}
+
+/// Removes the draft of a project, as when the project is deleted.
+pub fn remove_draft(project_id: &str) -> Result<()> {
+    let path = draft_path(project_id)?;
+    if !path.exists() {
+        return Ok(());
+    }
+    fs::remove_file(&path).map_err(|err| {
+        log_error(&format!(
+            "Failed to remove draft {}. Reason: {}",
+            path.display(),
+            err
+        ));
+        AppError::FileError(format!(
+            "Failed to remove draft {}. Reason: {}",
+            path.display(),
+            err
+        ))
+    })?;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use crate::{
+    services::{
+        list::mark_screen_dirty,
+        terminal::{resume_terminal, suspend_terminal},
+    },
+    utils::{error::AppError, logger::log_error},
+};
+use anyhow::Result;
+use std::{env, fs, io::Write, process::Command};
+
+/// The editor command from `$VISUAL` or `$EDITOR`, falling back to `vi`.
+pub fn editor_command() -> String {
+    env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| String::from("vi"))
+}
+
+/// Opens `content` in the user's editor and returns the edited text.
+///
+/// # Returns:
+/// - `Ok(Some(String))` with the edited text.
+/// - `Ok(None)` if the editor exited with an error or the text was not changed.
+pub fn edit_text(content: &str, extension: &str) -> Result<Option<String>> {
+    Ok(open_in_editor(content, extension)?.filter(|edited| edited != content))
+}
+
+/// Opens `content` in the user's editor and returns the saved text, changed or not.
+///
+/// The text is written to a temporary file with the given extension so the
+/// editor can pick the right syntax. The terminal is handed over while the editor runs.
+///
+/// # Returns:
+/// - `Ok(Some(String))` with the saved text.
+/// - `Ok(None)` if the editor exited with an error.
+pub fn open_in_editor(content: &str, extension: &str) -> Result<Option<String>> {
+    let mut file = tempfile::Builder::new()
+        .prefix("legatio_")
+        .suffix(&format!(".{}", extension))
+        .tempfile()
+        .map_err(|err| {
+            log_error(&format!("Failed to create temporary file. Reason: {}", err));
+            AppError::FileError(format!("Failed to create temporary file. Reason: {}", err))
+        })?;
+    file.write_all(content.as_bytes()).map_err(|err| {
+        log_error(&format!("Failed to write temporary file. Reason: {}", err));
+        AppError::FileError(format!("Failed to write temporary file. Reason: {}", err))
+    })?;
+
+    let editor = editor_command();
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+
+    suspend_terminal()?;
+    let status = Command::new(program).args(parts).arg(file.path()).status();
+    mark_screen_dirty();
+    resume_terminal()?;
+
+    let status = status.map_err(|err| {
+        log_error(&format!(
+            "Failed to start editor {}. Reason: {}",
+            editor, err
+        ));
+        AppError::UnexpectedError(format!(
+            "Failed to start editor {}. Reason: {}",
+            editor, err
+        ))
+    })?;
+    if !status.success() {
+        return Ok(None);
+    }
+
+    let edited = fs::read_to_string(file.path()).map_err(|err| {
+        log_error(&format!("Failed to read temporary file. Reason: {}", err));
+        AppError::FileError(format!("Failed to read temporary file. Reason: {}", err))
+    })?;
+
+    Ok(Some(edited))
+}
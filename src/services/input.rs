@@ -0,0 +1,176 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Position, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+    Frame,
+};
+
+/// A multi-line text box, used to write the next question.
+///
+/// `Enter` starts a new line; sending the text, opening it in `$EDITOR` and
+/// leaving the box are bound under `[keys.question]`.
+#[derive(Debug)]
+pub struct TextInput {
+    lines: Vec<String>,
+    /// Line of the cursor.
+    row: usize,
+    /// Character of the cursor in its line.
+    col: usize,
+    /// First line shown.
+    scroll: usize,
+}
+
+impl Default for TextInput {
+    fn default() -> TextInput {
+        TextInput {
+            lines: vec![String::new()],
+            row: 0,
+            col: 0,
+            scroll: 0,
+        }
+    }
+}
+
+impl TextInput {
+    /// The text, lines joined with `\n`.
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Replaces the text, putting the cursor at its end.
+    pub fn set_text(&mut self, text: &str) {
+        self.lines = text.split('\n').map(String::from).collect();
+        self.row = self.lines.len() - 1;
+        self.col = self.lines[self.row].chars().count();
+    }
+
+    pub fn clear(&mut self) {
+        *self = TextInput::default();
+    }
+
+    /// Whether the text is empty or only whitespace.
+    pub fn is_blank(&self) -> bool {
+        self.lines.iter().all(|l| l.trim().is_empty())
+    }
+
+    /// The line and character of the cursor.
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    /// Handles a key press, returning whether it changed the text or the cursor.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char(c) if !control => self.insert(&c.to_string()),
+            KeyCode::Tab => self.insert("    "),
+            KeyCode::Enter => {
+                let rest = self.split_off();
+                self.lines.insert(self.row + 1, rest);
+                self.row += 1;
+                self.col = 0;
+            }
+            KeyCode::Backspace => {
+                if self.col > 0 {
+                    self.col -= 1;
+                    let index = self.byte_index();
+                    self.lines[self.row].remove(index);
+                } else if self.row > 0 {
+                    let line = self.lines.remove(self.row);
+                    self.row -= 1;
+                    self.col = self.lines[self.row].chars().count();
+                    self.lines[self.row].push_str(&line);
+                }
+            }
+            KeyCode::Delete => {
+                if self.col < self.line_len() {
+                    let index = self.byte_index();
+                    self.lines[self.row].remove(index);
+                } else if self.row + 1 < self.lines.len() {
+                    let line = self.lines.remove(self.row + 1);
+                    self.lines[self.row].push_str(&line);
+                }
+            }
+            KeyCode::Left => {
+                if self.col > 0 {
+                    self.col -= 1;
+                } else if self.row > 0 {
+                    self.row -= 1;
+                    self.col = self.line_len();
+                }
+            }
+            KeyCode::Right => {
+                if self.col < self.line_len() {
+                    self.col += 1;
+                } else if self.row + 1 < self.lines.len() {
+                    self.row += 1;
+                    self.col = 0;
+                }
+            }
+            KeyCode::Up if self.row > 0 => {
+                self.row -= 1;
+                self.col = self.col.min(self.line_len());
+            }
+            KeyCode::Down if self.row + 1 < self.lines.len() => {
+                self.row += 1;
+                self.col = self.col.min(self.line_len());
+            }
+            KeyCode::Home => self.col = 0,
+            KeyCode::End => self.col = self.line_len(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Renders the text inside `block` and places the terminal cursor.
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, block: Block, style: Style) {
+        let inner = block.inner(area);
+        let height = (inner.height as usize).max(1);
+        // Keep the cursor in view
+        if self.row < self.scroll {
+            self.scroll = self.row;
+        } else if self.row >= self.scroll + height {
+            self.scroll = self.row + 1 - height;
+        }
+        let before: String = self.lines[self.row].chars().take(self.col).collect();
+        let x = Span::raw(before).width() as u16;
+        let h_scroll = x.saturating_sub(inner.width.saturating_sub(1));
+
+        let lines: Vec<Line> = self.lines.iter().map(|l| Line::from(l.as_str())).collect();
+        let paragraph = Paragraph::new(lines)
+            .block(block)
+            .style(style)
+            .scroll((self.scroll as u16, h_scroll));
+        frame.render_widget(paragraph, area);
+        frame.set_cursor_position(Position::new(
+            inner.x + x - h_scroll,
+            inner.y + (self.row - self.scroll) as u16,
+        ));
+    }
+
+    fn line_len(&self) -> usize {
+        self.lines[self.row].chars().count()
+    }
+
+    /// Byte offset of the cursor in its line.
+    fn byte_index(&self) -> usize {
+        let line = &self.lines[self.row];
+        line.char_indices()
+            .nth(self.col)
+            .map_or(line.len(), |(i, _)| i)
+    }
+
+    fn insert(&mut self, text: &str) {
+        let index = self.byte_index();
+        self.lines[self.row].insert_str(index, text);
+        self.col += text.chars().count();
+    }
+
+    /// Cuts the current line at the cursor, returning the text after it.
+    fn split_off(&mut self) -> String {
+        let index = self.byte_index();
+        self.lines[self.row].split_off(index)
+    }
+}
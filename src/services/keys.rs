@@ -0,0 +1,493 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{error::AppError, logger::log_error};
+
+/// An action the user can bind to a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InputEvent {
+    Select,
+    New,
+    Delete,
+    SwitchBranch,
+    ChangeProject,
+    EditScrolls,
+    AskModel,
+    Export,
+    Import,
+    Relocate,
+    EditProject,
+    Trash,
+    Mark,
+    FilterTag,
+    BranchHeads,
+    Reorganize,
+    Merge,
+    WriteQuestion,
+    OpenEditor,
+    Submit,
+    Quit,
+    Confirm,
+    Cancel,
+    Help,
+    Palette,
+    Notifications,
+    Refresh,
+    NoOp,
+}
+
+impl InputEvent {
+    /// The name of the action in the `[keys]` section of `config.toml`.
+    pub fn name(self) -> &'static str {
+        match self {
+            InputEvent::Select => "select",
+            InputEvent::New => "new",
+            InputEvent::Delete => "delete",
+            InputEvent::SwitchBranch => "switch_branch",
+            InputEvent::ChangeProject => "change_project",
+            InputEvent::EditScrolls => "edit_scrolls",
+            InputEvent::AskModel => "ask_model",
+            InputEvent::Export => "export",
+            InputEvent::Import => "import",
+            InputEvent::Relocate => "relocate",
+            InputEvent::EditProject => "edit_project",
+            InputEvent::Trash => "trash",
+            InputEvent::Mark => "mark",
+            InputEvent::FilterTag => "filter_tag",
+            InputEvent::BranchHeads => "branch_heads",
+            InputEvent::Reorganize => "reorganize",
+            InputEvent::Merge => "merge",
+            InputEvent::WriteQuestion => "write_question",
+            InputEvent::OpenEditor => "open_editor",
+            InputEvent::Submit => "submit",
+            InputEvent::Quit => "quit",
+            InputEvent::Confirm => "confirm",
+            InputEvent::Cancel => "cancel",
+            InputEvent::Help => "help",
+            InputEvent::Palette => "palette",
+            InputEvent::Notifications => "notifications",
+            InputEvent::Refresh => "refresh",
+            InputEvent::NoOp => "none",
+        }
+    }
+}
+
+/// A state of the application with its own key bindings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyScope {
+    Projects,
+    Prompts,
+    Ask,
+    Scrolls,
+    Confirm,
+    Question,
+}
+
+impl KeyScope {
+    pub const ALL: [KeyScope; 6] = [
+        KeyScope::Projects,
+        KeyScope::Prompts,
+        KeyScope::Ask,
+        KeyScope::Scrolls,
+        KeyScope::Confirm,
+        KeyScope::Question,
+    ];
+
+    /// The name of the table of the state under `[keys]`.
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyScope::Projects => "projects",
+            KeyScope::Prompts => "prompts",
+            KeyScope::Ask => "ask",
+            KeyScope::Scrolls => "scrolls",
+            KeyScope::Confirm => "confirm",
+            KeyScope::Question => "question",
+        }
+    }
+
+    /// Keys used by the list, tree or preview of the state and `ctrl-c`, which
+    /// quits, separated by spaces. Actions cannot take them.
+    fn reserved(self) -> &'static str {
+        match self {
+            KeyScope::Projects | KeyScope::Scrolls => {
+                "j k / enter esc up down pageup pagedown home end backspace ctrl-p ctrl-n ctrl-c"
+            }
+            KeyScope::Prompts => {
+                "j k J K / enter esc space up down left right pageup pagedown home end \
+                 backspace ctrl-p ctrl-n ctrl-d ctrl-u ctrl-f n N ctrl-c"
+            }
+            KeyScope::Ask => "esc ctrl-d ctrl-u ctrl-f n N ctrl-c",
+            KeyScope::Confirm => "ctrl-c",
+            KeyScope::Question => "enter tab backspace delete up down left right home end ctrl-c",
+        }
+    }
+
+    /// Whether `chord` is taken by the list, tree, preview or text box of the state.
+    fn reserves(self, chord: KeyChord) -> bool {
+        // The question box types every character without ctrl or alt
+        let typed = matches!(chord.code, KeyCode::Char(_))
+            && !chord
+                .modifiers
+                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        (self == KeyScope::Question && typed)
+            || self
+                .reserved()
+                .split_whitespace()
+                .any(|r| KeyChord::parse_text(r) == Ok(chord))
+    }
+
+    /// Default bindings of the state with their help text, in display order.
+    /// Actions with the key `"none"` are only run from the command palette.
+    fn defaults(self) -> &'static [(InputEvent, &'static str, &'static str)] {
+        match self {
+            KeyScope::Projects => PROJECT_KEYS,
+            KeyScope::Prompts => PROMPT_KEYS,
+            KeyScope::Ask => ASK_KEYS,
+            KeyScope::Scrolls => SCROLL_KEYS,
+            KeyScope::Confirm => CONFIRM_KEYS,
+            KeyScope::Question => QUESTION_KEYS,
+        }
+    }
+}
+
+const PROJECT_KEYS: &[(InputEvent, &str, &str)] = &[
+    (InputEvent::Help, "?", "Help"),
+    (InputEvent::Palette, ":", "Commands"),
+    (InputEvent::Notifications, "!", "Notifications"),
+    (InputEvent::Select, "s", "Select Project"),
+    (InputEvent::New, "n", "New Project"),
+    (InputEvent::Delete, "d", "Delete Project"),
+    (InputEvent::Export, "x", "Export Bundle"),
+    (InputEvent::Import, "i", "Import Bundle"),
+    (InputEvent::Relocate, "r", "Relocate Project"),
+    (InputEvent::EditProject, "e", "Edit Project"),
+    (InputEvent::Refresh, "none", "Reload from the Database"),
+    (InputEvent::Quit, "q", "Quit"),
+];
+
+const PROMPT_KEYS: &[(InputEvent, &str, &str)] = &[
+    (InputEvent::Help, "?", "Help"),
+    (InputEvent::Palette, ":", "Commands"),
+    (InputEvent::Notifications, "!", "Notifications"),
+    (InputEvent::Select, "s", "Select Prompt"),
+    (InputEvent::Delete, "d", "Delete Prompt"),
+    (InputEvent::EditScrolls, "e", "Edit Scrolls"),
+    (InputEvent::ChangeProject, "p", "Change Project"),
+    (InputEvent::Export, "x", "Export"),
+    (InputEvent::Import, "i", "Import Transcript"),
+    (InputEvent::Trash, "t", "Trash"),
+    (InputEvent::Mark, "m", "Tag, Star or Name Prompt"),
+    (InputEvent::FilterTag, "f", "Filter by Tag"),
+    (InputEvent::BranchHeads, "h", "Jump to Branch Head"),
+    (InputEvent::Reorganize, "r", "Move, Copy or Detach Prompt"),
+    (InputEvent::Merge, "g", "Merge Two Branches"),
+    (InputEvent::Refresh, "none", "Reload from the Database"),
+    (InputEvent::Quit, "q", "Quit"),
+];
+
+const ASK_KEYS: &[(InputEvent, &str, &str)] = &[
+    (InputEvent::Help, "?", "Help"),
+    (InputEvent::Palette, ":", "Commands"),
+    (InputEvent::Notifications, "!", "Notifications"),
+    (InputEvent::AskModel, "a", "Ask the Model"),
+    (InputEvent::WriteQuestion, "w", "Write a Question"),
+    (InputEvent::OpenEditor, "o", "Write a Question in $EDITOR"),
+    (InputEvent::SwitchBranch, "b", "Switch Branch"),
+    (InputEvent::EditScrolls, "e", "Edit Scrolls"),
+    (InputEvent::ChangeProject, "p", "Change Project"),
+    (InputEvent::Export, "x", "Export Branch"),
+    (InputEvent::Refresh, "none", "Reload from the Database"),
+    (InputEvent::Quit, "q", "Quit"),
+];
+
+const SCROLL_KEYS: &[(InputEvent, &str, &str)] = &[
+    (InputEvent::Help, "?", "Help"),
+    (InputEvent::Palette, ":", "Commands"),
+    (InputEvent::Notifications, "!", "Notifications"),
+    (InputEvent::New, "n", "New Scroll"),
+    (InputEvent::Delete, "d", "Delete Scroll"),
+    (InputEvent::AskModel, "a", "Ask Model"),
+    (InputEvent::SwitchBranch, "b", "Switch Branch"),
+    (InputEvent::ChangeProject, "p", "Change Project"),
+    (InputEvent::Refresh, "none", "Reload from the Database"),
+    (InputEvent::Quit, "q", "Quit"),
+];
+
+const CONFIRM_KEYS: &[(InputEvent, &str, &str)] = &[
+    (InputEvent::Confirm, "y", "Yes"),
+    (InputEvent::Cancel, "n", "No"),
+];
+
+const QUESTION_KEYS: &[(InputEvent, &str, &str)] = &[
+    (InputEvent::Submit, "ctrl-s", "Send the Question"),
+    (InputEvent::OpenEditor, "ctrl-o", "Continue in $EDITOR"),
+    (InputEvent::Cancel, "esc", "Back, Keeping the Draft"),
+];
+
+/// The `[keys]` section of `config.toml`.
+///
+/// Each table maps the actions of a state to the key chords replacing their
+/// default keys, e.g. `merge = "ctrl-g"` under `[keys.prompts]`. `"none"`
+/// leaves an action without a key.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct KeyConfig {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub projects: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub prompts: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub ask: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub scrolls: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub confirm: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub question: BTreeMap<String, String>,
+}
+
+impl KeyConfig {
+    /// Whether no default key is replaced.
+    pub fn is_empty(&self) -> bool {
+        KeyScope::ALL.iter().all(|&s| self.scope(s).is_empty())
+    }
+
+    fn scope(&self, scope: KeyScope) -> &BTreeMap<String, String> {
+        match scope {
+            KeyScope::Projects => &self.projects,
+            KeyScope::Prompts => &self.prompts,
+            KeyScope::Ask => &self.ask,
+            KeyScope::Scrolls => &self.scrolls,
+            KeyScope::Confirm => &self.confirm,
+            KeyScope::Question => &self.question,
+        }
+    }
+}
+
+/// A key with its modifiers, such as `ctrl-s`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// Parses a chord such as `s`, `S`, `ctrl-s`, `alt-enter` or `f2`.
+    ///
+    /// # Returns:
+    /// - `Ok(KeyChord)`, or a `ParseError` naming the unknown key or modifier.
+    pub fn parse(text: &str) -> Result<KeyChord, AppError> {
+        KeyChord::parse_text(text).map_err(AppError::ParseError)
+    }
+
+    fn parse_text(text: &str) -> Result<KeyChord, String> {
+        let (prefix, key) = match text.strip_suffix("--") {
+            Some(prefix) => (prefix, "-"),
+            None => match text.rsplit_once('-') {
+                Some((prefix, key)) if !key.is_empty() => (prefix, key),
+                _ => ("", text),
+            },
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in prefix.split('-').filter(|m| !m.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier '{}'", modifier)),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "space" => KeyCode::Char(' '),
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key '{}'", key)),
+                },
+            },
+        };
+        Ok(KeyChord::normalized(code, modifiers))
+    }
+
+    /// The chord of a key press.
+    pub fn from_event(event: &KeyEvent) -> KeyChord {
+        KeyChord::normalized(event.code, event.modifiers)
+    }
+
+    /// Letters carry the shift key in their case, as terminals report them.
+    fn normalized(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        let modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => KeyChord {
+                code: KeyCode::Char(c.to_ascii_uppercase()),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            code => KeyChord { code, modifiers },
+        }
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+fn parse_error(message: &str) -> anyhow::Error {
+    log_error(message);
+    AppError::ParseError(message.to_string()).into()
+}
+
+/// The active key bindings of every state.
+#[derive(Clone, Debug)]
+pub struct KeyMap {
+    /// Scope, action, key and help text, in display order.
+    bindings: Vec<(KeyScope, InputEvent, KeyChord, &'static str)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> KeyMap {
+        KeyMap::new(&KeyConfig::default()).expect("The default key bindings are valid")
+    }
+}
+
+impl KeyMap {
+    /// Builds the bindings from the defaults and the `[keys]` section.
+    ///
+    /// # Parameters:
+    /// - `config`: The keys replacing the default ones.
+    ///
+    /// # Returns:
+    /// - `Ok(KeyMap)`, or a `ParseError` for an unknown action or key, two actions
+    ///   sharing a key in the same state, or a key used to navigate the state.
+    pub fn new(config: &KeyConfig) -> Result<KeyMap> {
+        let mut bindings = vec![];
+        for scope in KeyScope::ALL {
+            let table = config.scope(scope);
+            let defaults = scope.defaults();
+
+            if let Some(unknown) = table
+                .keys()
+                .find(|name| !defaults.iter().any(|b| b.0.name() == name.as_str()))
+            {
+                return Err(parse_error(&format!(
+                    "Unknown action '{}' in [keys.{}]",
+                    unknown,
+                    scope.name()
+                )));
+            }
+
+            let mut used: HashMap<KeyChord, InputEvent> = HashMap::new();
+            for &(action, default, help) in defaults {
+                let key = table.get(action.name()).map_or(default, String::as_str);
+                if key == "none" {
+                    continue;
+                }
+                let chord = KeyChord::parse_text(key).map_err(|e| {
+                    parse_error(&format!(
+                        "Invalid key for {} in [keys.{}]: {}",
+                        action.name(),
+                        scope.name(),
+                        e
+                    ))
+                })?;
+
+                if let Some(other) = used.insert(chord, action) {
+                    return Err(parse_error(&format!(
+                        "'{}' is bound to both {} and {} in [keys.{}]",
+                        chord,
+                        other.name(),
+                        action.name(),
+                        scope.name()
+                    )));
+                }
+                if scope.reserves(chord) {
+                    return Err(parse_error(&format!(
+                        "'{}' of {} in [keys.{}] is already used by this view",
+                        chord,
+                        action.name(),
+                        scope.name()
+                    )));
+                }
+                bindings.push((scope, action, chord, help));
+            }
+        }
+        Ok(KeyMap { bindings })
+    }
+
+    /// The action bound to a key press in `scope`, `InputEvent::NoOp` if none.
+    pub fn action(&self, scope: KeyScope, event: &KeyEvent) -> InputEvent {
+        let chord = KeyChord::from_event(event);
+        self.bindings
+            .iter()
+            .find(|b| b.0 == scope && b.2 == chord)
+            .map_or(InputEvent::NoOp, |b| b.1)
+    }
+
+    /// The key bound to `action` in `scope`.
+    pub fn chord(&self, scope: KeyScope, action: InputEvent) -> Option<KeyChord> {
+        self.bindings
+            .iter()
+            .find(|b| b.0 == scope && b.1 == action)
+            .map(|b| b.2)
+    }
+
+    /// Every action of `scope` with its key, if any, and its help text, in display order.
+    pub fn actions(&self, scope: KeyScope) -> Vec<(InputEvent, Option<KeyChord>, &'static str)> {
+        scope
+            .defaults()
+            .iter()
+            .map(|&(action, _, help)| (action, self.chord(scope, action), help))
+            .collect()
+    }
+
+    /// One help line per bound action of `scope`, such as `[s] Select Prompt`.
+    pub fn help(&self, scope: KeyScope) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|b| b.0 == scope)
+            .map(|(_, _, chord, help)| format!("[{}] {}", chord, help))
+            .collect()
+    }
+}
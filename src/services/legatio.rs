@@ -1,67 +1,211 @@
-use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
-    terminal::{disable_raw_mode, enable_raw_mode},
-};
-use ratatui::layout::{Constraint, Direction, Layout};
-use ratatui::style::{Color, Style};
+use crossterm::event::{self, Event, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Constraint, Direction, Flex, Layout, Rect};
+use ratatui::style::{Modifier, Style};
 use ratatui::text::Line;
-use ratatui::widgets::{Block, BorderType, Borders, Paragraph};
+use ratatui::widgets::{Block, BorderType, Borders, Clear, Paragraph};
 use ratatui::{backend::CrosstermBackend, Terminal};
 
 use std::fs::{self, File};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use std::{io, vec};
 
 use crate::{
     core::{
+        bundle::{export_bundle, import_bundle, read_bundle},
         canvas::{chain_into_canvas, chain_match_canvas},
+        export::{write_export, ExportFormat, ExportScope},
+        import::import_transcript,
+        merge::{
+            branch_tips, format_branch, merge_request, store_merge, MergeMode, SUMMARY_REQUEST,
+        },
         project::{
-            build_select_project,
-            delete_project,
-            format_project_title,
-            get_projects,
-            store_project,
+            build_select_project, delete_project, format_project_title, get_projects,
+            parse_project_details, project_details_template, project_label, relocate_project,
+            store_project, update_project_details,
         },
         prompt::{
-            delete_prompt,
-            format_prompt,
-            get_prompts,
-            prompt_chain,
-            store_prompt,
-            system_prompt,
+            copy_prompt, format_prompt, get_prompts, move_prompt, move_targets, prompt_chain,
+            prompt_descendants, store_prompt, system_prompt, PromptScope,
         },
         scroll::{
-            delete_scroll,
-            get_scrolls,
-            read_file,
-            store_scroll,
-            update_scroll_content
+            delete_scroll, get_scrolls, read_project_file, relativize_scroll_paths,
+            resolve_scroll_path, store_scroll, update_scroll_content,
+        },
+        session::{get_session, store_session, Session},
+        tag::{
+            advance_branch, get_marks, parse_prompt_marks, prompt_marks_template, set_prompt_marks,
+            ProjectMarks,
+        },
+        trash::{
+            empty_trash, format_trash_entry, get_trash, purge_trash, restore_trash, trash_prompt,
+            DeleteMode,
         },
     },
     services::{
-        config::{read_config, store_config, UserConfig},
+        config::{read_config, UserConfig},
+        draft::{remove_draft, save_draft, take_draft, InFlight},
+        editor::{edit_text, open_in_editor},
+        input::TextInput,
+        keys::{InputEvent, KeyMap, KeyScope},
         //model::{ask_question, Question, LLM},
-        search::{item_selector, select_files, select_directories},
-        ui::{extract_theme_colors, usr_prompt_chain, usr_prompts, usr_scrolls},
+        list::{
+            is_interrupt, take_interrupted, take_screen_dirty, ListAction, SelectItem, SelectList,
+        },
+        preview::{CodeColors, Preview},
+        search::{item_selector, select_directories, select_files, select_item},
+        status::StatusBar,
+        terminal::{enter_terminal, install_handlers, restore_terminal},
+        tree::PromptTree,
+        ui::{themes_path, usr_prompt_chain, usr_scrolls, ThemeCache, ThemeColors},
+    },
+    utils::{
+        backup::backup_database,
+        structs::{Project, Prompt, Scroll},
     },
-    utils::structs::{Project, Prompt, Scroll},
 };
 
 use anyhow::Result;
 use ask_ai::{
     ask_ai::ask_question,
-    config::{AiConfig, AiPrompt, Framework, Question},
+    config::{AiPrompt, Question},
 };
 use sqlx::SqlitePool;
 
+/// How often the main loop wakes up without input to expire notifications.
+const STATUS_TICK: Duration = Duration::from_millis(500);
+
 pub struct Legatio {
     state: AppState,
     current_project: Option<Project>,
     current_prompt: Option<Prompt>,
+    /// Branch whose head is `current_prompt`, advanced by each new question.
+    current_branch: Option<String>,
+    /// Last session written to the database.
+    session: Session,
     user_config: Option<UserConfig>,
+    keys: KeyMap,
     project_list_cache: Option<Vec<Project>>,
     prompt_list_cache: Option<Vec<Prompt>>,
+    /// Tags, stars, branches and merges of the cached prompts, cleared with them.
+    marks_cache: Option<ProjectMarks>,
     scroll_list_cache: Option<Vec<Scroll>>,
+    pending_delete: Option<PendingDelete>,
+    /// Navigable contents of the bottom pane, one per pane.
+    project_list: SelectList,
+    prompt_tree: PromptTree,
+    scroll_list: SelectList,
+    /// Markdown rendering of the selected or current prompt.
+    preview: Preview,
+    /// Draft of the next question, written inside the application.
+    question: TextInput,
+    /// Question written in the application or `$EDITOR`, waiting to be sent.
+    pending_question: Option<String>,
+    /// Window over the current view, taking every key until it closes.
+    overlay: Option<Overlay>,
+    /// Notifications shown at the bottom of the screen.
+    status: StatusBar,
+    /// Height of the top box, dragged with the mouse.
+    split: Split,
+    /// The themes of `themes.toml` and the colors of the configured one.
+    themes: ThemeCache,
+    colors: ThemeColors,
+}
+
+/// Prompts waiting for the user to confirm their deletion.
+enum PendingDelete {
+    /// Prompts moved to the trash together, the first one being the deleted prompt.
+    Trash(DeleteMode, Vec<Prompt>),
+    /// Every prompt in the trash of the project, deleted for good.
+    EmptyTrash(String, Vec<Prompt>),
+}
+
+/// A window drawn over the current view.
+enum Overlay {
+    /// Every action of the view with its key, then the keys of its panes.
+    Help,
+    /// The actions of the view filtered by name, run with `Enter`.
+    Palette(SelectList),
+    /// The notifications of the session, newest first.
+    Notifications,
+}
+
+/// Rows kept for the panes under the top box when dragging its border.
+const MIN_PANE_ROWS: u16 = 6;
+
+/// The border between the top box and the panes under it, dragged with the mouse.
+#[derive(Default)]
+struct Split {
+    /// Rows of the top box, `None` for its default share of the screen.
+    rows: Option<u16>,
+    /// The top box and the area it shares with the panes, as last drawn.
+    top: Rect,
+    main: Rect,
+    dragging: bool,
+}
+
+impl Split {
+    /// Whether `row` is the bottom border of the top box.
+    fn on_border(&self, column: u16, row: u16) -> bool {
+        self.top.height > 0
+            && row + 1 == self.top.bottom()
+            && (self.top.left()..self.top.right()).contains(&column)
+    }
+
+    /// Moves the bottom border of the top box to `row`, keeping a line of text
+    /// in the box and room for the panes under it.
+    fn drag_to(&mut self, row: u16) {
+        let max = self.main.height.saturating_sub(MIN_PANE_ROWS).max(3);
+        self.rows = Some((row + 1).saturating_sub(self.main.y).clamp(3, max));
+    }
+}
+
+/// Keys of the project and scroll lists, listed by the help overlay.
+const LIST_HELP: &[(&str, &str)] = &[
+    ("j / k", "Move Through the List"),
+    ("/", "Filter the List"),
+    ("Esc", "Clear the Filter"),
+    ("Enter", "Open the Highlighted Item"),
+];
+
+/// Keys of the prompt tree, listed by the help overlay.
+const TREE_HELP: &[(&str, &str)] = &[
+    ("j / k", "Move Through the Tree"),
+    ("J / K", "Next or Previous Sibling"),
+    ("Left / Right", "Collapse or Expand, Parent or First Reply"),
+    ("Space", "Fold or Unfold a Subtree"),
+    ("/", "Filter the Tree"),
+    ("Esc", "Clear the Filter"),
+    ("Enter", "Continue the Highlighted Prompt"),
+];
+
+/// Keys of the preview, listed by the help overlay.
+const PREVIEW_HELP: &[(&str, &str)] = &[
+    ("Ctrl-d / Ctrl-u", "Scroll the Preview"),
+    ("Ctrl-f", "Search the Preview"),
+    ("n / N", "Next or Previous Match"),
+];
+
+/// The navigable widget shown in the bottom pane.
+enum Pane<'a> {
+    List(&'a mut SelectList),
+    Tree(&'a mut PromptTree),
+}
+
+impl Pane<'_> {
+    fn handle_key(&mut self, key_event: KeyEvent) -> ListAction {
+        match self {
+            Pane::List(list) => list.handle_key(key_event),
+            Pane::Tree(tree) => tree.handle_key(key_event),
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) -> ListAction {
+        match self {
+            Pane::List(list) => list.handle_mouse(mouse),
+            Pane::Tree(tree) => tree.handle_mouse(mouse),
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -69,26 +213,13 @@ enum AppState {
     SelectProject,
     SelectPrompt,
     AskModel,
+    WriteQuestion,
     EditScrolls,
     AskModelConfirmation,
+    DeletePromptConfirmation,
     Quit,
 }
 
-#[derive(Debug, PartialEq, Eq)]
-enum InputEvent {
-    Select,
-    New,
-    Delete,
-    SwitchBranch,
-    ChangeProject,
-    EditScrolls,
-    AskModel,
-    Quit,
-    Confirm,
-    Cancel,
-    NoOp,
-}
-
 impl Default for Legatio {
     fn default() -> Self {
         Self::new()
@@ -139,20 +270,37 @@ impl Legatio {
             state: AppState::SelectProject,
             current_project: None,
             current_prompt: None,
+            current_branch: None,
+            session: Session::default(),
             user_config: None,
+            keys: KeyMap::default(),
             project_list_cache: None,
             prompt_list_cache: None,
+            marks_cache: None,
             scroll_list_cache: None,
+            pending_delete: None,
+            project_list: SelectList::default(),
+            prompt_tree: PromptTree::default(),
+            preview: Preview::default(),
+            question: TextInput::default(),
+            pending_question: None,
+            overlay: None,
+            status: StatusBar::default(),
+            split: Split::default(),
+            themes: ThemeCache::default(),
+            colors: ThemeColors::default(),
+            scroll_list: SelectList::default(),
         }
     }
 
     /// The main entry point for running the `Legatio` application.
     ///
     /// This function:
-    /// 1. Sets up a terminal interface with raw mode.
-    /// 2. Initializes the application state.
-    /// 3. Runs a continuous loop (`main_loop`) until the application terminates.
-    /// 4. Ensures raw mode is disabled on exit.
+    /// 1. Installs the panic hook and signal handlers restoring the terminal.
+    /// 2. Sets up a terminal interface with raw mode on the alternate screen.
+    /// 3. Initializes the application state.
+    /// 4. Runs a continuous loop (`main_loop`) until the application terminates.
+    /// 5. Restores the terminal on exit, keeping an unsent question as a draft.
     ///
     /// ### Arguments:
     /// `pool` - A `SqlitePool` connection to the underlying SQLite database.
@@ -160,32 +308,29 @@ impl Legatio {
     /// ### Returns:
     /// - `Result<()>` indicating success or failure.
     pub async fn run(&mut self, pool: &SqlitePool) -> Result<()> {
-        // Initialize terminal with raw mode
-        enable_raw_mode()?;
+        // `check_config_files` wrote the defaults if the file was missing; a config
+        // that does not parse is reported, never replaced
+        let user_config = read_config()?;
+        // Refuse invalid bindings before taking over the terminal
+        self.keys = KeyMap::new(&user_config.keys)?;
+        self.user_config = Some(user_config);
+
+        // Initialize terminal with raw mode, restored even if the application dies
+        install_handlers()?;
+        enter_terminal()?;
         let mut stdout = io::stdout();
         let backend = CrosstermBackend::new(&mut stdout);
         let mut terminal = Terminal::new(backend)?;
-
-        // Default config for user
-        let default_config = UserConfig {
-            ai_conf: AiConfig {
-                llm: Framework::OpenAI,
-                model: String::from("chatgpt-4o-latest"),
-                max_token: None,
-            },
-            theme: String::from("Tokyo Storm"),
-            ask_conf: true,
-        };
-
-        self.user_config = Some(read_config().unwrap_or(default_config));
-        store_config(self.user_config.as_ref().unwrap()).unwrap();
+        terminal.clear()?;
 
         // Run the main loop
         let result = self.main_loop(&mut terminal, pool).await;
 
         // Clean up terminal
-        disable_raw_mode()?;
-        terminal.show_cursor()?;
+        restore_terminal();
+        if let (Some(project), false) = (&self.current_project, self.question.is_blank()) {
+            save_draft(&project.project_id, &self.question.text())?;
+        }
         result
     }
 
@@ -215,29 +360,56 @@ impl Legatio {
             self.project_list_cache = Some(p.clone());
             p
         };
-        if !projects.is_empty() {
-            self.current_project = Some(projects[0].clone());
-            self.state = AppState::SelectProject;
+        // Older databases store absolute scroll paths
+        for project in projects.iter() {
+            relativize_scroll_paths(pool, project).await?;
         }
+        self.restore_session(pool, &projects).await?;
 
         // Initial draw to display the UI
+        self.reload_theme();
         self.draw(terminal, pool).await?;
 
-        // Main event loop: wait for key events
+        // Main event loop: wait for key and mouse events
         loop {
-            // Block until a key event is received
-            if let Event::Key(key_event) = event::read()? {
-                // Process the input
-                let next_state = self.handle_input_with_key(pool, key_event).await?;
-                self.state = next_state;
-
-                // Redraw the UI after handling input
-                self.draw(terminal, pool).await?;
-
-                // Exit if the state is Quit
-                if matches!(self.state, AppState::Quit) {
-                    break;
+            // Wake up now and then to take down an expired notification or
+            // show the themes just saved
+            if !event::poll(STATUS_TICK)? {
+                let reloaded = self.reload_theme();
+                if self.status.expire(Instant::now()) || reloaded {
+                    self.draw(terminal, pool).await?;
+                }
+                continue;
+            }
+            // Process the input, a failed action leaves the application running
+            let handled = match event::read()? {
+                Event::Key(key_event) if is_interrupt(&key_event) => Ok(AppState::Quit),
+                Event::Key(key_event) => self.handle_input_with_key(pool, key_event).await,
+                Event::Mouse(mouse) if mouse.kind != MouseEventKind::Moved => {
+                    self.handle_mouse(pool, mouse).await
                 }
+                // The layout follows the new size of the terminal on the next draw
+                Event::Resize(..) => Ok(self.state),
+                _ => continue,
+            };
+            self.state = handled.unwrap_or_else(|err| self.recover(err));
+            // `Ctrl-c` in a picker quits as well
+            if take_interrupted() {
+                self.state = AppState::Quit;
+            }
+            if let Err(err) = self.save_session(pool).await {
+                self.status.error(&err);
+            }
+
+            // Redraw the UI after handling input, from scratch if a picker drew over it
+            if take_screen_dirty() {
+                terminal.clear()?;
+            }
+            self.draw(terminal, pool).await?;
+
+            // Exit if the state is Quit
+            if matches!(self.state, AppState::Quit) {
+                break;
             }
         }
 
@@ -264,29 +436,26 @@ impl Legatio {
         pool: &SqlitePool,
     ) -> Result<()> {
         // Prepare all the data we might need to render
-        let theme = &self.user_config.as_ref().unwrap().theme;
-        let colors = extract_theme_colors(theme)?;
-        let primary_color = colors.primary;
-        let secondary_color = colors.secondary;
-        let accent_color = colors.accent;
+        let colors = self.colors;
+        self.preview.set_colors(CodeColors {
+            code: colors.code,
+            diff_add: colors.diff_add,
+            diff_remove: colors.diff_remove,
+        });
 
         let top_title = format_project_title(&self.current_project);
-        let top_text: Vec<Line>;
+        let mut top_text: Vec<Line>;
         let mut scroll_title: Option<&str> = None;
         let mut scroll_text: Option<Vec<Line>> = None;
         let mut bot_title = String::new();
         let mut bot_items: Vec<Line> = vec![];
-        let mut pop_up = false;
+        let mut pop_up: Option<Vec<Line>> = None;
+        let mut show_preview = false;
 
         match self.state {
             // Define UI for specific state
             AppState::SelectProject => {
-                top_text = vec![
-                    Line::from("[s] Select Project"),
-                    Line::from("[n] New Project"),
-                    Line::from("[d] Delete Project"),
-                    Line::from("[q] Quit"),
-                ];
+                top_text = self.help_lines(KeyScope::Projects);
                 bot_title = "[ Projects ]".to_string();
 
                 // Fetch all projects from cache
@@ -298,56 +467,58 @@ impl Legatio {
                     p
                 };
                 let (items, _) = build_select_project(&projects);
-                bot_items.extend(items);
+                self.project_list.set_items(
+                    projects
+                        .iter()
+                        .zip(items)
+                        .map(|(p, line)| SelectItem::styled(&p.project_id, vec![line]))
+                        .collect(),
+                );
             }
             AppState::SelectPrompt => {
-                top_text = vec![
-                    Line::from("[s]: Select Prompt"),
-                    Line::from("[d]: Delete Prompt"),
-                    Line::from("[e]: Edit Scrolls"),
-                    Line::from("[p]: Change Project"),
-                    Line::from("[q]: Quit"),
-                ];
+                top_text = self.help_lines(KeyScope::Prompts);
+                top_text.push(Line::from("[Enter] Continue Prompt  [Space] Fold Subtree"));
 
                 if let Some(project) = &self.current_project {
-                    let project_name = project
-                        .project_path
-                        .split("/")
-                        .last()
-                        .unwrap_or("[Unnamed Project]");
+                    let project_name = project_label(project);
                     bot_title = format!("[ {} -:- Prompts ]", project_name);
 
-                    // Fetch all prompts from cache
-                    let prompts = if let Some(cache) = &self.prompt_list_cache {
-                        cache.clone()
-                    } else {
-                        let p = get_prompts(pool, &project.project_id).await?;
-                        self.prompt_list_cache = Some(p.clone());
-                        p
-                    };
+                    // Rebuild the tree only when the prompts or their marks were read again
+                    if self.prompt_list_cache.is_none() || self.marks_cache.is_none() {
+                        let prompts = get_prompts(pool, &project.project_id).await?;
+                        let marks = get_marks(pool, &project.project_id).await?;
+                        self.prompt_list_cache = Some(prompts.clone());
+                        self.marks_cache = Some(marks.clone());
+                        self.prompt_tree.set_prompts(prompts, marks);
+                    }
 
-                    if prompts.is_empty() {
+                    if self.prompt_list_cache.as_ref().is_some_and(Vec::is_empty) {
                         bot_items.push(Line::from("This project has no prompts!"));
-                    } else {
-                        let prompt_strs = usr_prompts(prompts.as_ref()).await?;
-                        for p in prompt_strs {
-                            bot_items.push(Line::from(p));
-                        }
                     }
+                    self.prompt_tree
+                        .set_current(self.current_prompt.as_ref().map(|p| p.prompt_id.as_str()));
+                    // The preview follows the highlighted prompt
+                    self.preview.set_prompt(self.prompt_tree.selected_prompt());
+                    show_preview = self.prompt_tree.selected_prompt().is_some();
                 } else {
                     bot_items.push(Line::from("No active project"));
                 }
             }
-            AppState::AskModel => {
-                top_text = vec![
-                    Line::from("[a] Ask the Model"),
-                    Line::from("[b] Switch branch"),
-                    Line::from("[e] Edit Scrolls"),
-                    Line::from("[p] Change Project"),
-                    Line::from("[q] Quit"),
-                ];
+            AppState::AskModel | AppState::WriteQuestion => {
+                top_text = if let AppState::WriteQuestion = self.state {
+                    let mut lines = vec![Line::from("[Enter] New Line")];
+                    lines.extend(self.help_lines(KeyScope::Question));
+                    lines
+                } else {
+                    self.help_lines(KeyScope::Ask)
+                };
                 scroll_title = Some("[ Scrolls ]");
-                bot_title = String::from("[ Prompts ]");
+                self.preview.set_prompt(self.current_prompt.as_ref());
+                show_preview = self.current_prompt.is_some();
+                bot_title = match &self.current_branch {
+                    Some(branch) => format!("[ Prompts -:- {} ]", branch),
+                    None => String::from("[ Prompts ]"),
+                };
                 if let Some(project) = &self.current_project {
                     // Scroll PREP
                     let scrolls = usr_scrolls(pool, project).await?;
@@ -372,11 +543,14 @@ impl Legatio {
 
                     let pmp_chain: Option<Vec<Prompt>>;
                     if file_prompt.is_err() {
-                        File::create(
-                            PathBuf::from(&self.current_project.as_ref().unwrap().project_path)
-                                .join("legatio.md"),
-                        )
-                        .expect("Could not create file!");
+                        let canvas = PathBuf::from(&project.project_path).join("legatio.md");
+                        if let Err(err) = File::create(&canvas) {
+                            self.status.warn(&format!(
+                                "Could not create {}: {}",
+                                canvas.display(),
+                                err
+                            ));
+                        }
                     } else if prompt.is_some() {
                         // Fetch all prompts from cache
                         let prompts = if let Some(cache) = &self.prompt_list_cache {
@@ -406,14 +580,7 @@ impl Legatio {
                 }
             }
             AppState::EditScrolls => {
-                top_text = vec![
-                    Line::from("[n] New Scroll"),
-                    Line::from("[d] Delete Scroll"),
-                    Line::from("[a] Ask Model"),
-                    Line::from("[s] Switch Branch"),
-                    Line::from("[p] Change Project"),
-                    Line::from("[q]: Quit"),
-                ];
+                top_text = self.help_lines(KeyScope::Scrolls);
                 bot_title = "[ Scrolls ]".to_string();
 
                 if let Some(project) = &self.current_project {
@@ -425,6 +592,7 @@ impl Legatio {
                         self.scroll_list_cache = Some(s.clone());
                         s
                     };
+                    let mut items = vec![];
                     for scroll in scrolls.iter() {
                         let scroll_name =
                             match scroll.scroll_path.strip_prefix(&project.project_path) {
@@ -433,19 +601,16 @@ impl Legatio {
                                 }
                                 None => scroll.scroll_path.to_string(),
                             };
-                        bot_items.push(Line::from(scroll_name));
+                        items.push(SelectItem::new(&scroll.scroll_id, &scroll_name));
                     }
+                    self.scroll_list.set_items(items);
                 }
             }
             AppState::AskModelConfirmation => {
-                top_text = vec![
-                    Line::from("[a] Ask the Model"),
-                    Line::from("[b] Switch branch"),
-                    Line::from("[e] Edit Scrolls"),
-                    Line::from("[p] Change Project"),
-                    Line::from("[q] Quit"),
-                ];
+                top_text = self.help_lines(KeyScope::Ask);
                 scroll_title = Some("[ Scrolls ]");
+                self.preview.set_prompt(self.current_prompt.as_ref());
+                show_preview = self.current_prompt.is_some();
                 bot_title = String::from("[ Prompts ]");
                 if let Some(project) = &self.current_project {
                     // Scroll PREP
@@ -471,11 +636,14 @@ impl Legatio {
 
                     let pmp_chain: Option<Vec<Prompt>>;
                     if file_prompt.is_err() {
-                        File::create(
-                            PathBuf::from(&self.current_project.as_ref().unwrap().project_path)
-                                .join("legatio.md"),
-                        )
-                        .expect("Could not create file!");
+                        let canvas = PathBuf::from(&project.project_path).join("legatio.md");
+                        if let Err(err) = File::create(&canvas) {
+                            self.status.warn(&format!(
+                                "Could not create {}: {}",
+                                canvas.display(),
+                                err
+                            ));
+                        }
                     } else if prompt.is_some() {
                         // Fetch all prompts from cache
                         let prompts = if let Some(cache) = &self.prompt_list_cache {
@@ -503,14 +671,73 @@ impl Legatio {
                 } else {
                     bot_items.push(Line::from("No active project"));
                 }
-                pop_up = true;
+                pop_up = Some(vec![Line::from(
+                    self.keys.help(KeyScope::Confirm).join(" "),
+                )]);
+            }
+            AppState::DeletePromptConfirmation => {
+                top_text = vec![];
+                bot_title = String::from("[ Prompts to delete ]");
+                if let Some(pending) = &self.pending_delete {
+                    let (mut text, prompts) = match pending {
+                        PendingDelete::Trash(mode, prompts) => {
+                            let mut text = vec![Line::from(format!(
+                                "Move {} prompt(s) to the trash?",
+                                prompts.len()
+                            ))];
+                            if *mode == DeleteMode::Splice {
+                                text.push(Line::from(
+                                    "The replies of the prompt are linked to its parent.",
+                                ));
+                            }
+                            (text, prompts)
+                        }
+                        PendingDelete::EmptyTrash(_, prompts) => (
+                            vec![Line::from(format!(
+                                "Permanently delete the {} prompt(s) in the trash?",
+                                prompts.len()
+                            ))],
+                            prompts,
+                        ),
+                    };
+                    text.push(Line::from(self.keys.help(KeyScope::Confirm).join(" ")));
+                    pop_up = Some(text);
+
+                    for p in prompts.iter() {
+                        let (p_str, _) = format_prompt(p);
+                        bot_items.push(Line::from(p_str));
+                    }
+                }
             }
             // TODO: is this correct?
-            AppState::Quit => return Ok(())
+            AppState::Quit => return Ok(()),
         }
 
+        // The overlay is drawn over the view; put back once drawn
+        let mut overlay = self.overlay.take();
+        let overlay_text = match overlay {
+            Some(Overlay::Help) => self.help_overlay(),
+            Some(Overlay::Notifications) if self.status.log().is_empty() => {
+                vec![Line::from(" No notifications yet")]
+            }
+            Some(Overlay::Notifications) => self
+                .status
+                .log()
+                .iter()
+                .map(|n| n.log_line(colors.error))
+                .collect(),
+            _ => vec![],
+        };
+        let status_line = self.status.line(&self.status_hint(), colors.error);
+        let top_rows = self.split.rows;
+
+        // The bottom pane is navigable when it shows projects, prompts or scrolls
+        let (pane, preview, input) = self.panes();
+        let bot_pane = if bot_items.is_empty() { pane } else { None };
+        let preview = if show_preview { Some(preview) } else { None };
+
         // Call render function with prepared data
-        self.render(
+        let drawn = Self::render(
             terminal,
             &top_title,
             &top_text,
@@ -518,15 +745,23 @@ impl Legatio {
             scroll_text,
             &bot_title,
             &bot_items,
-            primary_color,
-            secondary_color,
-            accent_color,
+            colors,
             pop_up,
-        )
+            bot_pane,
+            preview,
+            input,
+            overlay.as_mut(),
+            overlay_text,
+            status_line,
+            top_rows,
+        );
+        self.overlay = overlay;
+        (self.split.top, self.split.main) = drawn?;
+        Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn render(
-        &self,
         terminal: &mut Terminal<CrosstermBackend<&mut io::Stdout>>,
         top_title: &str,
         top_text: &[Line],
@@ -534,14 +769,23 @@ impl Legatio {
         scroll_text: Option<Vec<Line>>,
         bot_title: &str,
         bot_items: &[Line],
-        primary_color: Color,
-        secondary_color: Color,
-        accent_color: Color,
-        pop_up: bool,
-    ) -> Result<()> {
+        colors: ThemeColors,
+        pop_up: Option<Vec<Line>>,
+        bot_pane: Option<Pane>,
+        preview: Option<&mut Preview>,
+        input: Option<&mut TextInput>,
+        overlay: Option<&mut Overlay>,
+        overlay_text: Vec<Line>,
+        status_line: Line,
+        top_rows: Option<u16>,
+    ) -> Result<(Rect, Rect)> {
+        let primary_color = colors.primary;
+        let secondary_color = colors.secondary;
+        let accent_color = colors.accent;
+
         // Top box
-        let top_box = if pop_up {
-            Paragraph::new(vec![Line::from("[y]es [n]o")])
+        let top_box = if let Some(pop_up_text) = pop_up {
+            Paragraph::new(pop_up_text)
                 .block(
                     Block::default()
                         .borders(Borders::ALL)
@@ -557,12 +801,14 @@ impl Legatio {
                         .borders(Borders::ALL)
                         .border_type(BorderType::Thick)
                         .style(Style::default().fg(primary_color))
+                        .border_style(Style::default().fg(colors.border))
                         .title(top_title),
                 )
                 .style(Style::default().fg(secondary_color))
         };
 
-        // Scroll box
+        // Scroll box, under a top box of the height dragged by the user if any
+        let top_constraint = top_rows.map_or(Constraint::Percentage(18), Constraint::Length);
         let (scroll_box, constraints) =
             if let (Some(title), Some(text)) = (scroll_title, scroll_text.as_ref()) {
                 // Both `scroll_title` and `scroll_text` exist, so create the scroll box
@@ -572,185 +818,267 @@ impl Legatio {
                             .borders(Borders::ALL)
                             .border_type(BorderType::Thick)
                             .style(Style::default().fg(primary_color))
+                            .border_style(Style::default().fg(colors.border))
                             .title(title),
                     )
                     .style(Style::default().fg(secondary_color));
 
                 let constraints = Vec::from([
-                    Constraint::Percentage(18),
+                    top_constraint,
                     Constraint::Percentage(21),
-                    Constraint::Percentage(61),
+                    Constraint::Fill(1),
                 ]);
 
                 (Some(scroll_box), constraints)
             } else {
                 // No scroll box; provide default constraints
-                (
-                    None,
-                    Vec::from([Constraint::Percentage(18), Constraint::Percentage(82)]),
-                )
+                (None, Vec::from([top_constraint, Constraint::Fill(1)]))
             };
 
         // Bottom box
+        let bot_block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Thick)
+            .style(Style::default().fg(accent_color))
+            .title(bot_title);
         let bot_box = Paragraph::new(bot_items.to_owned())
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_type(BorderType::Thick)
-                    .style(Style::default().fg(accent_color))
-                    .title(bot_title),
-            )
+            .block(bot_block.clone())
             .style(Style::default().fg(secondary_color));
 
-        // Terminal draw
+        // Terminal draw, keeping the areas of the top box and the view for the mouse
+        let mut areas = (Rect::default(), Rect::default());
         terminal.draw(|f| {
+            // The status bar takes the last row
+            let [main_area, status_area] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(f.area());
+            f.render_widget(
+                Paragraph::new(status_line).style(Style::default().fg(secondary_color)),
+                status_area,
+            );
+
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints(constraints)
-                .split(f.area());
+                .split(main_area);
 
             // Render the top box
             f.render_widget(top_box, chunks[0]);
+            areas = (chunks[0], main_area);
 
             // Render the scroll box if it exists
-            if let Some(scroll_box) = scroll_box {
+            let bot_area = if let Some(scroll_box) = scroll_box {
                 f.render_widget(scroll_box, chunks[1]);
-                f.render_widget(bot_box, chunks[2]);
+                chunks[2]
             } else {
-                f.render_widget(bot_box, chunks[1]);
+                chunks[1]
+            };
+            // The question being written takes the bottom of the bottom pane
+            let bot_area = match input {
+                Some(input) => {
+                    let halves = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+                        .split(bot_area);
+                    input.render(
+                        f,
+                        halves[1],
+                        bot_block.clone().title("[ Question ]"),
+                        Style::default().fg(primary_color),
+                    );
+                    halves[0]
+                }
+                None => bot_area,
+            };
+            // The preview takes the right of the bottom pane
+            let bot_area = match preview {
+                Some(preview) => {
+                    let halves = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+                        .split(bot_area);
+                    preview.render(
+                        f,
+                        halves[1],
+                        bot_block.clone().title("[ Preview ]"),
+                        Style::default().fg(secondary_color),
+                        Style::default()
+                            .fg(colors.highlight)
+                            .add_modifier(Modifier::REVERSED),
+                    );
+                    halves[0]
+                }
+                None => bot_area,
+            };
+            let style = Style::default().fg(secondary_color);
+            let highlight = Style::default()
+                .fg(colors.selection)
+                .add_modifier(Modifier::REVERSED);
+            match bot_pane {
+                Some(Pane::List(list)) => list.render(f, bot_area, bot_block, style, highlight),
+                Some(Pane::Tree(tree)) => {
+                    let path_style = Style::default()
+                        .fg(primary_color)
+                        .add_modifier(Modifier::BOLD);
+                    tree.render(f, bot_area, bot_block, style, highlight, path_style)
+                }
+                None => f.render_widget(bot_box, bot_area),
+            }
+
+            if let Some(overlay) = overlay {
+                let area = centered_area(f.area(), 60, 70);
+                let block = Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Thick)
+                    .style(Style::default().fg(accent_color));
+                f.render_widget(Clear, area);
+                match overlay {
+                    Overlay::Help | Overlay::Notifications => {
+                        let title = match overlay {
+                            Overlay::Help => "[ Help ]",
+                            _ => "[ Notifications ]",
+                        };
+                        f.render_widget(
+                            Paragraph::new(overlay_text)
+                                .block(block.title(title).title_bottom("[ Any key closes ]"))
+                                .style(style),
+                            area,
+                        )
+                    }
+                    Overlay::Palette(palette) => {
+                        palette.render(f, area, block.title("[ Commands ]"), style, highlight)
+                    }
+                }
             }
         })?;
 
-        Ok(())
+        Ok(areas)
+    }
+
+    /// Help text of the active bindings of `scope`, one line per action.
+    fn help_lines(&self, scope: KeyScope) -> Vec<Line<'static>> {
+        self.keys.help(scope).into_iter().map(Line::from).collect()
+    }
+
+    /// The bindings of the current state, `None` once quitting.
+    fn key_scope(&self) -> Option<KeyScope> {
+        match self.state {
+            AppState::SelectProject => Some(KeyScope::Projects),
+            AppState::SelectPrompt => Some(KeyScope::Prompts),
+            AppState::AskModel => Some(KeyScope::Ask),
+            AppState::EditScrolls => Some(KeyScope::Scrolls),
+            AppState::AskModelConfirmation | AppState::DeletePromptConfirmation => {
+                Some(KeyScope::Confirm)
+            }
+            AppState::WriteQuestion => Some(KeyScope::Question),
+            AppState::Quit => None,
+        }
     }
 
     fn state_specific_keys(&self, key_event: KeyEvent) -> InputEvent {
+        match (self.state, self.key_scope()) {
+            (AppState::Quit, _) => InputEvent::Quit,
+            (_, Some(scope)) => self.keys.action(scope, &key_event),
+            (_, None) => InputEvent::NoOp,
+        }
+    }
+
+    /// Text of the status bar while no notification is shown: the current view
+    /// and the keys of the help, the command palette and the notifications.
+    fn status_hint(&self) -> String {
+        let mut hint = String::from(match self.state {
+            AppState::SelectProject => "Projects",
+            AppState::SelectPrompt => "Prompts",
+            AppState::AskModel => "Ask the Model",
+            AppState::WriteQuestion => "Question",
+            AppState::EditScrolls => "Scrolls",
+            AppState::AskModelConfirmation | AppState::DeletePromptConfirmation => "Confirm",
+            AppState::Quit => "",
+        });
+        if let Some(scope) = self.key_scope() {
+            for (action, chord, help) in self.keys.actions(scope) {
+                if let (
+                    InputEvent::Help | InputEvent::Palette | InputEvent::Notifications,
+                    Some(chord),
+                ) = (action, chord)
+                {
+                    hint.push_str(&format!("  [{}] {}", chord, help));
+                }
+            }
+        }
+        hint
+    }
+
+    /// Keeps the application running after an action failed: shows the error,
+    /// leaves a confirmation pop-up and drops the cached lists, which may be stale.
+    ///
+    /// ### Arguments:
+    /// `err` - The error returned by the action.
+    ///
+    /// ### Returns:
+    /// - `AppState`: The state to continue in.
+    fn recover(&mut self, err: anyhow::Error) -> AppState {
+        self.status.error(&err);
+        self.project_list_cache = None;
+        self.clear_prompt_cache();
+        self.scroll_list_cache = None;
         match self.state {
-            AppState::SelectProject => match key_event {
-                KeyEvent {
-                    code: KeyCode::Char('s'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::Select,
-                KeyEvent {
-                    code: KeyCode::Char('n'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::New,
-                KeyEvent {
-                    code: KeyCode::Char('d'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::Delete,
-                KeyEvent {
-                    code: KeyCode::Char('q'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::Quit,
-                _ => InputEvent::NoOp,
-            },
-            AppState::SelectPrompt => match key_event {
-                KeyEvent {
-                    code: KeyCode::Char('s'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::Select,
-                KeyEvent {
-                    code: KeyCode::Char('d'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::Delete,
-                KeyEvent {
-                    code: KeyCode::Char('p'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::ChangeProject,
-                KeyEvent {
-                    code: KeyCode::Char('e'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::EditScrolls,
-                KeyEvent {
-                    code: KeyCode::Char('q'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::Quit,
-                _ => InputEvent::NoOp,
-            },
-            AppState::AskModel => match key_event {
-                KeyEvent {
-                    code: KeyCode::Char('a'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::AskModel,
-                KeyEvent {
-                    code: KeyCode::Char('p'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::ChangeProject,
-                KeyEvent {
-                    code: KeyCode::Char('e'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::EditScrolls,
-                KeyEvent {
-                    code: KeyCode::Char('b'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::SwitchBranch,
-                KeyEvent {
-                    code: KeyCode::Char('q'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::Quit,
-                _ => InputEvent::NoOp,
-            },
-            AppState::EditScrolls => match key_event {
-                KeyEvent {
-                    code: KeyCode::Char('n'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::New,
-                KeyEvent {
-                    code: KeyCode::Char('d'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::Delete,
-                KeyEvent {
-                    code: KeyCode::Char('s'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::SwitchBranch,
-                KeyEvent {
-                    code: KeyCode::Char('p'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::ChangeProject,
-                KeyEvent {
-                    code: KeyCode::Char('a'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::AskModel,
-                KeyEvent {
-                    code: KeyCode::Char('q'),
-                    modifiers: KeyModifiers::NONE,
-                    ..
-                } => InputEvent::Quit,
-                _ => InputEvent::NoOp,
-            },
-            AppState::AskModelConfirmation => match key_event {
-                KeyEvent {
-                    code: KeyCode::Char('y'),
-                    ..
-                } => InputEvent::Confirm,
-                KeyEvent {
-                    code: KeyCode::Char('n'),
-                    ..
-                } => InputEvent::Cancel,
-                _ => InputEvent::NoOp,
-            },
-            AppState::Quit => InputEvent::Quit,
+            AppState::AskModelConfirmation => AppState::AskModel,
+            AppState::DeletePromptConfirmation => {
+                self.pending_delete = None;
+                AppState::SelectPrompt
+            }
+            state => state,
+        }
+    }
+
+    /// Lines of the help overlay: every action of the current state with its key,
+    /// then the keys of the list, tree or preview shown.
+    fn help_overlay(&self) -> Vec<Line<'static>> {
+        let Some(scope) = self.key_scope() else {
+            return vec![];
+        };
+        let key_line = |key: &str, help: &str| Line::from(format!("{:>15}  {}", key, help));
+
+        let mut lines = vec![Line::from(" Actions")];
+        for (_, chord, help) in self.keys.actions(scope) {
+            match chord {
+                Some(chord) => lines.push(key_line(&chord.to_string(), help)),
+                None => lines.push(key_line("-", &format!("{} (Commands only)", help))),
+            }
+        }
+
+        let panes: &[&[(&str, &str)]] = match self.state {
+            AppState::SelectProject | AppState::EditScrolls => &[LIST_HELP],
+            AppState::SelectPrompt => &[TREE_HELP, PREVIEW_HELP],
+            AppState::AskModel => &[PREVIEW_HELP],
+            _ => &[],
+        };
+        if !panes.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(" Navigation"));
         }
+        for (key, help) in panes.iter().flat_map(|keys| keys.iter()) {
+            lines.push(key_line(key, help));
+        }
+        lines
+    }
+
+    /// The command palette, listing the actions of the current state with their keys.
+    fn palette(&self) -> SelectList {
+        let actions = match self.key_scope() {
+            Some(scope) => self.keys.actions(scope),
+            None => vec![],
+        };
+        let items = actions
+            .into_iter()
+            .filter(|(action, ..)| *action != InputEvent::Palette)
+            .map(|(action, chord, help)| {
+                let key = chord.map_or(String::new(), |c| format!("[{}]", c));
+                let text = format!("{:<9} {} ({})", key, help, action.name());
+                SelectItem::new(action.name(), &text)
+            })
+            .collect();
+        SelectList::picker(items)
     }
 
     /// Handles user input events (like keypresses) based on the application's current state.
@@ -770,7 +1098,181 @@ impl Legatio {
         pool: &SqlitePool,
         key_event: KeyEvent,
     ) -> Result<AppState> {
+        // An overlay takes every key until it closes
+        match self.overlay.take() {
+            Some(Overlay::Help | Overlay::Notifications) => return Ok(self.state),
+            Some(Overlay::Palette(mut palette)) => {
+                match palette.handle_key(key_event) {
+                    ListAction::Select(name) => {
+                        return self.run_action(pool, self.palette_action(&name)).await;
+                    }
+                    ListAction::Cancel => {}
+                    _ => self.overlay = Some(Overlay::Palette(palette)),
+                }
+                return Ok(self.state);
+            }
+            None => {}
+        }
+        // A search in the preview takes every key
+        let preview_shown = matches!(self.state, AppState::SelectPrompt | AppState::AskModel);
+        if preview_shown && self.preview.is_searching() {
+            self.preview.handle_key(key_event);
+            return Ok(self.state);
+        }
+        // The bottom pane gets the navigation and filter keys first
+        if let Some(mut pane) = self.panes().0 {
+            match pane.handle_key(key_event) {
+                ListAction::Ignored => {}
+                ListAction::Select(id) => return self.open_pane_item(pool, &id).await,
+                _ => return Ok(self.state),
+            }
+        }
+        if preview_shown && self.preview.handle_key(key_event) {
+            return Ok(self.state);
+        }
         let input_event = self.state_specific_keys(key_event); // Get state-specific keys
+                                                               // The question box types every key left without an action
+        if let (AppState::WriteQuestion, InputEvent::NoOp) = (self.state, input_event) {
+            self.question.handle_key(key_event);
+            return Ok(self.state);
+        }
+        self.run_action(pool, input_event).await
+    }
+
+    /// Handles a mouse event: a click highlights a project, prompt or scroll and a
+    /// second click opens it, the wheel scrolls the pane or preview under the pointer,
+    /// and dragging the bottom border of the top box resizes it.
+    ///
+    /// ### Arguments:
+    /// `pool` - The database connection pool.
+    /// `mouse` - The mouse event.
+    ///
+    /// ### Returns:
+    /// - `Result<AppState>`: Returns the next `AppState` after processing the event.
+    async fn handle_mouse(&mut self, pool: &SqlitePool, mouse: MouseEvent) -> Result<AppState> {
+        // The palette takes the mouse, the other overlays close on a click
+        match self.overlay.take() {
+            Some(Overlay::Palette(mut palette)) => {
+                if let ListAction::Select(name) = palette.handle_mouse(mouse) {
+                    return self.run_action(pool, self.palette_action(&name)).await;
+                }
+                self.overlay = Some(Overlay::Palette(palette));
+                return Ok(self.state);
+            }
+            Some(overlay) => {
+                if !matches!(mouse.kind, MouseEventKind::Down(_)) {
+                    self.overlay = Some(overlay);
+                }
+                return Ok(self.state);
+            }
+            None => {}
+        }
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left)
+                if self.split.on_border(mouse.column, mouse.row) =>
+            {
+                self.split.dragging = true;
+                return Ok(self.state);
+            }
+            MouseEventKind::Drag(MouseButton::Left) if self.split.dragging => {
+                self.split.drag_to(mouse.row);
+                return Ok(self.state);
+            }
+            MouseEventKind::Up(MouseButton::Left) if self.split.dragging => {
+                self.split.dragging = false;
+                return Ok(self.state);
+            }
+            _ => {}
+        }
+        let preview_shown = matches!(
+            self.state,
+            AppState::SelectPrompt | AppState::AskModel | AppState::WriteQuestion
+        );
+        if preview_shown && self.preview.handle_mouse(mouse) {
+            return Ok(self.state);
+        }
+        if let Some(mut pane) = self.panes().0 {
+            if let ListAction::Select(id) = pane.handle_mouse(mouse) {
+                return self.open_pane_item(pool, &id).await;
+            }
+        }
+        Ok(self.state)
+    }
+
+    /// Drops the cached prompts of the current project with their marks, so the
+    /// next draw reads both again and rebuilds the tree.
+    fn clear_prompt_cache(&mut self) {
+        self.prompt_list_cache = None;
+        self.marks_cache = None;
+    }
+
+    /// The action of the current view named `name` in the command palette.
+    fn palette_action(&self, name: &str) -> InputEvent {
+        self.key_scope()
+            .and_then(|scope| {
+                self.keys
+                    .actions(scope)
+                    .into_iter()
+                    .find(|(action, ..)| action.name() == name)
+            })
+            .map_or(InputEvent::NoOp, |(action, ..)| action)
+    }
+
+    /// Reads `themes.toml` again when it changed and takes the colors of the
+    /// configured theme. A broken file or an unknown theme is reported in the
+    /// status bar, keeping the colors in use.
+    ///
+    /// ### Returns:
+    /// - `true` if the themes were read again and the screen needs a redraw.
+    fn reload_theme(&mut self) -> bool {
+        match themes_path().and_then(|path| self.themes.refresh(&path)) {
+            Ok(false) => false,
+            Ok(true) => {
+                let name = self.user_config.as_ref().map_or("", |c| c.theme.as_str());
+                match self.themes.colors(name) {
+                    Ok(colors) => self.colors = colors,
+                    Err(err) => self.status.error(&err),
+                }
+                true
+            }
+            Err(err) => {
+                self.status.error(&err);
+                true
+            }
+        }
+    }
+
+    /// Runs an action of the current state, bound to a key or picked in the command palette.
+    ///
+    /// ### Arguments:
+    /// `pool` - The database connection pool.
+    /// `input_event` - The action to run.
+    ///
+    /// ### Returns:
+    /// - `Result<AppState>`: Returns the next `AppState` after running the action.
+    async fn run_action(&mut self, pool: &SqlitePool, input_event: InputEvent) -> Result<AppState> {
+        match input_event {
+            InputEvent::Help => {
+                self.overlay = Some(Overlay::Help);
+                return Ok(self.state);
+            }
+            InputEvent::Palette => {
+                self.overlay = Some(Overlay::Palette(self.palette()));
+                return Ok(self.state);
+            }
+            InputEvent::Notifications => {
+                self.overlay = Some(Overlay::Notifications);
+                return Ok(self.state);
+            }
+            InputEvent::Refresh => {
+                // Clear the caches so the next draw reads the database again
+                self.project_list_cache = None;
+                self.clear_prompt_cache();
+                self.scroll_list_cache = None;
+                return Ok(self.state);
+            }
+            _ => {}
+        }
 
         match self.state {
             AppState::SelectProject => self.process_select_project_input(input_event, pool).await,
@@ -778,9 +1280,15 @@ impl Legatio {
             AppState::AskModel => self.process_ask_model_input(input_event, pool).await,
             AppState::EditScrolls => self.process_edit_scrolls_input(input_event, pool).await,
             AppState::AskModelConfirmation => {
-                self.process_confirmation_popup_input(input_event, pool).await
+                self.process_confirmation_popup_input(input_event, pool)
+                    .await
+            }
+            AppState::DeletePromptConfirmation => {
+                self.process_delete_confirmation_input(input_event, pool)
+                    .await
             }
-            AppState::Quit => Ok(AppState::Quit)
+            AppState::WriteQuestion => self.process_write_question_input(input_event, pool).await,
+            AppState::Quit => Ok(AppState::Quit),
         }
     }
 
@@ -814,37 +1322,31 @@ impl Legatio {
                     p
                 };
                 if !projects.is_empty() {
-                    let (_, str_names) = build_select_project(&projects);
-                    if let Some(selected_project) = item_selector(str_names.clone())? {
-                        let sel_idx = str_names
-                            .iter()
-                            .position(|p| *p == selected_project)
-                            .unwrap();
-                        self.current_project = Some(projects[sel_idx].clone());
-
-                        // Clear cache for new project
-                        self.scroll_list_cache = None;
-                        self.prompt_list_cache = None;
-                        return Ok(AppState::SelectPrompt);
+                    if let Some(project) = pick_project(&projects, self.project_list.selected_id())?
+                    {
+                        self.open_project(project);
+                        Ok(AppState::SelectPrompt)
                     } else {
-                        enable_raw_mode()?;
-                        return Ok(AppState::SelectProject);
+                        Ok(AppState::SelectProject)
                     }
                 } else {
-                    let selected_dir = select_directories(None).unwrap().unwrap();
+                    let Some(selected_dir) = select_directories(None)? else {
+                        self.status.info("No directory selected");
+                        return Ok(AppState::SelectProject);
+                    };
                     let project = Project::new(&selected_dir);
                     store_project(pool, &project).await?;
-                    self.current_project = Some(project.clone());
                     // Clear cache
-                    self.project_list_cache = Some(vec![project]);
-                    // Clear cache for new project
-                    self.scroll_list_cache = None;
-                    self.prompt_list_cache = None;
-                    return Ok(AppState::EditScrolls);
+                    self.project_list_cache = Some(vec![project.clone()]);
+                    self.open_project(project);
+                    Ok(AppState::EditScrolls)
                 }
             }
             InputEvent::New => {
-                let selected_dir = select_directories(None).unwrap().unwrap();
+                let Some(selected_dir) = select_directories(None)? else {
+                    self.status.info("No directory selected");
+                    return Ok(AppState::SelectProject);
+                };
                 // Fetch all projects from cache
                 let projects = if let Some(cache) = &self.project_list_cache {
                     cache.clone()
@@ -854,19 +1356,16 @@ impl Legatio {
                     p
                 };
                 let old_proj = projects.iter().find(|p| p.project_path == selected_dir);
-                if old_proj.is_some() {
-                    self.current_project = Some(old_proj.unwrap().to_owned());
+                if let Some(old_proj) = old_proj {
+                    self.open_project(old_proj.to_owned());
                 } else {
                     let project = Project::new(&selected_dir);
                     store_project(pool, &project).await?;
-                    self.current_project = Some(project.to_owned());
+                    self.open_project(project);
                     // Clear project cache
                     self.project_list_cache = None;
                 }
-                // Clear cache for new project
-                self.scroll_list_cache = None;
-                self.prompt_list_cache = None;
-                return Ok(AppState::EditScrolls);
+                Ok(AppState::EditScrolls)
             }
             InputEvent::Delete => {
                 // Fetch all projects from cache
@@ -878,28 +1377,149 @@ impl Legatio {
                     p
                 };
                 if !projects.is_empty() {
-                    let (_, str_names) = build_select_project(&projects);
-                    if let Some(selected_project) = item_selector(str_names.clone())? {
-                        let sel_idx = str_names
-                            .iter()
-                            .position(|p| *p == selected_project)
-                            .unwrap();
-
-                        delete_project(pool, &projects[sel_idx].project_id).await?;
+                    if let Some(project) = pick_project(&projects, self.project_list.selected_id())?
+                    {
+                        self.backup(pool, "delete-project").await?;
+                        delete_project(pool, &project.project_id).await?;
+                        remove_draft(&project.project_id)?;
+                        if self
+                            .current_project
+                            .as_ref()
+                            .is_some_and(|p| p.project_id == project.project_id)
+                        {
+                            self.current_project = None;
+                            self.current_prompt = None;
+                            self.current_branch = None;
+                            self.scroll_list_cache = None;
+                            self.clear_prompt_cache();
+                        }
                         // Clear the cache
                         self.project_list_cache = None;
                     } else {
                         return Ok(AppState::SelectProject);
                     }
                 }
-                return Ok(AppState::SelectProject);
+                Ok(AppState::SelectProject)
             }
-            InputEvent::Quit => Ok(AppState::Quit),
-            _ => Ok(AppState::SelectProject),
-        }
-    }
+            InputEvent::Export => {
+                // Fetch all projects from cache
+                let projects = if let Some(cache) = &self.project_list_cache {
+                    cache.clone()
+                } else {
+                    let p = get_projects(pool).await?;
+                    self.project_list_cache = Some(p.clone());
+                    p
+                };
+                if !projects.is_empty() {
+                    if let Some(project) = pick_project(&projects, self.project_list.selected_id())?
+                    {
+                        let path = export_bundle(pool, &project).await?;
+                        self.status.info(&format!("Exported to {}", path.display()));
+                    }
+                }
+                Ok(AppState::SelectProject)
+            }
+            InputEvent::Import => {
+                let Some(bundle_path) = select_files(None)? else {
+                    return Ok(AppState::SelectProject);
+                };
+                let bundle = read_bundle(&PathBuf::from(bundle_path))?;
+                let Some(root_path) = select_directories(None)? else {
+                    return Ok(AppState::SelectProject);
+                };
+                let imported = import_bundle(pool, &bundle, &root_path).await?;
+                self.status.info(&format!(
+                    "Imported {}: {} prompt(s) added, {} skipped, {} scroll(s) added, {} skipped",
+                    imported.project.name(),
+                    imported.prompts_added,
+                    imported.prompts_skipped,
+                    imported.scrolls_added,
+                    imported.scrolls_skipped
+                ));
+                self.open_project(imported.project);
+
+                // Clear cache
+                self.project_list_cache = None;
+                Ok(AppState::SelectPrompt)
+            }
+            InputEvent::Relocate => {
+                // Fetch all projects from cache
+                let projects = if let Some(cache) = &self.project_list_cache {
+                    cache.clone()
+                } else {
+                    let p = get_projects(pool).await?;
+                    self.project_list_cache = Some(p.clone());
+                    p
+                };
+                if !projects.is_empty() {
+                    if let Some(project) = pick_project(&projects, self.project_list.selected_id())?
+                    {
+                        if let Some(new_path) = select_directories(None)? {
+                            let (project, missing) =
+                                relocate_project(pool, &project, &new_path).await?;
+                            if !missing.is_empty() {
+                                let paths: Vec<&str> =
+                                    missing.iter().map(|s| s.scroll_path.as_str()).collect();
+                                self.status.warn(&format!(
+                                    "{} scroll(s) not found in {}: {}",
+                                    missing.len(),
+                                    new_path,
+                                    paths.join(", ")
+                                ));
+                            }
+                            if self
+                                .current_project
+                                .as_ref()
+                                .is_some_and(|p| p.project_id == project.project_id)
+                            {
+                                self.current_project = Some(project);
+                            }
 
-    /// Processes user input in the `AppState::SelectPrompt` state.
+                            // Clear the cache
+                            self.project_list_cache = None;
+                            self.scroll_list_cache = None;
+                        }
+                    }
+                }
+                Ok(AppState::SelectProject)
+            }
+            InputEvent::EditProject => {
+                // Fetch all projects from cache
+                let projects = if let Some(cache) = &self.project_list_cache {
+                    cache.clone()
+                } else {
+                    let p = get_projects(pool).await?;
+                    self.project_list_cache = Some(p.clone());
+                    p
+                };
+                if !projects.is_empty() {
+                    if let Some(project) = pick_project(&projects, self.project_list.selected_id())?
+                    {
+                        let template = project_details_template(&project);
+                        if let Some(details) = edit_text(&template, "toml")? {
+                            let project = parse_project_details(&project, &details)?;
+                            update_project_details(pool, &project).await?;
+                            if self
+                                .current_project
+                                .as_ref()
+                                .is_some_and(|p| p.project_id == project.project_id)
+                            {
+                                self.current_project = Some(project);
+                            }
+
+                            // Clear the cache
+                            self.project_list_cache = None;
+                        }
+                    }
+                }
+                Ok(AppState::SelectProject)
+            }
+            InputEvent::Quit => Ok(AppState::Quit),
+            _ => Ok(AppState::SelectProject),
+        }
+    }
+
+    /// Processes user input in the `AppState::SelectPrompt` state.
     ///
     /// In this state, users can:
     /// - Browse and select prompts.
@@ -921,7 +1541,7 @@ impl Legatio {
     ) -> Result<AppState> {
         match key_event {
             InputEvent::Select => {
-                if let Some(project) = &self.current_project {
+                if let Some(project) = self.current_project.clone() {
                     // Fetch all prompts from cache
                     let prompts = if let Some(cache) = &self.prompt_list_cache {
                         cache.clone()
@@ -932,44 +1552,27 @@ impl Legatio {
                     };
 
                     if !prompts.is_empty() {
-                        let project_name = project
-                            .project_path
-                            .split('/')
-                            .next_back()
-                            .unwrap_or("[Unnamed Project]");
-                        let mut concat_prompts =
-                            vec![format!(" -[ {} -:- Unchained]-", project_name)];
-                        for p in prompts.iter() {
-                            let (p_str, o_str) = format_prompt(p);
-                            concat_prompts.push(format!("{}\n{}", p_str, o_str));
-                        }
-                        concat_prompts.reverse();
-
-                        if let Some(selected_prompt) = item_selector(concat_prompts.clone())? {
-                            let mut idx = concat_prompts
-                                .iter()
-                                .position(|p| p == &selected_prompt)
-                                .unwrap();
-
-                            if idx < prompts.len() {
-                                idx = prompts.len() - 1 - idx;
-                                self.current_prompt = prompts.get(idx).map(|p| p.to_owned());
-                                chain_into_canvas(
-                                    project,
-                                    Some(&prompts),
-                                    self.current_prompt.as_ref(),
-                                )?;
-                            } else {
-                                self.current_prompt = None;
-                                chain_into_canvas(project, None, None)?;
-                            }
-                        } else {
-                            enable_raw_mode()?;
+                        let marks = get_marks(pool, &project.project_id).await?;
+                        let mut items: Vec<SelectItem> = prompts
+                            .iter()
+                            .rev()
+                            .map(|p| SelectItem::new(&p.prompt_id, &picker_line(p, &marks)))
+                            .collect();
+                        items.push(SelectItem::new(
+                            &project.project_id,
+                            &format!(" -[ {} -:- Unchained]-", project.name()),
+                        ));
+
+                        let selected = self.prompt_tree.selected_id();
+                        let Some(id) = select_item("Select a prompt", items, selected)? else {
                             return Ok(AppState::SelectPrompt);
-                        }
+                        };
+                        // Anything but a prompt starts a new chain
+                        let prompt = prompts.iter().find(|p| p.prompt_id == id).cloned();
+                        self.open_prompt(&project, &prompts, prompt, &marks)?;
                     } else {
                         // No prompts so only place holder
-                        chain_into_canvas(project, None, None)?;
+                        chain_into_canvas(&project, None, None)?;
                     }
                     return Ok(AppState::AskModel);
                 }
@@ -977,30 +1580,259 @@ impl Legatio {
             InputEvent::Delete => {
                 if let Some(project) = &self.current_project {
                     let prompts = get_prompts(pool, &project.project_id).await?;
-                    let project_name = project
-                        .project_path
-                        .split('/')
-                        .next_back()
-                        .unwrap_or("[Unnamed Project]");
+                    let marks = get_marks(pool, &project.project_id).await?;
 
-                    let mut concat_prompts = vec![format!(" -[ {} -:- Unchained]-", project_name)];
-                    for p in prompts.iter() {
-                        let (p_str, o_str) = format_prompt(p);
-                        concat_prompts.push(format!("{}\n{}", p_str, o_str));
+                    let selected = self.prompt_tree.selected_id();
+                    if let Some(prompt) = pick_prompt(&prompts, &marks, selected)? {
+                        let descendants = prompt_descendants(&prompts, &prompt);
+
+                        let mode = if descendants.is_empty() {
+                            DeleteMode::Subtree
+                        } else {
+                            let modes = vec![
+                                format!(
+                                    "Remove this prompt only, its {} descendant(s) move up to its parent",
+                                    descendants.len()
+                                ),
+                                format!(
+                                    "Delete this prompt and its {} descendant(s)",
+                                    descendants.len()
+                                ),
+                            ];
+                            match item_selector(modes.clone())? {
+                                Some(selected) if selected == modes[0] => DeleteMode::Splice,
+                                Some(_) => DeleteMode::Subtree,
+                                None => return Ok(AppState::SelectPrompt),
+                            }
+                        };
+
+                        let mut affected = vec![prompt];
+                        if mode == DeleteMode::Subtree {
+                            affected.extend(descendants);
+                        }
+                        self.pending_delete = Some(PendingDelete::Trash(mode, affected));
+                        return Ok(AppState::DeletePromptConfirmation);
+                    }
+                }
+                return Ok(AppState::SelectPrompt);
+            }
+            InputEvent::Trash => {
+                if let Some(project) = &self.current_project {
+                    let trash = get_trash(pool, &project.project_id).await?;
+                    if trash.is_empty() {
+                        return Ok(AppState::SelectPrompt);
                     }
 
-                    if let Some(selected_prompt) = item_selector(concat_prompts.clone())? {
-                        let index = concat_prompts
+                    let mut entries = vec![SelectItem::new(
+                        &project.project_id,
+                        " -[ Empty the trash ]-",
+                    )];
+                    entries.extend(
+                        trash
                             .iter()
-                            .position(|p| p == &selected_prompt)
-                            .unwrap();
-                        delete_prompt(pool, &prompts[index]).await?;
+                            .map(|t| SelectItem::new(&t.batch.batch_id, &format_trash_entry(t))),
+                    );
+                    if let Some(selected) = select_item("Trash", entries, None)? {
+                        if selected == project.project_id {
+                            let prompts = trash.into_iter().flat_map(|t| t.prompts).collect();
+                            self.pending_delete = Some(PendingDelete::EmptyTrash(
+                                project.project_id.clone(),
+                                prompts,
+                            ));
+                            return Ok(AppState::DeletePromptConfirmation);
+                        } else {
+                            let batch_id = &selected;
+                            let actions = vec![String::from("Restore"), String::from("Purge")];
+                            match item_selector(actions)?.as_deref() {
+                                Some("Restore") => {
+                                    restore_trash(pool, batch_id).await?;
+                                    // Clear the cache
+                                    self.clear_prompt_cache();
+                                }
+                                Some("Purge") => {
+                                    self.backup(pool, "purge-trash").await?;
+                                    purge_trash(pool, batch_id).await?;
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+                return Ok(AppState::SelectPrompt);
+            }
+            InputEvent::Mark => {
+                if let Some(project) = &self.current_project {
+                    let prompts = get_prompts(pool, &project.project_id).await?;
+                    let marks = get_marks(pool, &project.project_id).await?;
+                    let selected = self.prompt_tree.selected_id();
+                    if let Some(prompt) = pick_prompt(&prompts, &marks, selected)? {
+                        let template =
+                            prompt_marks_template(&prompt, &marks.prompt(&prompt.prompt_id));
+                        if let Some(text) = edit_text(&template, "toml")? {
+                            let new_marks = parse_prompt_marks(&text)?;
+                            set_prompt_marks(pool, &prompt, &new_marks).await?;
+                            self.clear_prompt_cache();
+                        }
+                    }
+                }
+                return Ok(AppState::SelectPrompt);
+            }
+            InputEvent::FilterTag => {
+                if let Some(project) = &self.current_project {
+                    let prompts = get_prompts(pool, &project.project_id).await?;
+                    let marks = get_marks(pool, &project.project_id).await?;
 
-                        // Clear the cache
-                        self.prompt_list_cache = None;
+                    let starred = String::from(" -[ Starred ]-");
+                    let mut filters = vec![starred.clone()];
+                    filters.extend(marks.all_tags().iter().map(|t| format!("#{}", t)));
+                    let Some(filter) = item_selector(filters)? else {
+                        return Ok(AppState::SelectPrompt);
+                    };
+                    let matching = if filter == starred {
+                        marks.starred_prompts(&prompts)
+                    } else {
+                        marks.tagged(&prompts, filter.trim_start_matches('#'))
+                    };
+
+                    if let Some(prompt) = pick_prompt(&matching, &marks, None)? {
+                        self.current_branch = marks.branch_of(&prompt.prompt_id);
+                        self.current_prompt = Some(prompt);
+                        chain_into_canvas(project, Some(&prompts), self.current_prompt.as_ref())?;
+                        return Ok(AppState::AskModel);
+                    }
+                }
+                return Ok(AppState::SelectPrompt);
+            }
+            InputEvent::BranchHeads => {
+                if let Some(project) = &self.current_project {
+                    let prompts = get_prompts(pool, &project.project_id).await?;
+                    let marks = get_marks(pool, &project.project_id).await?;
+                    let heads = marks.heads(&prompts);
+                    if heads.is_empty() {
+                        return Ok(AppState::SelectPrompt);
+                    }
+
+                    let items = heads
+                        .iter()
+                        .map(|(label, prompt)| {
+                            let (p_str, _) = format_prompt(prompt);
+                            SelectItem::new(&label.name, &format!(" -[ {} ]-{}", label.name, p_str))
+                        })
+                        .collect::<Vec<_>>();
+                    let selected = self.current_branch.as_deref();
+                    if let Some(name) = select_item("Branch heads", items, selected)? {
+                        if let Some((label, prompt)) =
+                            heads.iter().find(|(label, _)| label.name == name)
+                        {
+                            self.current_prompt = Some((*prompt).clone());
+                            self.current_branch = Some(label.name.clone());
+                            chain_into_canvas(
+                                project,
+                                Some(&prompts),
+                                self.current_prompt.as_ref(),
+                            )?;
+                            return Ok(AppState::AskModel);
+                        }
+                    }
+                }
+                return Ok(AppState::SelectPrompt);
+            }
+            InputEvent::Reorganize => {
+                if let Some(project) = &self.current_project {
+                    let prompts = get_prompts(pool, &project.project_id).await?;
+                    let marks = get_marks(pool, &project.project_id).await?;
+                    if prompts.is_empty() {
+                        return Ok(AppState::SelectPrompt);
+                    }
+
+                    let actions = vec![
+                        String::from("Move prompt"),
+                        String::from("Move prompt with its subtree"),
+                        String::from("Detach subtree into a new root"),
+                        String::from("Copy prompt"),
+                        String::from("Copy prompt with its subtree"),
+                    ];
+                    let Some(action) = item_selector(actions)? else {
+                        return Ok(AppState::SelectPrompt);
+                    };
+                    let selected = self.prompt_tree.selected_id();
+                    let Some(prompt) = pick_prompt(&prompts, &marks, selected)? else {
+                        return Ok(AppState::SelectPrompt);
+                    };
+                    let scope = if action.ends_with("prompt") {
+                        PromptScope::Single
+                    } else {
+                        PromptScope::Subtree
+                    };
+
+                    if action.starts_with("Move") {
+                        let targets = move_targets(&prompts, &prompt, scope);
+                        if let Some(parent_id) = pick_parent(&project.project_id, &targets, &marks)?
+                        {
+                            move_prompt(pool, &prompt, &parent_id, scope).await?;
+                        }
+                    } else if action.starts_with("Detach") {
+                        move_prompt(pool, &prompt, &project.project_id, scope).await?;
                     } else {
+                        let projects = get_projects(pool).await?;
+                        let Some(target) = pick_project(&projects, Some(&project.project_id))?
+                        else {
+                            return Ok(AppState::SelectPrompt);
+                        };
+                        let target = &target.project_id;
+                        let target_prompts = get_prompts(pool, target).await?;
+                        let target_marks = get_marks(pool, target).await?;
+                        if let Some(parent_id) =
+                            pick_parent(target, &target_prompts, &target_marks)?
+                        {
+                            copy_prompt(pool, &prompt, scope, target, &parent_id).await?;
+                        }
+                    }
+
+                    // The current prompt may have a new parent
+                    if let Some(current) = &self.current_prompt {
+                        self.current_prompt = get_prompts(pool, &project.project_id)
+                            .await?
+                            .into_iter()
+                            .find(|p| p.prompt_id == current.prompt_id);
+                    }
+                    // Clear the cache
+                    self.clear_prompt_cache();
+                }
+                return Ok(AppState::SelectPrompt);
+            }
+            InputEvent::Merge => {
+                if let Some(project) = self.current_project.clone() {
+                    let prompts = get_prompts(pool, &project.project_id).await?;
+                    let marks = get_marks(pool, &project.project_id).await?;
+                    let tips = branch_tips(&prompts, &marks);
+                    if tips.len() < 2 {
                         return Ok(AppState::SelectPrompt);
                     }
+
+                    let Some(first) = pick_prompt(&tips, &marks, None)? else {
+                        return Ok(AppState::SelectPrompt);
+                    };
+                    let others: Vec<Prompt> = tips
+                        .into_iter()
+                        .filter(|p| p.prompt_id != first.prompt_id)
+                        .collect();
+                    let Some(second) = pick_prompt(&others, &marks, None)? else {
+                        return Ok(AppState::SelectPrompt);
+                    };
+                    let modes = vec![
+                        String::from("Merge full branches"),
+                        String::from("Merge branch summaries"),
+                    ];
+                    let mode = match item_selector(modes)?.as_deref() {
+                        Some("Merge full branches") => MergeMode::Full,
+                        Some(_) => MergeMode::Summary,
+                        None => return Ok(AppState::SelectPrompt),
+                    };
+
+                    return self
+                        .produce_merge(pool, &project, &first, &second, mode)
+                        .await;
                 }
                 return Ok(AppState::SelectPrompt);
             }
@@ -1010,11 +1842,22 @@ impl Legatio {
             InputEvent::EditScrolls => {
                 return Ok(AppState::EditScrolls);
             }
-            InputEvent::Quit => {
-                //TODO handle store appstate
-                disable_raw_mode()?;
-                std::process::exit(0);
+            InputEvent::Export => {
+                self.export(pool, false).await?;
+                return Ok(AppState::SelectPrompt);
             }
+            InputEvent::Import => {
+                if let Some(project) = &self.current_project {
+                    if let Some(transcript) = select_files(None)? {
+                        import_transcript(pool, project, &transcript, None).await?;
+
+                        // Clear the cache
+                        self.clear_prompt_cache();
+                    }
+                }
+                return Ok(AppState::SelectPrompt);
+            }
+            InputEvent::Quit => return Ok(AppState::Quit),
             _ => {}
         }
         Ok(AppState::SelectPrompt)
@@ -1042,19 +1885,89 @@ impl Legatio {
             InputEvent::AskModel => {
                 if self.user_config.is_some() && self.user_config.as_ref().unwrap().ask_conf {
                     // Require confirmation for specific models
-                    return Ok(AppState::AskModelConfirmation);
+                    Ok(AppState::AskModelConfirmation)
                 } else {
                     return self.produce_question(pool).await;
                 }
             }
+            InputEvent::WriteQuestion => Ok(AppState::WriteQuestion),
+            InputEvent::OpenEditor => self.edit_question(pool).await,
             InputEvent::SwitchBranch => Ok(AppState::SelectPrompt),
             InputEvent::EditScrolls => Ok(AppState::EditScrolls),
+            InputEvent::Export => {
+                self.export(pool, true).await?;
+                Ok(AppState::AskModel)
+            }
             InputEvent::ChangeProject => Ok(AppState::SelectProject),
             InputEvent::Quit => Ok(AppState::Quit),
             _ => Ok(AppState::AskModel),
         }
     }
 
+    /// Processes user input in the `AppState::WriteQuestion` state.
+    ///
+    /// In this state, users:
+    /// - Write the next question in a multi-line box.
+    /// - Send it, or continue it in `$EDITOR`.
+    /// - Go back to `AppState::AskModel`, keeping the draft.
+    ///
+    /// ### Arguments:
+    /// `key_event` - The user input event.
+    /// `pool` - The database connection pool.
+    ///
+    /// ### Returns:
+    /// - `Result<AppState>`: Returns the next state of the application.
+    async fn process_write_question_input(
+        &mut self,
+        key_event: InputEvent,
+        pool: &SqlitePool,
+    ) -> Result<AppState> {
+        match key_event {
+            InputEvent::Submit => self.send_question(pool).await,
+            InputEvent::OpenEditor => self.edit_question(pool).await,
+            InputEvent::Cancel => Ok(AppState::AskModel),
+            _ => Ok(AppState::WriteQuestion),
+        }
+    }
+
+    /// Opens the draft question in `$EDITOR` and sends what was saved.
+    ///
+    /// ### Arguments:
+    /// `pool` - The database connection pool.
+    ///
+    /// ### Returns:
+    /// - `Result<AppState>`: The state of the ask flow, or the current state if
+    ///   the editor failed.
+    async fn edit_question(&mut self, pool: &SqlitePool) -> Result<AppState> {
+        match open_in_editor(&self.question.text(), "md")? {
+            Some(text) => {
+                self.question.set_text(text.trim_end_matches('\n'));
+                self.send_question(pool).await
+            }
+            None => Ok(self.state),
+        }
+    }
+
+    /// Sends the draft question through the ask flow, asking for confirmation
+    /// first when configured. A blank draft goes back to the question box.
+    ///
+    /// ### Arguments:
+    /// `pool` - The database connection pool.
+    ///
+    /// ### Returns:
+    /// - `Result<AppState>`: Returns the next state of the application.
+    async fn send_question(&mut self, pool: &SqlitePool) -> Result<AppState> {
+        if self.question.is_blank() {
+            return Ok(AppState::WriteQuestion);
+        }
+        self.pending_question = Some(self.question.text());
+        if self.user_config.as_ref().is_some_and(|c| c.ask_conf) {
+            Ok(AppState::AskModelConfirmation)
+        } else {
+            self.produce_question(pool).await
+        }
+    }
+
     /// Processes user input in the `AppState::EditScrolls` state.
     ///
     /// In this state, users can:
@@ -1077,11 +1990,10 @@ impl Legatio {
         match key_event {
             InputEvent::New => {
                 if let Some(project) = &self.current_project {
-                    disable_raw_mode()?;
-                    let selected_scroll = select_files(Some(&project.project_path))
-                        .unwrap()
-                        .unwrap_or(String::from(""));
-                    enable_raw_mode()?;
+                    let Some(selected_scroll) = select_files(Some(&project.project_path))? else {
+                        self.status.info("No file selected");
+                        return Ok(AppState::EditScrolls);
+                    };
                     // Fetch all scrolls from cache
                     let scrolls: Vec<Scroll> = if let Some(cache) = &self.scroll_list_cache {
                         cache.clone()
@@ -1090,15 +2002,18 @@ impl Legatio {
                         self.scroll_list_cache = Some(s.clone());
                         s
                     };
-                    let old_scroll = scrolls.iter().find(|s| s.scroll_path == selected_scroll);
+                    let old_scroll = scrolls.iter().find(|s| {
+                        resolve_scroll_path(&project.project_path, &s.scroll_path).as_path()
+                            == Path::new(&selected_scroll)
+                    });
                     if old_scroll.is_none() {
-                        let new_scroll = read_file(&selected_scroll, &project.project_id, None)?;
+                        let new_scroll = read_project_file(project, &selected_scroll)?;
                         store_scroll(pool, &new_scroll).await?;
                         // Initial scroll
                         self.scroll_list_cache = Some(vec![new_scroll]);
                     }
                 }
-                return Ok(AppState::EditScrolls);
+                Ok(AppState::EditScrolls)
             }
             InputEvent::Delete => {
                 if let Some(project) = &self.current_project {
@@ -1110,31 +2025,20 @@ impl Legatio {
                         self.scroll_list_cache = Some(s.clone());
                         s
                     };
-                    let scroll_names = scrolls
+                    let items = scrolls
                         .iter()
-                        .map(|s| s.scroll_path.clone())
+                        .map(|s| SelectItem::new(&s.scroll_id, &s.scroll_path))
                         .collect::<Vec<_>>();
 
-                    disable_raw_mode()?;
-                    if let Some(selected_scroll) = item_selector(scroll_names.clone())? {
-                        enable_raw_mode()?;
-                        let idx = scroll_names
-                            .iter()
-                            .position(|s| s == &selected_scroll)
-                            .unwrap();
+                    let selected = self.scroll_list.selected_id();
+                    if let Some(scroll_id) = select_item("Delete a scroll", items, selected)? {
+                        delete_scroll(pool, &scroll_id).await?;
 
-                        if idx < scrolls.len() {
-                            delete_scroll(pool, &scrolls[idx].scroll_id).await?;
-                            
-                            // Clear the cache
-                            self.scroll_list_cache = None;
-                        }
-                    } else {
-                        enable_raw_mode()?;
-                        return Ok(AppState::EditScrolls);
+                        // Clear the cache
+                        self.scroll_list_cache = None;
                     }
                 }
-                return Ok(AppState::EditScrolls);
+                Ok(AppState::EditScrolls)
             }
             InputEvent::SwitchBranch => Ok(AppState::SelectPrompt),
             InputEvent::ChangeProject => Ok(AppState::SelectProject),
@@ -1167,6 +2071,7 @@ impl Legatio {
             }
             InputEvent::Cancel => {
                 // User cancelled; return to the previous state (e.g., `AskModel`)
+                self.pending_question = None;
                 return Ok(AppState::AskModel);
             }
             _ => {}
@@ -1174,6 +2079,57 @@ impl Legatio {
         Ok(AppState::AskModelConfirmation)
     }
 
+    /// Processes user input in the `AppState::DeletePromptConfirmation` state.
+    ///
+    /// In this state, users:
+    /// - Confirm or cancel moving the pending prompts to the trash, or emptying it.
+    ///
+    /// ### Arguments:
+    /// `key_event` - The user input for confirmation or cancellation.
+    /// `pool` - The database connection pool.
+    ///
+    /// ### Returns:
+    /// - `Result<AppState>`: Returns the next state of the application.
+    async fn process_delete_confirmation_input(
+        &mut self,
+        key_event: InputEvent,
+        pool: &SqlitePool,
+    ) -> Result<AppState> {
+        match key_event {
+            InputEvent::Confirm => {
+                match self.pending_delete.take() {
+                    Some(PendingDelete::Trash(mode, prompts)) => {
+                        let entry = trash_prompt(pool, &prompts[0], mode).await?;
+                        if let Some(current) = &self.current_prompt {
+                            if entry
+                                .prompts
+                                .iter()
+                                .any(|p| p.prompt_id == current.prompt_id)
+                            {
+                                self.current_prompt = None;
+                                self.current_branch = None;
+                            }
+                        }
+
+                        // Clear the cache
+                        self.clear_prompt_cache();
+                    }
+                    Some(PendingDelete::EmptyTrash(project_id, _)) => {
+                        self.backup(pool, "empty-trash").await?;
+                        empty_trash(pool, &project_id).await?;
+                    }
+                    None => {}
+                }
+                Ok(AppState::SelectPrompt)
+            }
+            InputEvent::Cancel => {
+                self.pending_delete = None;
+                Ok(AppState::SelectPrompt)
+            }
+            _ => Ok(AppState::DeletePromptConfirmation),
+        }
+    }
+
     /// Generates and sends a new question to the AI model for processing.
     ///
     /// This function:
@@ -1199,7 +2155,7 @@ impl Legatio {
             };
             let mut new_scrolls = Vec::new();
             for scroll in scrolls.iter() {
-                let new_scroll = update_scroll_content(pool, scroll).await?;
+                let new_scroll = update_scroll_content(pool, &project.project_path, scroll).await?;
                 new_scrolls.push(new_scroll);
             }
             let sys_prompt = system_prompt(&new_scrolls).await;
@@ -1211,21 +2167,23 @@ impl Legatio {
                 chain = Some(prompt_chain(&prompts, curr_prompt));
             }
 
-            let final_prompt = chain_match_canvas(project).unwrap_or(String::from("."));
-
-            let prompt_chain: Option<Vec<AiPrompt>> = match chain {
-                Some(prompts) => Some(
-                    prompts
-                        .iter()
-                        .map(|p| AiPrompt {
-                            content: p.content.to_owned(),
-                            output: p.output.to_owned(),
-                        })
-                        .collect(),
-                ),
-                None => None,
+            // A question written in the application, else the one below the canvas marker
+            let from_draft = self.pending_question.is_some();
+            let final_prompt = match self.pending_question.take() {
+                Some(question) => question,
+                None => chain_match_canvas(project).unwrap_or(String::from(".")),
             };
 
+            let prompt_chain: Option<Vec<AiPrompt>> = chain.map(|prompts| {
+                prompts
+                    .iter()
+                    .map(|p| AiPrompt {
+                        content: p.content.to_owned(),
+                        output: p.output.to_owned(),
+                    })
+                    .collect()
+            });
+
             let question = Question {
                 system_prompt: if sys_prompt.is_empty() {
                     None
@@ -1236,6 +2194,8 @@ impl Legatio {
                 new_prompt: final_prompt.to_owned(),
             };
 
+            // Saved as a draft if the application dies before the answer is stored
+            let _in_flight = InFlight::start(&project.project_id, &final_prompt);
             let output =
                 ask_question(&self.user_config.as_ref().unwrap().ai_conf, question).await?;
 
@@ -1249,17 +2209,463 @@ impl Legatio {
                     .map_or(project.project_id.clone(), |p| p.prompt_id.clone()),
             );
 
-            store_prompt(pool, &new_prompt).await?;
-            self.current_prompt = Some(new_prompt);
+            // Asking the same question twice moves to the existing prompt
+            let stored = store_prompt(pool, &new_prompt).await?;
+            let new_prompt = Prompt {
+                prompt_id: stored.id().to_string(),
+                ..new_prompt
+            };
 
-            // Clear cache
+            // Asking on the head of a branch moves the branch along
+            if let Some(branch) = &self.current_branch {
+                let advanced = advance_branch(
+                    pool,
+                    &project.project_id,
+                    branch,
+                    &new_prompt.prev_prompt_id,
+                    &new_prompt.prompt_id,
+                )
+                .await?;
+                if !advanced {
+                    self.current_branch = None;
+                }
+            }
+            self.current_prompt = Some(new_prompt.clone());
+            if from_draft {
+                self.question.clear();
+            }
+
+            // Clear cache, field by field as `project` still borrows `self`
             self.prompt_list_cache = None;
+            self.marks_cache = None;
 
             let mut new_prompts = prompts.clone();
-            new_prompts.push(self.current_prompt.as_ref().unwrap().clone());
+            if !stored.is_reused() {
+                new_prompts.push(new_prompt);
+            }
 
             chain_into_canvas(project, Some(&new_prompts), self.current_prompt.as_ref())?;
         }
         Ok(AppState::AskModel)
     }
+
+    /// The navigable widget of the bottom pane in the current state, if any,
+    /// the preview drawn next to it and the question box while writing one.
+    fn panes(&mut self) -> (Option<Pane<'_>>, &mut Preview, Option<&mut TextInput>) {
+        let pane = match self.state {
+            AppState::SelectProject => Some(Pane::List(&mut self.project_list)),
+            AppState::SelectPrompt => Some(Pane::Tree(&mut self.prompt_tree)),
+            AppState::EditScrolls => Some(Pane::List(&mut self.scroll_list)),
+            _ => None,
+        };
+        let input = match self.state {
+            AppState::WriteQuestion => Some(&mut self.question),
+            _ => None,
+        };
+        (pane, &mut self.preview, input)
+    }
+
+    /// Opens the item picked with `Enter` in the bottom pane: a project shows its
+    /// prompts, a prompt is continued in the canvas.
+    ///
+    /// ### Arguments:
+    /// `pool` - The database connection pool.
+    /// `id` - The id of the picked project or prompt.
+    ///
+    /// ### Returns:
+    /// - `Result<AppState>`: The next state of the application.
+    async fn open_pane_item(&mut self, pool: &SqlitePool, id: &str) -> Result<AppState> {
+        match self.state {
+            AppState::SelectProject => {
+                let projects = get_projects(pool).await?;
+                if let Some(project) = projects.into_iter().find(|p| p.project_id == id) {
+                    self.open_project(project);
+                    return Ok(AppState::SelectPrompt);
+                }
+            }
+            AppState::SelectPrompt => {
+                if let Some(project) = self.current_project.clone() {
+                    let prompts = get_prompts(pool, &project.project_id).await?;
+                    let marks = get_marks(pool, &project.project_id).await?;
+                    let prompt = prompts.iter().find(|p| p.prompt_id == id).cloned();
+                    if prompt.is_some() {
+                        self.open_prompt(&project, &prompts, prompt, &marks)?;
+                        return Ok(AppState::AskModel);
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(self.state)
+    }
+
+    /// Makes `project` the active project, dropping the prompt, branch and caches
+    /// of the previous one.
+    fn open_project(&mut self, project: Project) {
+        self.current_project = Some(project);
+
+        // Clear cache for new project
+        self.scroll_list_cache = None;
+        self.clear_prompt_cache();
+        self.current_prompt = None;
+        self.current_branch = None;
+        self.restore_draft();
+    }
+
+    /// Puts the question left unsent in the current project, by a crash or on
+    /// quitting, back in the question box.
+    fn restore_draft(&mut self) {
+        let Some(project) = &self.current_project else {
+            return;
+        };
+        match take_draft(&project.project_id) {
+            Ok(Some(draft)) => {
+                self.question.set_text(&draft);
+                self.status
+                    .info("The unsent question is back in the question box");
+            }
+            Ok(None) => {}
+            Err(err) => self.status.error(&err),
+        }
+    }
+
+    /// Makes `prompt` the current prompt, on the branch it heads if any, and
+    /// writes its chain to the canvas. `None` starts a new chain.
+    fn open_prompt(
+        &mut self,
+        project: &Project,
+        prompts: &[Prompt],
+        prompt: Option<Prompt>,
+        marks: &ProjectMarks,
+    ) -> Result<()> {
+        self.current_branch = prompt.as_ref().and_then(|p| marks.branch_of(&p.prompt_id));
+        self.current_prompt = prompt;
+        match &self.current_prompt {
+            Some(_) => chain_into_canvas(project, Some(prompts), self.current_prompt.as_ref()),
+            None => chain_into_canvas(project, None, None),
+        }
+    }
+
+    /// Restores the project, branch and prompt of the last session.
+    ///
+    /// A branch takes the user back to its head, otherwise the stored prompt is used.
+    /// Falls back to selecting among `projects` when the project no longer exists.
+    ///
+    /// ### Arguments:
+    /// `pool` - The database connection pool.
+    /// `projects` - All projects of the database.
+    ///
+    /// ### Returns:
+    /// - `Result<()>` indicating success or failure.
+    async fn restore_session(&mut self, pool: &SqlitePool, projects: &[Project]) -> Result<()> {
+        let session = get_session(pool).await?;
+        let restored = session
+            .project_id
+            .as_ref()
+            .and_then(|id| projects.iter().find(|p| &p.project_id == id));
+
+        if let Some(project) = restored {
+            self.current_project = Some(project.clone());
+            self.state = AppState::SelectPrompt;
+            self.restore_draft();
+
+            let prompts = get_prompts(pool, &project.project_id).await?;
+            let marks = get_marks(pool, &project.project_id).await?;
+            let head = session.branch.as_ref().and_then(|name| {
+                marks
+                    .heads(&prompts)
+                    .into_iter()
+                    .find(|(label, _)| &label.name == name)
+            });
+            if let Some((label, prompt)) = head {
+                self.current_branch = Some(label.name);
+                self.current_prompt = Some(prompt);
+                self.state = AppState::AskModel;
+            } else if let Some(prompt) = session
+                .prompt_id
+                .as_ref()
+                .and_then(|id| prompts.iter().find(|p| &p.prompt_id == id))
+            {
+                self.current_prompt = Some(prompt.clone());
+                self.state = AppState::AskModel;
+            }
+        } else if !projects.is_empty() {
+            self.current_project = Some(projects[0].clone());
+            self.state = AppState::SelectProject;
+        }
+
+        self.session = session;
+        Ok(())
+    }
+
+    /// Stores the current project, branch and prompt when they changed since
+    /// the last call.
+    ///
+    /// ### Arguments:
+    /// `pool` - The database connection pool.
+    ///
+    /// ### Returns:
+    /// - `Result<()>` indicating success or failure.
+    async fn save_session(&mut self, pool: &SqlitePool) -> Result<()> {
+        let session = Session {
+            project_id: self.current_project.as_ref().map(|p| p.project_id.clone()),
+            branch: self.current_branch.clone(),
+            prompt_id: self.current_prompt.as_ref().map(|p| p.prompt_id.clone()),
+        };
+        if session != self.session {
+            store_session(pool, &session).await?;
+            self.session = session;
+        }
+        Ok(())
+    }
+
+    /// Asks the AI model to reconcile two branches and stores its answer as a
+    /// prompt with both heads as parents.
+    ///
+    /// The answer continues the first branch: its chain and its branch label
+    /// follow the merge, while the second head is recorded as an extra parent.
+    ///
+    /// ### Arguments:
+    /// `pool` - The database connection pool.
+    /// `project` - The project of both branches.
+    /// `first` - Head of the branch the merge continues.
+    /// `second` - Head of the branch brought in.
+    /// `mode` - Whether full branches or their summaries are sent.
+    ///
+    /// ### Returns:
+    /// - `Result<AppState>`: `AppState::AskModel` on the merge prompt.
+    async fn produce_merge(
+        &mut self,
+        pool: &SqlitePool,
+        project: &Project,
+        first: &Prompt,
+        second: &Prompt,
+        mode: MergeMode,
+    ) -> Result<AppState> {
+        let scrolls = get_scrolls(pool, &project.project_id).await?;
+        let sys_prompt = system_prompt(&scrolls).await;
+        let prompts = get_prompts(pool, &project.project_id).await?;
+        let first_chain = prompt_chain(&prompts, first);
+        let second_chain = prompt_chain(&prompts, second);
+
+        let ai_conf = &self.user_config.as_ref().unwrap().ai_conf;
+        let as_messages = |chain: &[Prompt]| {
+            chain
+                .iter()
+                .map(|p| AiPrompt {
+                    content: p.content.to_owned(),
+                    output: p.output.to_owned(),
+                })
+                .collect::<Vec<_>>()
+        };
+        let (messages, request) = match mode {
+            MergeMode::Full => (
+                Some(as_messages(&first_chain)),
+                merge_request(None, &format_branch(&second_chain)),
+            ),
+            MergeMode::Summary => {
+                let mut summaries = vec![];
+                for chain in [&first_chain, &second_chain] {
+                    let question = Question {
+                        system_prompt: None,
+                        messages: Some(as_messages(chain)),
+                        new_prompt: SUMMARY_REQUEST.to_string(),
+                    };
+                    summaries.push(ask_question(ai_conf, question).await?);
+                }
+                (None, merge_request(Some(&summaries[0]), &summaries[1]))
+            }
+        };
+
+        let question = Question {
+            system_prompt: if sys_prompt.is_empty() {
+                None
+            } else {
+                Some(sys_prompt)
+            },
+            messages,
+            new_prompt: request.to_owned(),
+        };
+        let output = ask_question(ai_conf, question).await?;
+
+        let merge = Prompt::new(&project.project_id, &request, &output, &first.prompt_id);
+        let stored = store_merge(pool, &merge, &second.prompt_id).await?;
+        let merge = Prompt {
+            prompt_id: stored.id().to_string(),
+            ..merge
+        };
+
+        // The branch of the first head moves on to the merge
+        let marks = get_marks(pool, &project.project_id).await?;
+        self.current_branch = marks.branch_of(&first.prompt_id);
+        if let Some(branch) = &self.current_branch {
+            advance_branch(
+                pool,
+                &project.project_id,
+                branch,
+                &first.prompt_id,
+                &merge.prompt_id,
+            )
+            .await?;
+        }
+        self.current_prompt = Some(merge.clone());
+
+        // Clear cache
+        self.clear_prompt_cache();
+
+        let mut new_prompts = prompts;
+        if !stored.is_reused() {
+            new_prompts.push(merge);
+        }
+        chain_into_canvas(project, Some(&new_prompts), self.current_prompt.as_ref())?;
+
+        Ok(AppState::AskModel)
+    }
+
+    /// Snapshots the database before a destructive operation, keeping as many
+    /// snapshots as the user config allows.
+    ///
+    /// ### Arguments:
+    /// `pool` - The database connection pool.
+    /// `reason` - The operation about to run, kept in the snapshot name.
+    async fn backup(&self, pool: &SqlitePool, reason: &str) -> Result<()> {
+        let retention = self
+            .user_config
+            .as_ref()
+            .map_or(0, |config| config.backup_retention);
+        backup_database(pool, reason, retention).await?;
+        Ok(())
+    }
+
+    /// Exports the current project to Markdown, HTML or JSON.
+    ///
+    /// When `branch_only` is set the current prompt chain is exported directly,
+    /// otherwise the user picks between the current branch, the whole prompt tree
+    /// or the project including its scrolls.
+    ///
+    /// ### Arguments:
+    /// `pool` - The database connection pool.
+    /// `branch_only` - Skip the scope selection and export the current branch.
+    async fn export(&mut self, pool: &SqlitePool, branch_only: bool) -> Result<()> {
+        let Some(project) = &self.current_project else {
+            return Ok(());
+        };
+
+        let prompts = get_prompts(pool, &project.project_id).await?;
+        let scrolls = get_scrolls(pool, &project.project_id).await?;
+
+        let scope = if branch_only {
+            match &self.current_prompt {
+                Some(prompt) => ExportScope::Branch(prompt),
+                None => return Ok(()),
+            }
+        } else {
+            let mut scopes = vec![];
+            if let Some(prompt) = &self.current_prompt {
+                scopes.push(("Current branch", ExportScope::Branch(prompt)));
+            }
+            scopes.push(("Prompt tree", ExportScope::Tree));
+            scopes.push(("Project with scrolls", ExportScope::Project));
+
+            let names = scopes.iter().map(|(n, _)| n.to_string()).collect();
+            let Some(selected) = item_selector(names)? else {
+                return Ok(());
+            };
+            match scopes.iter().find(|(n, _)| *n == selected) {
+                Some((_, scope)) => *scope,
+                None => return Ok(()),
+            }
+        };
+
+        let formats = ExportFormat::ALL
+            .iter()
+            .map(|f| f.name().to_string())
+            .collect();
+        let Some(selected) = item_selector(formats)? else {
+            return Ok(());
+        };
+        let Some(format) = ExportFormat::ALL.iter().find(|f| f.name() == selected) else {
+            return Ok(());
+        };
+
+        let path = write_export(project, &prompts, &scrolls, scope, *format)?;
+        self.status.info(&format!("Exported to {}", path.display()));
+        Ok(())
+    }
+}
+
+/// Lets the user pick one of `prompts`, each shown with its marks, starting on
+/// the prompt with id `selected`.
+///
+/// ### Returns:
+/// - `Ok(Some(Prompt))` with the chosen prompt, `Ok(None)` if the selection was cancelled.
+fn pick_prompt(
+    prompts: &[Prompt],
+    marks: &ProjectMarks,
+    selected: Option<&str>,
+) -> Result<Option<Prompt>> {
+    let items = prompts
+        .iter()
+        .map(|p| SelectItem::new(&p.prompt_id, &picker_line(p, marks)))
+        .collect();
+
+    Ok(select_item("Select a prompt", items, selected)?
+        .and_then(|id| prompts.iter().find(|p| p.prompt_id == id).cloned()))
+}
+
+/// Lets the user pick one of `projects`, starting on the project with id `selected`.
+///
+/// ### Returns:
+/// - `Ok(Some(Project))` with the chosen project, `Ok(None)` if the selection was cancelled.
+fn pick_project(projects: &[Project], selected: Option<&str>) -> Result<Option<Project>> {
+    let (lines, _) = build_select_project(projects);
+    let items = projects
+        .iter()
+        .zip(lines)
+        .map(|(p, line)| SelectItem::styled(&p.project_id, vec![line]))
+        .collect();
+
+    Ok(select_item("Select a project", items, selected)?
+        .and_then(|id| projects.iter().find(|p| p.project_id == id).cloned()))
+}
+
+/// Lets the user pick a new parent among `prompts` or the project root.
+///
+/// ### Arguments:
+/// `project_id` - The project of `prompts`, returned when the root is picked.
+/// `prompts` - The candidate parents.
+/// `marks` - Marks shown next to each prompt.
+///
+/// ### Returns:
+/// - `Result<Option<String>>` with the id of the picked parent, `None` if cancelled.
+fn pick_parent(
+    project_id: &str,
+    prompts: &[Prompt],
+    marks: &ProjectMarks,
+) -> Result<Option<String>> {
+    let mut items = vec![SelectItem::new(project_id, " -[ Project root ]-")];
+    items.extend(
+        prompts
+            .iter()
+            .map(|p| SelectItem::new(&p.prompt_id, &picker_line(p, marks))),
+    );
+
+    select_item("Select the new parent", items, None)
+}
+
+/// Formats a prompt and its marks as a picker entry.
+fn picker_line(prompt: &Prompt, marks: &ProjectMarks) -> String {
+    let (p_str, o_str) = format_prompt(prompt);
+    format!("{}{}\n{}", p_str, marks.label(&prompt.prompt_id), o_str)
+}
+
+/// A rectangle of `percent_x` by `percent_y` of `area`, centered in it.
+fn centered_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let [area] = Layout::vertical([Constraint::Percentage(percent_y)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::horizontal([Constraint::Percentage(percent_x)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
 }
@@ -0,0 +1,501 @@
+use std::{
+    cmp::Reverse,
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Receiver,
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use crossterm::{
+    event::{
+        self, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
+    terminal::is_raw_mode_enabled,
+};
+use nucleo_picker::nucleo::{
+    pattern::{CaseMatching, Normalization, Pattern},
+    Config, Matcher, Utf32Str, Utf32String,
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Position, Rect},
+    style::{Modifier, Style},
+    text::{Line, Text},
+    widgets::{Block, BorderType, Borders, List, ListItem, ListState},
+    Frame, Terminal,
+};
+
+use crate::services::terminal::{enter_terminal, restore_terminal};
+
+/// Rows moved by `PageUp` and `PageDown`.
+pub(crate) const PAGE_SIZE: usize = 10;
+
+/// Set when a modal picker or an editor drew over the screen of the application.
+static SCREEN_DIRTY: AtomicBool = AtomicBool::new(false);
+
+/// Set when `Ctrl-c` closed a modal picker.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether a modal picker drew over the screen since the last call, in which
+/// case the application must redraw it from scratch.
+pub fn take_screen_dirty() -> bool {
+    SCREEN_DIRTY.swap(false, Ordering::Relaxed)
+}
+
+/// Asks the application to redraw its screen from scratch, as after a program
+/// that took over the terminal.
+pub fn mark_screen_dirty() {
+    SCREEN_DIRTY.store(true, Ordering::Relaxed);
+}
+
+/// Whether `Ctrl-c` closed a modal picker since the last call, in which case
+/// the application quits as it does from any of its views.
+pub fn take_interrupted() -> bool {
+    INTERRUPTED.swap(false, Ordering::Relaxed)
+}
+
+/// Whether the key is `Ctrl-c`, which quits from every view.
+pub(crate) fn is_interrupt(key_event: &KeyEvent) -> bool {
+    key_event.code == KeyCode::Char('c') && key_event.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+/// An entry of a [`SelectList`], tracked by its id rather than by its text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectItem {
+    pub id: String,
+    pub lines: Vec<Line<'static>>,
+}
+
+impl SelectItem {
+    /// An entry showing `text`, one line per `\n`.
+    pub fn new(id: &str, text: &str) -> SelectItem {
+        SelectItem {
+            id: id.to_string(),
+            lines: text.lines().map(|l| Line::from(l.to_string())).collect(),
+        }
+    }
+
+    /// An entry showing already styled lines.
+    pub fn styled(id: &str, lines: Vec<Line<'static>>) -> SelectItem {
+        SelectItem {
+            id: id.to_string(),
+            lines,
+        }
+    }
+
+    /// The text the filter matches against, all lines joined.
+    fn haystack(&self) -> Utf32String {
+        self.lines
+            .iter()
+            .map(|l| l.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+            .into()
+    }
+}
+
+/// Outcome of a key handled by a [`SelectList`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ListAction {
+    /// The key is not used by the list.
+    Ignored,
+    /// The selection or the filter changed.
+    Handled,
+    /// `Enter` was pressed on the item with this id.
+    Select(String),
+    /// `Esc` was pressed in a modal picker.
+    Cancel,
+}
+
+/// A scrollable list with a fuzzy filter.
+///
+/// In a pane, `j`/`k` and the arrows move the selection and `/` starts
+/// filtering. In a modal picker, typing always edits the filter.
+#[derive(Debug, Default)]
+pub struct SelectList {
+    items: Vec<SelectItem>,
+    /// The text of each item the filter matches against, converted once.
+    haystacks: Vec<Utf32String>,
+    /// Indexes into `items` of the entries matching the filter, best match first.
+    visible: Vec<usize>,
+    /// Score of each entry of `visible` against the filter.
+    scores: Vec<u32>,
+    filter: String,
+    filtering: bool,
+    modal: bool,
+    state: ListState,
+    /// Inside of the block the list was last drawn in, for the mouse.
+    area: Rect,
+}
+
+impl SelectList {
+    /// A list navigated from a pane of the application.
+    pub fn new(items: Vec<SelectItem>) -> SelectList {
+        let mut list = SelectList::default();
+        list.set_items(items);
+        list
+    }
+
+    /// A list shown by [`run_picker`], filtering as the user types.
+    pub fn picker(items: Vec<SelectItem>) -> SelectList {
+        let mut list = SelectList::new(items);
+        list.modal = true;
+        list.filtering = true;
+        list
+    }
+
+    /// Replaces the items, keeping the selection on the same id when it is
+    /// still listed.
+    pub fn set_items(&mut self, items: Vec<SelectItem>) {
+        let selected = self.selected_id().map(String::from);
+        self.haystacks = items.iter().map(SelectItem::haystack).collect();
+        self.items = items;
+        self.refilter(0);
+        match selected {
+            Some(id) if self.select_id(&id) => {}
+            _ => self.select_index(0),
+        }
+    }
+
+    /// Appends items, as a picker fed from another thread does. Only the new
+    /// items are matched against the filter.
+    pub fn extend(&mut self, items: Vec<SelectItem>) {
+        if items.is_empty() {
+            return;
+        }
+        let selected = self.selected_id().map(String::from);
+        let from = self.items.len();
+        self.haystacks
+            .extend(items.iter().map(SelectItem::haystack));
+        self.items.extend(items);
+        self.refilter(from);
+        match selected {
+            Some(id) if self.select_id(&id) => {}
+            _ => self.select_index(0),
+        }
+    }
+
+    /// The id of the highlighted item.
+    pub fn selected_id(&self) -> Option<&str> {
+        self.state
+            .selected()
+            .and_then(|index| self.visible.get(index))
+            .map(|&item| self.items[item].id.as_str())
+    }
+
+    /// Highlights the item with `id`.
+    ///
+    /// # Returns:
+    /// - `false` when no visible item has this id.
+    pub fn select_id(&mut self, id: &str) -> bool {
+        match self.visible.iter().position(|&i| self.items[i].id == id) {
+            Some(index) => {
+                self.state.select(Some(index));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The ids of the items matching the filter, in display order.
+    pub fn visible_ids(&self) -> Vec<&str> {
+        self.visible
+            .iter()
+            .map(|&i| self.items[i].id.as_str())
+            .collect()
+    }
+
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    pub fn is_filtering(&self) -> bool {
+        self.filtering
+    }
+
+    /// Replaces the filter and highlights the best match.
+    pub fn set_filter(&mut self, filter: &str) {
+        self.filter = filter.to_string();
+        self.refilter(0);
+        self.select_index(0);
+    }
+
+    /// Handles a key press.
+    pub fn handle_key(&mut self, key: KeyEvent) -> ListAction {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Up => self.move_by(-1),
+            KeyCode::Down => self.move_by(1),
+            KeyCode::Char('p') if control => self.move_by(-1),
+            KeyCode::Char('n') if control => self.move_by(1),
+            KeyCode::Char('k') if !self.filtering => self.move_by(-1),
+            KeyCode::Char('j') if !self.filtering => self.move_by(1),
+            KeyCode::PageUp => self.move_by(-(PAGE_SIZE as isize)),
+            KeyCode::PageDown => self.move_by(PAGE_SIZE as isize),
+            KeyCode::Home => self.select_index(0),
+            KeyCode::End => self.select_index(self.visible.len().saturating_sub(1)),
+            KeyCode::Enter => {
+                return match self.selected_id() {
+                    Some(id) => ListAction::Select(id.to_string()),
+                    None => ListAction::Handled,
+                };
+            }
+            KeyCode::Esc if self.modal => return ListAction::Cancel,
+            KeyCode::Esc if self.filtering || !self.filter.is_empty() => {
+                self.filtering = false;
+                self.set_filter("");
+            }
+            KeyCode::Char('/') if !self.filtering => self.filtering = true,
+            KeyCode::Backspace if self.filtering => {
+                if self.filter.is_empty() && !self.modal {
+                    self.filtering = false;
+                } else {
+                    let mut filter = self.filter.clone();
+                    filter.pop();
+                    self.set_filter(&filter);
+                }
+            }
+            KeyCode::Char(c) if self.filtering && !control => {
+                let filter = format!("{}{}", self.filter, c);
+                self.set_filter(&filter);
+            }
+            _ => return ListAction::Ignored,
+        }
+        ListAction::Handled
+    }
+
+    /// Handles a mouse event: the wheel moves the selection, a click highlights
+    /// an item and a click on the highlighted item opens it.
+    pub fn handle_mouse(&mut self, mouse: MouseEvent) -> ListAction {
+        if !self.area.contains(Position::new(mouse.column, mouse.row)) {
+            return ListAction::Ignored;
+        }
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.move_by(-1),
+            MouseEventKind::ScrollDown => self.move_by(1),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let heights = self.visible.iter().map(|&i| self.items[i].lines.len());
+                let row = (mouse.row - self.area.y) as usize;
+                match item_at(heights, self.state.offset(), row) {
+                    Some(index) if self.state.selected() == Some(index) => {
+                        return match self.selected_id() {
+                            Some(id) => ListAction::Select(id.to_string()),
+                            None => ListAction::Handled,
+                        };
+                    }
+                    Some(index) => self.select_index(index),
+                    None => {}
+                }
+            }
+            _ => return ListAction::Ignored,
+        }
+        ListAction::Handled
+    }
+
+    /// Renders the list inside `block`, showing the filter under it.
+    pub fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        block: Block,
+        style: Style,
+        highlight: Style,
+    ) {
+        let block = if self.filtering || !self.filter.is_empty() {
+            block.title_bottom(format!("[ /{} ]", self.filter))
+        } else {
+            block
+        };
+        self.area = block.inner(area);
+        let items: Vec<ListItem> = self
+            .visible
+            .iter()
+            .map(|&i| ListItem::new(Text::from(self.items[i].lines.clone())))
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .style(style)
+            .highlight_style(highlight);
+        frame.render_stateful_widget(list, area, &mut self.state);
+    }
+
+    /// Matches the items from index `from` on against the filter and ranks them
+    /// with the visible items before them, or recomputes every visible item
+    /// when `from` is 0.
+    fn refilter(&mut self, from: usize) {
+        if from == 0 {
+            self.visible.clear();
+            self.scores.clear();
+        }
+        if self.filter.is_empty() {
+            self.visible.extend(from..self.items.len());
+            self.scores.resize(self.visible.len(), 0);
+            return;
+        }
+
+        let pattern = Pattern::parse(&self.filter, CaseMatching::Smart, Normalization::Smart);
+        let mut matcher = Matcher::new(Config::DEFAULT);
+        let mut ranked: Vec<(u32, usize)> = self
+            .scores
+            .iter()
+            .copied()
+            .zip(self.visible.iter().copied())
+            .collect();
+        ranked.extend((from..self.items.len()).filter_map(|i| {
+            pattern
+                .score(self.haystacks[i].slice(..), &mut matcher)
+                .map(|score| (score, i))
+        }));
+        ranked.sort_by_key(|&(score, i)| (Reverse(score), i));
+        (self.scores, self.visible) = ranked.into_iter().unzip();
+    }
+
+    fn select_index(&mut self, index: usize) {
+        if self.visible.is_empty() {
+            self.state.select(None);
+        } else {
+            self.state.select(Some(index.min(self.visible.len() - 1)));
+        }
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let current = self.state.selected().unwrap_or(0) as isize;
+        self.select_index((current + delta).max(0) as usize);
+    }
+}
+
+/// The item drawn on `row` of a list scrolled to `offset`.
+///
+/// # Parameters:
+/// - `heights`: The number of lines of each item of the list.
+/// - `offset`: The first item drawn.
+/// - `row`: The row clicked, from the top of the list.
+///
+/// # Returns:
+/// - The index of the item, `None` below the last one.
+pub(crate) fn item_at(
+    heights: impl Iterator<Item = usize>,
+    offset: usize,
+    row: usize,
+) -> Option<usize> {
+    let mut top = 0;
+    for (index, height) in heights.enumerate().skip(offset) {
+        top += height.max(1);
+        if row < top {
+            return Some(index);
+        }
+    }
+    None
+}
+
+/// Matches `haystacks` against the fuzzy `filter`.
+///
+/// # Returns:
+/// - The indexes of the matching haystacks, best match first; ties keep their order.
+pub(crate) fn fuzzy_rank(filter: &str, haystacks: &[String]) -> Vec<usize> {
+    let pattern = Pattern::parse(filter, CaseMatching::Smart, Normalization::Smart);
+    let mut matcher = Matcher::new(Config::DEFAULT);
+    let mut buf = vec![];
+    let mut scored: Vec<(u32, usize)> = haystacks
+        .iter()
+        .enumerate()
+        .filter_map(|(i, haystack)| {
+            pattern
+                .score(Utf32Str::new(haystack, &mut buf), &mut matcher)
+                .map(|score| (score, i))
+        })
+        .collect();
+    scored.sort_by_key(|&(score, i)| (Reverse(score), i));
+    scored.into_iter().map(|(_, i)| i).collect()
+}
+
+/// Shows `list` full screen until the user picks an item or presses `Esc`.
+///
+/// Items received from `feed` are appended while the picker is open, so slow
+/// sources such as directory walks do not block it. `Ctrl-c` closes the picker
+/// and asks the application to quit, see [`take_interrupted`].
+///
+/// # Parameters:
+/// - `title`: The title of the picker.
+/// - `list`: The items to pick from, see [`SelectList::picker`].
+/// - `feed`: More items, sent from another thread.
+///
+/// # Returns:
+/// - `Ok(Some(String))` with the id of the picked item, `Ok(None)` if cancelled.
+pub fn run_picker(
+    title: &str,
+    list: &mut SelectList,
+    feed: Option<Receiver<SelectItem>>,
+) -> Result<Option<String>> {
+    // Outside of the application the picker takes the terminal for itself
+    let raw_mode = is_raw_mode_enabled().unwrap_or(false);
+    if !raw_mode {
+        enter_terminal()?;
+    }
+    mark_screen_dirty();
+
+    let picked = picker_loop(title, list, feed);
+
+    if !raw_mode {
+        restore_terminal();
+    }
+    picked
+}
+
+fn picker_loop(
+    title: &str,
+    list: &mut SelectList,
+    feed: Option<Receiver<SelectItem>>,
+) -> Result<Option<String>> {
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    terminal.clear()?;
+
+    let mut dirty = true;
+    loop {
+        if let Some(feed) = &feed {
+            let items: Vec<SelectItem> = feed.try_iter().collect();
+            dirty |= !items.is_empty();
+            list.extend(items);
+        }
+        // Redraw only when the list, the filter or the size of the terminal changed
+        if dirty {
+            terminal.draw(|f| {
+                let block = Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Thick)
+                    .title(format!("[ {} ]", title));
+                list.render(
+                    f,
+                    f.area(),
+                    block,
+                    Style::default(),
+                    Style::default().add_modifier(Modifier::REVERSED),
+                );
+            })?;
+            dirty = false;
+        }
+
+        if !event::poll(Duration::from_millis(50))? {
+            continue;
+        }
+        let action = match event::read()? {
+            Event::Key(key) if is_interrupt(&key) => {
+                INTERRUPTED.store(true, Ordering::Relaxed);
+                ListAction::Cancel
+            }
+            Event::Key(key) => list.handle_key(key),
+            Event::Mouse(mouse) => list.handle_mouse(mouse),
+            Event::Resize(..) => ListAction::Handled,
+            _ => ListAction::Ignored,
+        };
+        match action {
+            ListAction::Select(id) => break Ok(Some(id)),
+            ListAction::Cancel => break Ok(None),
+            ListAction::Handled => dirty = true,
+            ListAction::Ignored => {}
+        }
+    }
+}
@@ -1,5 +1,15 @@
+pub mod cli;
 pub mod config;
+pub mod draft;
+pub mod editor;
+pub mod input;
+pub mod keys;
 pub mod legatio;
+pub mod list;
+pub mod preview;
 //pub mod model;
 pub mod search;
+pub mod status;
+pub mod terminal;
+pub mod tree;
 pub mod ui;
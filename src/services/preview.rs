@@ -0,0 +1,584 @@
+use std::sync::OnceLock;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use ratatui::{
+    layout::{Position, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Wrap},
+    Frame,
+};
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Theme, ThemeSet},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+
+use crate::utils::structs::Prompt;
+
+/// Theme used to highlight code blocks.
+const CODE_THEME: &str = "base16-ocean.dark";
+
+/// Syntaxes and theme of the code blocks, loaded on first use.
+fn syntaxes() -> &'static (SyntaxSet, Theme) {
+    static SYNTAXES: OnceLock<(SyntaxSet, Theme)> = OnceLock::new();
+    SYNTAXES.get_or_init(|| {
+        let mut themes = ThemeSet::load_defaults();
+        let theme = themes.themes.remove(CODE_THEME).unwrap_or_default();
+        (SyntaxSet::load_defaults_newlines(), theme)
+    })
+}
+
+/// Colors of the code in the preview, taken from the theme.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CodeColors {
+    /// Inline code.
+    pub code: Color,
+    /// Added and removed lines of `diff` code blocks.
+    pub diff_add: Color,
+    pub diff_remove: Color,
+}
+
+impl Default for CodeColors {
+    fn default() -> Self {
+        CodeColors {
+            code: Color::Yellow,
+            diff_add: Color::Green,
+            diff_remove: Color::Red,
+        }
+    }
+}
+
+/// Renders Markdown into styled lines: headings, emphasis, lists, quotes,
+/// tables and code blocks highlighted by their language.
+///
+/// # Parameters:
+/// - `text`: The Markdown source.
+/// - `colors`: The colors of inline code and of `diff` code blocks.
+///
+/// # Returns:
+/// - The lines to display, without wrapping.
+pub fn markdown_lines(text: &str, colors: &CodeColors) -> Vec<Line<'static>> {
+    let mut renderer = Renderer {
+        colors: *colors,
+        ..Renderer::default()
+    };
+    let options =
+        Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS;
+    for event in Parser::new_ext(text, options) {
+        renderer.event(event);
+    }
+    renderer.finish()
+}
+
+#[derive(Default)]
+struct Renderer {
+    lines: Vec<Line<'static>>,
+    spans: Vec<Span<'static>>,
+    /// Styles of the open inline tags, innermost last.
+    styles: Vec<Style>,
+    /// Next number of each open list, `None` for bullet lists.
+    lists: Vec<Option<u64>>,
+    quotes: usize,
+    /// Bullet of a list item whose first line was not written yet.
+    bullet: Option<String>,
+    /// Language and source of the code block being read.
+    code: Option<(String, String)>,
+    colors: CodeColors,
+}
+
+impl Renderer {
+    fn event(&mut self, event: Event) {
+        if let Some((_, source)) = self.code.as_mut() {
+            match event {
+                Event::Text(text) => source.push_str(&text),
+                Event::End(TagEnd::CodeBlock) => self.code_block(),
+                _ => {}
+            }
+            return;
+        }
+
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.text(&text),
+            Event::Code(code) => {
+                let style = self.style().fg(self.colors.code);
+                self.push(Span::styled(code.to_string(), style));
+            }
+            Event::InlineMath(text) | Event::DisplayMath(text) => self.text(&text),
+            Event::Html(html) | Event::InlineHtml(html) => self.text(html.trim_end()),
+            Event::FootnoteReference(name) => self.text(&format!("[^{}]", name)),
+            Event::SoftBreak => self.text(" "),
+            Event::HardBreak => self.flush(),
+            Event::Rule => {
+                self.blank();
+                self.lines.push(Line::from("─".repeat(40)));
+                self.blank();
+            }
+            Event::TaskListMarker(done) => self.text(if done { "[x] " } else { "[ ] " }),
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.blank();
+                let style = match level {
+                    HeadingLevel::H1 => Modifier::BOLD | Modifier::UNDERLINED,
+                    _ => Modifier::BOLD,
+                };
+                self.styles.push(Style::default().add_modifier(style));
+                self.text(&format!("{} ", "#".repeat(level as usize)));
+            }
+            Tag::BlockQuote(_) => {
+                self.flush();
+                self.quotes += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush();
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some((lang, String::new()));
+            }
+            Tag::List(start) => {
+                self.flush();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                let bullet = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => String::from("•  "),
+                };
+                self.bullet = Some(bullet);
+            }
+            Tag::Emphasis => self
+                .styles
+                .push(Style::default().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self
+                .styles
+                .push(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => self
+                .styles
+                .push(Style::default().add_modifier(Modifier::CROSSED_OUT)),
+            Tag::Link { .. } | Tag::Image { .. } => self
+                .styles
+                .push(Style::default().add_modifier(Modifier::UNDERLINED)),
+            Tag::TableHead => self
+                .styles
+                .push(Style::default().add_modifier(Modifier::BOLD)),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Heading(_) => {
+                self.styles.pop();
+                self.flush();
+                self.blank();
+            }
+            TagEnd::Paragraph => {
+                self.flush();
+                if self.lists.is_empty() {
+                    self.blank();
+                }
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush();
+                self.quotes -= 1;
+                self.blank();
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank();
+                }
+            }
+            TagEnd::Item => self.flush(),
+            TagEnd::Emphasis
+            | TagEnd::Strong
+            | TagEnd::Strikethrough
+            | TagEnd::Link
+            | TagEnd::Image => {
+                self.styles.pop();
+            }
+            TagEnd::TableCell => self.text(" │ "),
+            TagEnd::TableHead => {
+                self.styles.pop();
+                self.flush();
+            }
+            TagEnd::TableRow => self.flush(),
+            TagEnd::Table => self.blank(),
+            _ => {}
+        }
+    }
+
+    fn style(&self) -> Style {
+        self.styles
+            .iter()
+            .fold(Style::default(), |style, s| style.patch(*s))
+    }
+
+    fn text(&mut self, text: &str) {
+        let style = self.style();
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.flush();
+            }
+            if !line.is_empty() {
+                self.push(Span::styled(line.to_string(), style));
+            }
+        }
+    }
+
+    /// Adds a span, starting the line with the quote bars and list indent.
+    fn push(&mut self, span: Span<'static>) {
+        if self.spans.is_empty() {
+            self.spans = self.prefix();
+        }
+        self.spans.push(span);
+    }
+
+    fn prefix(&mut self) -> Vec<Span<'static>> {
+        let mut prefix = "│ ".repeat(self.quotes);
+        if !self.lists.is_empty() {
+            prefix.push_str(&"   ".repeat(self.lists.len() - 1));
+            prefix.push_str(&self.bullet.take().unwrap_or_else(|| String::from("   ")));
+        }
+        if prefix.is_empty() {
+            vec![]
+        } else {
+            vec![Span::raw(prefix)]
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.spans.is_empty() {
+            self.lines.push(Line::from(std::mem::take(&mut self.spans)));
+        }
+    }
+
+    /// Separates blocks with a single empty line.
+    fn blank(&mut self) {
+        self.flush();
+        if self.lines.last().is_some_and(|l| l.width() > 0) {
+            self.lines.push(Line::default());
+        }
+    }
+
+    fn code_block(&mut self) {
+        let Some((lang, source)) = self.code.take() else {
+            return;
+        };
+        let (syntaxes, theme) = syntaxes();
+        let syntax = syntaxes
+            .find_syntax_by_token(&lang)
+            .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+        let mut highlighter = HighlightLines::new(syntax, theme);
+        let diff = matches!(lang.as_str(), "diff" | "patch");
+
+        for line in LinesWithEndings::from(&source) {
+            let mut spans = self.prefix();
+            spans.push(Span::raw("  "));
+            // Changes take the colors of the theme
+            let change = match line.chars().next() {
+                Some('+') if diff => Some(self.colors.diff_add),
+                Some('-') if diff => Some(self.colors.diff_remove),
+                _ => None,
+            };
+            if let Some(color) = change {
+                spans.push(Span::styled(
+                    line.trim_end_matches('\n').to_string(),
+                    Style::default().fg(color),
+                ));
+                self.lines.push(Line::from(spans));
+                continue;
+            }
+            match highlighter.highlight_line(line, syntaxes) {
+                Ok(ranges) => spans.extend(ranges.into_iter().map(|(style, text)| {
+                    let fg = style.foreground;
+                    Span::styled(
+                        text.trim_end_matches('\n').to_string(),
+                        Style::default().fg(Color::Rgb(fg.r, fg.g, fg.b)),
+                    )
+                })),
+                Err(_) => spans.push(Span::raw(line.trim_end_matches('\n').to_string())),
+            }
+            self.lines.push(Line::from(spans));
+        }
+        self.blank();
+    }
+
+    fn finish(mut self) -> Vec<Line<'static>> {
+        self.flush();
+        while self.lines.last().is_some_and(|l| l.width() == 0) {
+            self.lines.pop();
+        }
+        self.lines
+    }
+}
+
+/// Rows scrolled by a notch of the mouse wheel.
+const WHEEL_ROWS: usize = 3;
+
+/// The prompt and output of a single prompt rendered as Markdown, with
+/// paging and search.
+///
+/// `Ctrl-d`/`Ctrl-u` page down and up, `Ctrl-f` starts a search and `n`/`N`
+/// jump to the next and previous match.
+#[derive(Debug, Default)]
+pub struct Preview {
+    prompt_id: Option<String>,
+    lines: Vec<Line<'static>>,
+    /// First wrapped row of each line, for the width in `width`.
+    rows: Vec<usize>,
+    total_rows: usize,
+    width: u16,
+    height: u16,
+    scroll: usize,
+    search: String,
+    searching: bool,
+    /// Indexes of the lines matching the search.
+    matches: Vec<usize>,
+    current: usize,
+    /// Inside of the block the preview was last drawn in, for the mouse.
+    area: Rect,
+    colors: CodeColors,
+}
+
+impl Preview {
+    /// Sets the colors of the code, rendering the prompt again when they changed.
+    pub fn set_colors(&mut self, colors: CodeColors) {
+        if self.colors != colors {
+            self.colors = colors;
+            // Forget the prompt so the next `set_prompt` renders it again
+            self.prompt_id = None;
+        }
+    }
+
+    /// Shows `prompt`, rendering it again only when it changed.
+    pub fn set_prompt(&mut self, prompt: Option<&Prompt>) {
+        if self.prompt_id.as_deref() == prompt.map(|p| p.prompt_id.as_str()) {
+            return;
+        }
+        self.prompt_id = prompt.map(|p| p.prompt_id.clone());
+        self.lines = match prompt {
+            Some(prompt) => {
+                let heading = Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED);
+                let mut lines = vec![Line::styled(" Prompt ", heading), Line::default()];
+                lines.extend(markdown_lines(&prompt.content, &self.colors));
+                lines.extend([Line::default(), Line::styled(" Output ", heading)]);
+                lines.push(Line::default());
+                lines.extend(markdown_lines(&prompt.output, &self.colors));
+                lines
+            }
+            None => vec![],
+        };
+        self.width = 0;
+        self.layout(self.width);
+        self.scroll = 0;
+        self.find();
+    }
+
+    pub fn lines(&self) -> &[Line<'static>] {
+        &self.lines
+    }
+
+    /// The first row shown.
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    /// The indexes of the lines matching the search.
+    pub fn matches(&self) -> &[usize] {
+        &self.matches
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.searching
+    }
+
+    /// Handles a key press.
+    ///
+    /// # Returns:
+    /// - `true` if the preview used the key.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        if self.searching {
+            match key.code {
+                KeyCode::Enter => self.searching = false,
+                KeyCode::Esc => {
+                    self.searching = false;
+                    self.search.clear();
+                    self.find();
+                }
+                KeyCode::Backspace => {
+                    self.search.pop();
+                    self.find();
+                }
+                KeyCode::Char(c) if !control => {
+                    self.search.push(c);
+                    self.find();
+                }
+                _ => {}
+            }
+            return true;
+        }
+
+        match key.code {
+            KeyCode::Char('d') if control => self.scroll_by(self.page() as isize),
+            KeyCode::Char('u') if control => self.scroll_by(-(self.page() as isize)),
+            KeyCode::Char('f') if control => {
+                self.searching = true;
+                self.search.clear();
+                self.find();
+            }
+            KeyCode::Char('n') if !control && !self.matches.is_empty() => self.jump(1),
+            KeyCode::Char('N') if !self.matches.is_empty() => self.jump(-1),
+            KeyCode::Esc if !self.search.is_empty() => {
+                self.search.clear();
+                self.find();
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Scrolls with the mouse wheel over the preview.
+    ///
+    /// # Returns:
+    /// - `true` if the event was used.
+    pub fn handle_mouse(&mut self, mouse: MouseEvent) -> bool {
+        if !self.area.contains(Position::new(mouse.column, mouse.row)) {
+            return false;
+        }
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.scroll_by(-(WHEEL_ROWS as isize)),
+            MouseEventKind::ScrollDown => self.scroll_by(WHEEL_ROWS as isize),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Renders the preview inside `block`, marking the lines matching the
+    /// search and the current match with `highlight`.
+    pub fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        block: Block,
+        style: Style,
+        highlight: Style,
+    ) {
+        let inner = block.inner(area);
+        self.area = inner;
+        self.height = inner.height;
+        self.layout(inner.width);
+        self.scroll = self.scroll.min(self.max_scroll());
+
+        let block = if self.searching || !self.search.is_empty() {
+            let count = match self.matches.len() {
+                0 => String::from("no match"),
+                n => format!("{}/{}", self.current + 1, n),
+            };
+            block.title_bottom(format!("[ find: {} -:- {} ]", self.search, count))
+        } else {
+            block
+        };
+
+        let lines: Vec<Line> = self
+            .lines
+            .iter()
+            .enumerate()
+            .map(
+                |(i, line)| match self.matches.iter().position(|&m| m == i) {
+                    Some(n) if n == self.current => line.clone().patch_style(highlight),
+                    Some(_) => line
+                        .clone()
+                        .patch_style(Style::default().add_modifier(Modifier::UNDERLINED)),
+                    None => line.clone(),
+                },
+            )
+            .collect();
+        let paragraph = Paragraph::new(lines)
+            .block(block)
+            .style(style)
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll.min(u16::MAX as usize) as u16, 0));
+        frame.render_widget(paragraph, area);
+    }
+
+    /// Computes the wrapped rows of the lines for `width`, one row per line
+    /// before the preview was first drawn.
+    fn layout(&mut self, width: u16) {
+        if width == self.width && self.rows.len() == self.lines.len() {
+            return;
+        }
+        self.width = width;
+        self.rows.clear();
+        self.total_rows = 0;
+        for line in self.lines.iter() {
+            self.rows.push(self.total_rows);
+            self.total_rows += match width {
+                0 => 1,
+                _ => Paragraph::new(line.clone())
+                    .wrap(Wrap { trim: false })
+                    .line_count(width)
+                    .max(1),
+            };
+        }
+    }
+
+    fn page(&self) -> usize {
+        (self.height as usize).saturating_sub(1).max(1)
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.total_rows.saturating_sub(self.height.max(1) as usize)
+    }
+
+    fn scroll_by(&mut self, delta: isize) {
+        let scroll = (self.scroll as isize + delta).max(0) as usize;
+        self.scroll = scroll.min(self.max_scroll());
+    }
+
+    /// Finds the lines matching the search, ignoring case, and shows the first
+    /// match.
+    fn find(&mut self) {
+        self.current = 0;
+        self.matches = if self.search.is_empty() {
+            vec![]
+        } else {
+            let search = self.search.to_lowercase();
+            self.lines
+                .iter()
+                .enumerate()
+                .filter(|(_, line)| line.to_string().to_lowercase().contains(&search))
+                .map(|(i, _)| i)
+                .collect()
+        };
+        self.jump(0);
+    }
+
+    /// Moves to another match and scrolls it into view.
+    fn jump(&mut self, delta: isize) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let len = self.matches.len() as isize;
+        self.current = (self.current as isize + delta).rem_euclid(len) as usize;
+        self.scroll = self.rows[self.matches[self.current]].min(self.max_scroll());
+    }
+}
@@ -1,45 +1,33 @@
-use std::{borrow::Cow, path::PathBuf, thread::spawn};
+use std::{
+    path::PathBuf,
+    sync::mpsc::{channel, Sender},
+    thread::spawn,
+};
 
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
 use ignore::{DirEntry, WalkBuilder, WalkState};
-use nucleo_picker::{nucleo::Config, render::StrRenderer, PickerOptions, Render};
 
 use anyhow::Result;
 
-use crate::utils::{error::AppError, logger::log_error};
+use crate::{
+    services::list::{run_picker, SelectItem, SelectList},
+    utils::logger::log_error,
+};
 
-pub struct DirEntryRender;
-
-impl Render<DirEntry> for DirEntryRender {
-    type Str<'a> = Cow<'a, str>;
-
-    /// Render a `DirEntry` using its internal path buffer.
-    fn render<'a>(&self, value: &'a DirEntry) -> Self::Str<'a> {
-        value.path().to_string_lossy()
-    }
-}
-
-pub fn select_files(dir_path: Option<&str>) -> Result<Option<String>> {
-    disable_raw_mode()
-        .map_err(|_| AppError::UnexpectedError("Failed to disable raw mode".into()))?;
-    let mut picker = PickerOptions::default()
-        // See the nucleo configuration for more options:
-        //   https://docs.rs/nucleo/latest/nucleo/struct.Config.html
-        .config(Config::DEFAULT.match_paths())
-        // Use our custom renderer for a `DirEntry`
-        .picker(DirEntryRender);
-
-    // "argument parsing"
-    let root: PathBuf = dir_path.map(Into::into).unwrap_or_else(|| "/home/".into());
-
-    // populate from a separate thread to avoid locking the picker interface
-    let injector = picker.injector();
+/// Walks `root` from a separate thread, sending the entries kept by `keep` to
+/// `sender` until the picker reading them is closed.
+fn walk_into(root: PathBuf, sender: Sender<SelectItem>, keep: fn(&DirEntry) -> bool) {
     spawn(move || {
         WalkBuilder::new(root).build_parallel().run(|| {
-            let injector = injector.clone();
+            let sender = sender.clone();
             Box::new(move |walk_res| {
                 match walk_res {
-                    Ok(dir) => injector.push(dir),
+                    Ok(entry) if keep(&entry) => {
+                        let path = entry.path().display().to_string();
+                        if sender.send(SelectItem::new(&path, &path)).is_err() {
+                            return WalkState::Quit;
+                        }
+                    }
+                    Ok(_) => {}
                     Err(err) => {
                         // Log the error or handle it appropriately
                         log_error(&format!("Error during directory walk: {}", err));
@@ -49,79 +37,58 @@ pub fn select_files(dir_path: Option<&str>) -> Result<Option<String>> {
             })
         });
     });
+}
 
-    let file: Option<String> = picker
-        .pick()
-        .map_err(|_| AppError::UnexpectedError("Picker failed to pick a file".into()))?
-        .map(|entry| entry.path().display().to_string());
+pub fn select_files(dir_path: Option<&str>) -> Result<Option<String>> {
+    let root: PathBuf = dir_path.map(Into::into).unwrap_or_else(|| "/home/".into());
 
-    enable_raw_mode().map_err(|_| AppError::UnexpectedError("Failed to enable raw mode".into()))?;
-    Ok(file)
+    // populate from a separate thread to avoid locking the picker interface
+    let (sender, receiver) = channel();
+    walk_into(root, sender, |_| true);
+
+    run_picker(
+        "Select a file",
+        &mut SelectList::picker(vec![]),
+        Some(receiver),
+    )
 }
 
 pub fn select_directories(dir_path: Option<&str>) -> Result<Option<String>> {
-    disable_raw_mode()
-        .map_err(|_| AppError::UnexpectedError("Failed to disable raw mode".into()))?;
-    
-    let mut picker = PickerOptions::default()
-        .config(Config::DEFAULT.match_paths())
-        .picker(DirEntryRender);
-
     let root: PathBuf = dir_path.map(Into::into).unwrap_or_else(|| "/home/".into());
 
-    let injector = picker.injector();
-    spawn(move || {
-        WalkBuilder::new(root).build_parallel().run(|| {
-            let injector = injector.clone();
-            Box::new(move |walk_res| {
-                if let Ok(entry) = walk_res {
-                    if let Ok(metadata) = entry.metadata() {
-                        if metadata.is_dir() {
-                            injector.push(entry);
-                        }
-                    }
-                }
-                WalkState::Continue
-            })
-        });
+    let (sender, receiver) = channel();
+    walk_into(root, sender, |entry| {
+        entry.metadata().is_ok_and(|metadata| metadata.is_dir())
     });
 
-    let selected: Option<String> = picker
-        .pick()
-        .map_err(|_| AppError::UnexpectedError("Picker failed to pick a directory".into()))?
-        .map(|entry| entry.path().display().to_string());
-
-    enable_raw_mode().map_err(|_| AppError::UnexpectedError("Failed to enable raw mode".into()))?;
-    Ok(selected)
+    run_picker(
+        "Select a directory",
+        &mut SelectList::picker(vec![]),
+        Some(receiver),
+    )
 }
 
-pub fn item_selector(items: Vec<String>) -> Result<Option<String>> {
-    disable_raw_mode().map_err(|e| {
-        log_error(&format!("Failed to disable raw mode. Reason: {}", e));
-        AppError::UnexpectedError(format!("Failed to disable raw mode. Reason: {}", e))
-    })?;
-
-    let mut picker = PickerOptions::default()
-        .config(Config::DEFAULT.match_paths())
-        .picker(StrRenderer);
-
-    let injector = picker.injector();
-    for item in items {
-        injector.push(item);
+/// Lets the user pick one of `items`, starting on the item with id `selected`.
+///
+/// # Returns:
+/// - `Ok(Some(String))` with the id of the picked item, `Ok(None)` if cancelled.
+pub fn select_item(
+    title: &str,
+    items: Vec<SelectItem>,
+    selected: Option<&str>,
+) -> Result<Option<String>> {
+    let mut list = SelectList::picker(items);
+    if let Some(id) = selected {
+        list.select_id(id);
     }
+    run_picker(title, &mut list, None)
+}
 
-    let sel_item: Option<String> = picker
-        .pick()
-        .map_err(|e| {
-            log_error(&format!("Picker failed to pick an item. Reason: {}", e));
-            AppError::UnexpectedError(format!("Picker failed to pick an item. Reason: {}", e))
-        })?
-        .map(|opt| opt.to_string());
-
-    enable_raw_mode().map_err(|e| {
-        log_error(&format!("Failed to enable raw mode. Reason: {}", e));
-        AppError::UnexpectedError(format!("Failed to enable raw mode. Reason: {}", e))
-    })?;
-
-    Ok(sel_item)
+/// Lets the user pick one of `items`, returning the picked string.
+pub fn item_selector(items: Vec<String>) -> Result<Option<String>> {
+    let items = items
+        .iter()
+        .map(|item| SelectItem::new(item, item))
+        .collect();
+    select_item("Select", items, None)
 }
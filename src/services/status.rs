@@ -0,0 +1,168 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+/// Number of notifications kept in the log.
+const LOG_SIZE: usize = 100;
+
+/// How long the newest notification stays in the status bar.
+pub const TOAST_DURATION: Duration = Duration::from_secs(5);
+
+/// Severity of a notification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Level {
+    fn label(self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        }
+    }
+
+    /// Style of the label, `error` being the error color of the theme.
+    fn style(self, error: Color) -> Style {
+        match self {
+            Level::Info => Style::default(),
+            Level::Warning => Style::default().fg(Color::Yellow),
+            Level::Error => Style::default().fg(error).add_modifier(Modifier::BOLD),
+        }
+    }
+}
+
+/// A message shown to the user without leaving the current view.
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub level: Level,
+    pub message: String,
+    /// Times the message was repeated in a row.
+    pub count: usize,
+    pub time: DateTime<Local>,
+}
+
+impl Notification {
+    /// The notification as a log line, such as `12:03:44 error  Failed to ...`,
+    /// errors being labelled in `error`.
+    pub fn log_line(&self, error: Color) -> Line<'static> {
+        let repeated = if self.count > 1 {
+            format!(" (x{})", self.count)
+        } else {
+            String::new()
+        };
+        Line::from(vec![
+            Span::raw(format!("{} ", self.time.format("%H:%M:%S"))),
+            Span::styled(
+                format!("{:<8}", self.level.label()),
+                self.level.style(error),
+            ),
+            Span::raw(format!("{}{}", self.message, repeated)),
+        ])
+    }
+}
+
+/// Notifications of the session: the newest one is shown in the status bar
+/// for a few seconds, and all of them are kept in a log.
+#[derive(Debug, Default)]
+pub struct StatusBar {
+    /// Newest notification first.
+    log: VecDeque<Notification>,
+    /// When the newest notification leaves the status bar.
+    toast_until: Option<Instant>,
+}
+
+impl StatusBar {
+    pub fn info(&mut self, message: &str) {
+        self.push(Level::Info, message);
+    }
+
+    pub fn warn(&mut self, message: &str) {
+        self.push(Level::Warning, message);
+    }
+
+    /// Notifies a recoverable error, such as an `AppError` returned by a handler.
+    pub fn error(&mut self, err: &anyhow::Error) {
+        self.push(Level::Error, &err.to_string());
+    }
+
+    /// Adds a notification and shows it in the status bar. A message repeating
+    /// the newest one only bumps its count.
+    pub fn push(&mut self, level: Level, message: &str) {
+        match self.log.front_mut() {
+            Some(last) if last.level == level && last.message == message => {
+                last.count += 1;
+                last.time = Local::now();
+            }
+            _ => {
+                self.log.push_front(Notification {
+                    level,
+                    message: message.to_string(),
+                    count: 1,
+                    time: Local::now(),
+                });
+                self.log.truncate(LOG_SIZE);
+            }
+        }
+        self.toast_until = Some(Instant::now() + TOAST_DURATION);
+    }
+
+    /// The notifications of the session, newest first.
+    pub fn log(&self) -> &VecDeque<Notification> {
+        &self.log
+    }
+
+    /// The notification shown in the status bar at `now`, if any.
+    pub fn toast(&self, now: Instant) -> Option<&Notification> {
+        match self.toast_until {
+            Some(until) if now < until => self.log.front(),
+            _ => None,
+        }
+    }
+
+    /// Hides the toast once its time is up.
+    ///
+    /// # Returns:
+    /// - `true` when the toast was just hidden and the screen needs a redraw.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        match self.toast_until {
+            Some(until) if now >= until => {
+                self.toast_until = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The status bar: the current toast, or `hint` when there is none. Errors
+    /// are labelled in `error`.
+    pub fn line(&self, hint: &str, error: Color) -> Line<'static> {
+        match self.toast(Instant::now()) {
+            Some(toast) => {
+                let repeated = if toast.count > 1 {
+                    format!(" (x{})", toast.count)
+                } else {
+                    String::new()
+                };
+                Line::from(vec![
+                    Span::styled(
+                        format!(" {}: ", toast.level.label()),
+                        toast.level.style(error),
+                    ),
+                    Span::raw(format!("{}{}", toast.message, repeated)),
+                ])
+            }
+            None => Line::from(format!(" {}", hint)),
+        }
+    }
+}
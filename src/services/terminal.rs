@@ -0,0 +1,159 @@
+use std::{
+    backtrace::Backtrace,
+    io, panic, process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Once,
+    },
+    thread,
+};
+
+use anyhow::Result;
+use crossterm::{
+    cursor::Show,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use signal_hook::{
+    consts::{SIGHUP, SIGINT, SIGQUIT, SIGTERM},
+    iterator::Signals,
+};
+
+use crate::{
+    services::draft::save_in_flight,
+    utils::{
+        error::AppError,
+        logger::{log_error, write_crash_log},
+    },
+};
+
+/// Whether the terminal is in raw mode on the alternate screen.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+static HANDLERS: Once = Once::new();
+
+/// Switches the terminal to raw mode on the alternate screen, reporting mouse events.
+pub fn enter_terminal() -> Result<()> {
+    enable_raw_mode().map_err(|e| {
+        log_error(&format!("Failed to enable raw mode. Reason: {}", e));
+        AppError::UnexpectedError(format!("Failed to enable raw mode. Reason: {}", e))
+    })?;
+    ACTIVE.store(true, Ordering::SeqCst);
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture).map_err(|e| {
+        log_error(&format!(
+            "Failed to enter the alternate screen. Reason: {}",
+            e
+        ));
+        AppError::UnexpectedError(format!(
+            "Failed to enter the alternate screen. Reason: {}",
+            e
+        ))
+    })?;
+    Ok(())
+}
+
+/// Leaves the alternate screen, raw mode and mouse capture and shows the cursor.
+///
+/// Does nothing unless [`enter_terminal`] ran, so the panic hook, the signal
+/// handlers and a normal exit can all call it.
+pub fn restore_terminal() {
+    if ACTIVE.swap(false, Ordering::SeqCst) {
+        let _ = execute!(
+            io::stdout(),
+            DisableMouseCapture,
+            LeaveAlternateScreen,
+            Show
+        );
+        let _ = disable_raw_mode();
+    }
+}
+
+/// Hands the terminal over to another program, such as `$EDITOR`, until
+/// [`resume_terminal`]: leaves raw mode and, inside the application, the
+/// alternate screen and mouse capture.
+pub fn suspend_terminal() -> Result<()> {
+    if ACTIVE.load(Ordering::SeqCst) {
+        execute!(io::stdout(), DisableMouseCapture, LeaveAlternateScreen).map_err(|e| {
+            log_error(&format!(
+                "Failed to leave the alternate screen. Reason: {}",
+                e
+            ));
+            AppError::UnexpectedError(format!(
+                "Failed to leave the alternate screen. Reason: {}",
+                e
+            ))
+        })?;
+    }
+    disable_raw_mode().map_err(|e| {
+        log_error(&format!("Failed to disable raw mode. Reason: {}", e));
+        AppError::UnexpectedError(format!("Failed to disable raw mode. Reason: {}", e))
+    })?;
+    Ok(())
+}
+
+/// Takes the terminal back after [`suspend_terminal`].
+pub fn resume_terminal() -> Result<()> {
+    enable_raw_mode().map_err(|e| {
+        log_error(&format!("Failed to enable raw mode. Reason: {}", e));
+        AppError::UnexpectedError(format!("Failed to enable raw mode. Reason: {}", e))
+    })?;
+    if ACTIVE.load(Ordering::SeqCst) {
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture).map_err(|e| {
+            log_error(&format!(
+                "Failed to enter the alternate screen. Reason: {}",
+                e
+            ));
+            AppError::UnexpectedError(format!(
+                "Failed to enter the alternate screen. Reason: {}",
+                e
+            ))
+        })?;
+    }
+    Ok(())
+}
+
+/// Restores the terminal and saves the question waiting for the model when
+/// the application panics or receives `SIGINT`, `SIGTERM`, `SIGHUP` or `SIGQUIT`.
+///
+/// Panics are written with their backtrace to a crash log in the log directory.
+/// Installing the handlers more than once has no effect.
+pub fn install_handlers() -> Result<()> {
+    let mut result = Ok(());
+    HANDLERS.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            let draft = save_in_flight();
+            log_error(&format!("Crash: {}", info));
+            let report = format!("{}\n\n{}", info, Backtrace::force_capture());
+            if let Ok(path) = write_crash_log(&report) {
+                eprintln!("Legatio crashed, see {}", path.display());
+            }
+            if let Some(path) = draft {
+                eprintln!("The question being asked was saved to {}", path.display());
+            }
+            default_hook(info);
+        }));
+
+        result = Signals::new([SIGINT, SIGTERM, SIGHUP, SIGQUIT])
+            .map(|mut signals| {
+                thread::spawn(move || {
+                    if let Some(signal) = signals.forever().next() {
+                        restore_terminal();
+                        let draft = save_in_flight();
+                        log_error(&format!("Received signal {}, exiting", signal));
+                        if let Some(path) = draft {
+                            eprintln!("The question being asked was saved to {}", path.display());
+                        }
+                        process::exit(128 + signal);
+                    }
+                });
+            })
+            .map_err(|e| {
+                log_error(&format!("Failed to handle signals. Reason: {}", e));
+                AppError::UnexpectedError(format!("Failed to handle signals. Reason: {}", e)).into()
+            });
+    });
+    result
+}
@@ -0,0 +1,556 @@
+use std::collections::{HashMap, HashSet};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{
+    layout::{Position, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, List, ListItem, ListState},
+    Frame,
+};
+
+use crate::{core::tag::ProjectMarks, utils::structs::Prompt};
+
+use super::list::{fuzzy_rank, item_at, ListAction, PAGE_SIZE};
+
+/// A visible node of a [`PromptTree`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeRow {
+    pub prompt_id: String,
+    /// Zero for the roots of the project.
+    pub depth: usize,
+    /// Number of direct replies.
+    pub children: usize,
+    /// Number of prompts hidden by collapsing this one.
+    pub hidden: usize,
+    pub collapsed: bool,
+    /// Whether the prompt is on the chain leading to the current prompt.
+    pub on_path: bool,
+    /// For each ancestor below the root, whether more of its siblings follow.
+    guides: Vec<bool>,
+    /// Whether this is the last visible reply of its parent.
+    last: bool,
+}
+
+/// The prompts of a project as a tree with collapsible subtrees.
+///
+/// `j`/`k` and the arrows move between the visible prompts, `Left`/`Right`
+/// collapse and expand a subtree or go to the parent and first reply, `J`/`K`
+/// go to the next and previous sibling, `Space` folds a subtree and `/`
+/// filters the tree.
+#[derive(Debug, Default)]
+pub struct PromptTree {
+    prompts: Vec<Prompt>,
+    marks: ProjectMarks,
+    /// Index into `prompts` of each prompt id.
+    index: HashMap<String, usize>,
+    /// Indexes into `prompts` of the replies of each prompt id.
+    children: HashMap<String, Vec<usize>>,
+    roots: Vec<usize>,
+    collapsed: HashSet<String>,
+    current: Option<String>,
+    /// Ids of the prompts from the root down to the current prompt.
+    path: HashSet<String>,
+    filter: String,
+    filtering: bool,
+    /// With a filter, the prompts matching it and their ancestors.
+    shown: Option<HashSet<String>>,
+    rows: Vec<TreeRow>,
+    state: ListState,
+    /// Inside of the block the tree was last drawn in, for the mouse.
+    area: Rect,
+}
+
+impl PromptTree {
+    /// Replaces the prompts, keeping the selection and the collapsed subtrees.
+    /// Prompts whose parent is missing are shown as roots.
+    pub fn set_prompts(&mut self, prompts: Vec<Prompt>, marks: ProjectMarks) {
+        self.index.clear();
+        self.children.clear();
+        self.roots.clear();
+        for (i, p) in prompts.iter().enumerate() {
+            self.index.insert(p.prompt_id.clone(), i);
+        }
+        for (i, p) in prompts.iter().enumerate() {
+            if p.prev_prompt_id == p.project_id || !self.index.contains_key(&p.prev_prompt_id) {
+                self.roots.push(i);
+            } else {
+                self.children
+                    .entry(p.prev_prompt_id.clone())
+                    .or_default()
+                    .push(i);
+            }
+        }
+        self.prompts = prompts;
+        self.marks = marks;
+        self.collapsed.retain(|id| self.index.contains_key(id));
+        self.update_path();
+        self.refilter();
+    }
+
+    /// Marks `prompt_id` as the current prompt and highlights its chain. When
+    /// the current prompt changes it is revealed and selected.
+    pub fn set_current(&mut self, prompt_id: Option<&str>) {
+        if self.current.as_deref() == prompt_id {
+            return;
+        }
+        self.current = prompt_id.map(String::from);
+        self.update_path();
+        self.rebuild();
+        if let Some(id) = prompt_id {
+            self.select_id(id);
+        }
+    }
+
+    /// The visible prompts, in display order.
+    pub fn rows(&self) -> &[TreeRow] {
+        &self.rows
+    }
+
+    /// The id of the highlighted prompt.
+    pub fn selected_id(&self) -> Option<&str> {
+        self.selected_row().map(|row| row.prompt_id.as_str())
+    }
+
+    /// The highlighted prompt.
+    pub fn selected_prompt(&self) -> Option<&Prompt> {
+        self.selected_id().and_then(|id| self.prompt(id))
+    }
+
+    /// Highlights the prompt with `id`, expanding its ancestors if needed.
+    ///
+    /// # Returns:
+    /// - `false` when the prompt is not in the tree or hidden by the filter.
+    pub fn select_id(&mut self, id: &str) -> bool {
+        let mut parent = self.parent_of(id);
+        let mut seen = HashSet::new();
+        let mut expanded = false;
+        while let Some(p) = parent.filter(|p| seen.insert(p.clone())) {
+            expanded |= self.collapsed.remove(&p);
+            parent = self.parent_of(&p);
+        }
+        if expanded {
+            self.rebuild();
+        }
+        match self.rows.iter().position(|row| row.prompt_id == id) {
+            Some(index) => {
+                self.state.select(Some(index));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    pub fn is_filtering(&self) -> bool {
+        self.filtering
+    }
+
+    /// Replaces the filter, showing the matching prompts with their ancestors.
+    pub fn set_filter(&mut self, filter: &str) {
+        self.filter = filter.to_string();
+        self.refilter();
+        self.select_index(0);
+    }
+
+    /// Collapses the subtree below `id`, or expands it when already collapsed.
+    pub fn toggle(&mut self, id: &str) {
+        if !self.collapsed.remove(id) && self.children.contains_key(id) {
+            self.collapsed.insert(id.to_string());
+        }
+        self.rebuild();
+    }
+
+    /// Handles a key press.
+    pub fn handle_key(&mut self, key: KeyEvent) -> ListAction {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Up => self.move_by(-1),
+            KeyCode::Down => self.move_by(1),
+            KeyCode::Char('p') if control => self.move_by(-1),
+            KeyCode::Char('n') if control => self.move_by(1),
+            KeyCode::Char('k') if !self.filtering => self.move_by(-1),
+            KeyCode::Char('j') if !self.filtering => self.move_by(1),
+            KeyCode::Char('K') if !self.filtering => self.move_to_sibling(-1),
+            KeyCode::Char('J') if !self.filtering => self.move_to_sibling(1),
+            KeyCode::PageUp => self.move_by(-(PAGE_SIZE as isize)),
+            KeyCode::PageDown => self.move_by(PAGE_SIZE as isize),
+            KeyCode::Home => self.select_index(0),
+            KeyCode::End => self.select_index(self.rows.len().saturating_sub(1)),
+            KeyCode::Left => self.collapse_or_parent(),
+            KeyCode::Right => self.expand_or_child(),
+            KeyCode::Char(' ') if !self.filtering => {
+                if let Some(id) = self.selected_id().map(String::from) {
+                    self.toggle(&id);
+                }
+            }
+            KeyCode::Enter => {
+                return match self.selected_id() {
+                    Some(id) => ListAction::Select(id.to_string()),
+                    None => ListAction::Handled,
+                };
+            }
+            KeyCode::Esc if self.filtering || !self.filter.is_empty() => {
+                self.filtering = false;
+                self.set_filter("");
+            }
+            KeyCode::Char('/') if !self.filtering => self.filtering = true,
+            KeyCode::Backspace if self.filtering => {
+                if self.filter.is_empty() {
+                    self.filtering = false;
+                } else {
+                    let mut filter = self.filter.clone();
+                    filter.pop();
+                    self.set_filter(&filter);
+                }
+            }
+            KeyCode::Char(c) if self.filtering && !control => {
+                let filter = format!("{}{}", self.filter, c);
+                self.set_filter(&filter);
+            }
+            _ => return ListAction::Ignored,
+        }
+        ListAction::Handled
+    }
+
+    /// Handles a mouse event: the wheel moves the selection, a click highlights
+    /// a prompt and a click on the highlighted prompt continues it.
+    pub fn handle_mouse(&mut self, mouse: MouseEvent) -> ListAction {
+        if !self.area.contains(Position::new(mouse.column, mouse.row)) {
+            return ListAction::Ignored;
+        }
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.move_by(-1),
+            MouseEventKind::ScrollDown => self.move_by(1),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let heights = self.rows.iter().map(|row| self.row_lines(row).len());
+                let row = (mouse.row - self.area.y) as usize;
+                match item_at(heights, self.state.offset(), row) {
+                    Some(index) if self.state.selected() == Some(index) => {
+                        return match self.selected_id() {
+                            Some(id) => ListAction::Select(id.to_string()),
+                            None => ListAction::Handled,
+                        };
+                    }
+                    Some(index) => self.select_index(index),
+                    None => {}
+                }
+            }
+            _ => return ListAction::Ignored,
+        }
+        ListAction::Handled
+    }
+
+    /// Renders the tree inside `block`, drawing the chain of the current prompt
+    /// with `path_style`.
+    pub fn render(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        block: Block,
+        style: Style,
+        highlight: Style,
+        path_style: Style,
+    ) {
+        self.area = block.inner(area);
+        let block = if self.filtering || !self.filter.is_empty() {
+            block.title_bottom(format!("[ /{} ]", self.filter))
+        } else {
+            block
+        };
+        let items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let item = ListItem::new(Text::from(self.row_lines(row)));
+                if row.on_path {
+                    item.style(path_style)
+                } else {
+                    item
+                }
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block)
+            .style(style)
+            .highlight_style(highlight);
+        frame.render_stateful_widget(list, area, &mut self.state);
+    }
+
+    /// The lines drawn for `row`: the prompt with its marks, the second parent
+    /// of a merge and the first line of the output.
+    fn row_lines(&self, row: &TreeRow) -> Vec<Line<'static>> {
+        let Some(prompt) = self.prompt(&row.prompt_id) else {
+            return vec![];
+        };
+
+        let mut guides: String = row
+            .guides
+            .iter()
+            .map(|&more| if more { "│  " } else { "   " })
+            .collect();
+        let connector = match (row.depth, row.last) {
+            (0, _) => "",
+            (_, true) => "└─ ",
+            (_, false) => "├─ ",
+        };
+        let marker = match (row.children, row.collapsed) {
+            (0, _) => "•",
+            (_, true) => "▸",
+            (_, false) => "▾",
+        };
+        let count = match (row.children, row.collapsed) {
+            (0, _) => String::new(),
+            (_, true) => format!(" (+{})", row.hidden),
+            (n, false) => format!(" ({})", n),
+        };
+
+        let mut content = prompt.content.lines();
+        let mut lines = vec![Line::from(format!(
+            "{}{}{} {}{}{}",
+            guides,
+            connector,
+            marker,
+            content.next().unwrap_or_default(),
+            self.marks.label(&prompt.prompt_id),
+            count
+        ))];
+
+        // The following lines line up under the content, keeping the guides
+        if row.depth > 0 {
+            guides.push_str(if row.last { "   " } else { "│  " });
+        }
+        let bar = if row.children > 0 && !row.collapsed {
+            "│ "
+        } else {
+            "  "
+        };
+        let indent = format!("{}{}", guides, bar);
+
+        for line in content {
+            lines.push(Line::from(format!("{}{}", indent, line)));
+        }
+        for parent_id in self
+            .marks
+            .merged
+            .get(&prompt.prompt_id)
+            .into_iter()
+            .flatten()
+        {
+            if let Some(parent) = self.prompt(parent_id) {
+                lines.push(Line::from(format!(
+                    "{}> Merged: {}",
+                    indent,
+                    parent.content.lines().next().unwrap_or_default()
+                )));
+            }
+        }
+        let mut output = prompt.output.lines();
+        let first = output.next().unwrap_or_default();
+        let more = if output.next().is_some() { " …" } else { "" };
+        lines.push(Line::from(vec![
+            Span::raw(indent),
+            Span::styled(
+                format!("Output: {}{}", first, more),
+                Style::default().add_modifier(Modifier::DIM),
+            ),
+        ]));
+        lines
+    }
+
+    fn prompt(&self, id: &str) -> Option<&Prompt> {
+        self.index.get(id).map(|&i| &self.prompts[i])
+    }
+
+    fn parent_of(&self, id: &str) -> Option<String> {
+        self.prompt(id)
+            .map(|p| p.prev_prompt_id.clone())
+            .filter(|prev| self.index.contains_key(prev))
+    }
+
+    fn selected_row(&self) -> Option<&TreeRow> {
+        self.state.selected().and_then(|index| self.rows.get(index))
+    }
+
+    /// Recomputes the chain of the current prompt.
+    fn update_path(&mut self) {
+        self.path.clear();
+        let mut next = self.current.clone();
+        while let Some(id) = next {
+            if !self.index.contains_key(&id) || !self.path.insert(id.clone()) {
+                break;
+            }
+            next = self.parent_of(&id);
+        }
+    }
+
+    /// Recomputes the prompts shown by the filter, then the rows.
+    fn refilter(&mut self) {
+        self.shown = if self.filter.is_empty() {
+            None
+        } else {
+            let haystacks: Vec<String> = self
+                .prompts
+                .iter()
+                .map(|p| format!("{} {}", p.content, self.marks.label(&p.prompt_id)))
+                .collect();
+            let mut shown = HashSet::new();
+            for i in fuzzy_rank(&self.filter, &haystacks) {
+                let mut id = Some(self.prompts[i].prompt_id.clone());
+                while let Some(current) = id {
+                    if !shown.insert(current.clone()) {
+                        break;
+                    }
+                    id = self.parent_of(&current);
+                }
+            }
+            Some(shown)
+        };
+        self.rebuild();
+    }
+
+    /// Recomputes the visible rows, keeping the selection on the same prompt.
+    fn rebuild(&mut self) {
+        let selected = self.selected_id().map(String::from);
+        let index = self.state.selected().unwrap_or(0);
+
+        let mut rows = vec![];
+        let roots = self.visible(&self.roots);
+        for (n, &i) in roots.iter().enumerate() {
+            self.push_rows(i, &mut vec![], n + 1 == roots.len(), &mut rows);
+        }
+        self.rows = rows;
+
+        match selected {
+            Some(id) if self.rows.iter().any(|row| row.prompt_id == id) => {
+                let position = self.rows.iter().position(|row| row.prompt_id == id);
+                self.state.select(position);
+            }
+            _ => self.select_index(index),
+        }
+    }
+
+    /// The prompts of `indexes` that the filter shows.
+    fn visible(&self, indexes: &[usize]) -> Vec<usize> {
+        indexes
+            .iter()
+            .copied()
+            .filter(|&i| match &self.shown {
+                Some(shown) => shown.contains(&self.prompts[i].prompt_id),
+                None => true,
+            })
+            .collect()
+    }
+
+    fn push_rows(&self, index: usize, guides: &mut Vec<bool>, last: bool, rows: &mut Vec<TreeRow>) {
+        let prompt = &self.prompts[index];
+        // Roots and prompts whose parent is missing start at the left
+        let nested = self.index.contains_key(&prompt.prev_prompt_id);
+        let replies = self.visible(
+            self.children
+                .get(&prompt.prompt_id)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+        );
+        // A filter shows every match, whatever was collapsed
+        let collapsed = !replies.is_empty()
+            && self.shown.is_none()
+            && self.collapsed.contains(&prompt.prompt_id);
+
+        rows.push(TreeRow {
+            prompt_id: prompt.prompt_id.clone(),
+            depth: guides.len() + usize::from(nested),
+            children: replies.len(),
+            hidden: if collapsed {
+                self.descendants(&prompt.prompt_id)
+            } else {
+                0
+            },
+            collapsed,
+            on_path: self.path.contains(&prompt.prompt_id),
+            guides: guides.clone(),
+            last,
+        });
+
+        if collapsed {
+            return;
+        }
+        if nested {
+            guides.push(!last);
+        }
+        for (n, &i) in replies.iter().enumerate() {
+            self.push_rows(i, guides, n + 1 == replies.len(), rows);
+        }
+        if nested {
+            guides.pop();
+        }
+    }
+
+    fn descendants(&self, id: &str) -> usize {
+        self.children
+            .get(id)
+            .into_iter()
+            .flatten()
+            .map(|&i| 1 + self.descendants(&self.prompts[i].prompt_id))
+            .sum()
+    }
+
+    fn select_index(&mut self, index: usize) {
+        if self.rows.is_empty() {
+            self.state.select(None);
+        } else {
+            self.state.select(Some(index.min(self.rows.len() - 1)));
+        }
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let current = self.state.selected().unwrap_or(0) as isize;
+        self.select_index((current + delta).max(0) as usize);
+    }
+
+    /// Moves to the next (`delta > 0`) or previous sibling, staying put on the
+    /// last or first reply.
+    fn move_to_sibling(&mut self, delta: isize) {
+        let Some(index) = self.state.selected() else {
+            return;
+        };
+        let depth = self.rows[index].depth;
+        let mut i = index as isize + delta.signum();
+        while i >= 0 && (i as usize) < self.rows.len() {
+            let row = &self.rows[i as usize];
+            if row.depth < depth {
+                return;
+            }
+            if row.depth == depth {
+                self.state.select(Some(i as usize));
+                return;
+            }
+            i += delta.signum();
+        }
+    }
+
+    fn collapse_or_parent(&mut self) {
+        let Some(row) = self.selected_row().cloned() else {
+            return;
+        };
+        if row.children > 0 && !row.collapsed && self.shown.is_none() {
+            self.toggle(&row.prompt_id);
+        } else if let Some(parent) = self.parent_of(&row.prompt_id) {
+            self.select_id(&parent);
+        }
+    }
+
+    fn expand_or_child(&mut self) {
+        let Some(row) = self.selected_row().cloned() else {
+            return;
+        };
+        if row.collapsed {
+            self.toggle(&row.prompt_id);
+        } else if row.children > 0 {
+            self.move_by(1);
+        }
+    }
+}
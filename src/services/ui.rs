@@ -1,14 +1,18 @@
-use std::fs;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::SystemTime,
+};
 
-use anyhow::{Context, Ok, Result};
+use anyhow::{Context, Result};
 use ratatui::style::Color;
+use serde::Deserialize;
 use sqlx::SqlitePool;
 
 use crate::{
-    core::{
-        prompt::{format_prompt, format_prompt_depth},
-        scroll::get_scrolls,
-    },
+    core::{prompt::format_prompt, scroll::get_scrolls},
     utils::{
         error::AppError,
         logger::log_error,
@@ -16,15 +20,50 @@ use crate::{
     },
 };
 
-use toml::Value;
-
 use super::config::get_config_dir;
 
+/// Colors of a theme from `themes.toml`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct ThemeColors {
     pub background: Color,
+    /// Titles of the boxes.
     pub primary: Color,
+    /// Text.
     pub secondary: Color,
+    /// The bottom pane and the popups.
     pub accent: Color,
+    /// Borders of the top and scroll boxes.
+    pub border: Color,
+    /// The highlighted item of a list or tree.
+    pub selection: Color,
+    /// Matches of a search in the preview.
+    pub highlight: Color,
+    /// Errors in the status bar and the notifications.
+    pub error: Color,
+    /// Added and removed lines of `diff` code blocks.
+    pub diff_add: Color,
+    pub diff_remove: Color,
+    /// Inline code in the preview.
+    pub code: Color,
+}
+
+impl Default for ThemeColors {
+    /// The colors of the terminal, used when no theme could be read.
+    fn default() -> Self {
+        ThemeColors {
+            background: Color::Reset,
+            primary: Color::Reset,
+            secondary: Color::Reset,
+            accent: Color::Reset,
+            border: Color::Reset,
+            selection: Color::Reset,
+            highlight: Color::Reset,
+            error: Color::Red,
+            diff_add: Color::Green,
+            diff_remove: Color::Red,
+            code: Color::Yellow,
+        }
+    }
 }
 
 pub async fn usr_scrolls(pool: &SqlitePool, project: &Project) -> Result<Vec<String>> {
@@ -38,50 +77,6 @@ pub async fn usr_scrolls(pool: &SqlitePool, project: &Project) -> Result<Vec<Str
         .collect())
 }
 
-pub fn helper_print(prompts: &Vec<Prompt>, prompt: &Prompt, b_depth: &str) -> Result<Vec<String>> {
-    let mut format_vec: Vec<String> = vec![];
-    format_vec.push(b_depth.to_string());
-
-    let (p_str, o_str) = format_prompt_depth(prompt, b_depth);
-    format_vec.push(p_str);
-    format_vec.push(o_str);
-
-    let new_indent = format!("{}  |", b_depth); // Append to the current indentation for children
-
-    let child_prompts: Vec<&Prompt> = prompts
-        .iter()
-        .filter(|p| p.prev_prompt_id == prompt.prompt_id)
-        .collect();
-
-    for p in child_prompts.iter() {
-        let child_vec = helper_print(prompts, p, &new_indent)
-            .with_context(|| format!("Error processing child prompt with ID: {:?}", p.prompt_id))?;
-        format_vec.extend(child_vec);
-    }
-
-    Ok(format_vec)
-}
-
-pub async fn usr_prompts(prompts: &Vec<Prompt>) -> Result<Vec<String>> {
-    let fst_prompts: Vec<&Prompt> = prompts
-        .iter()
-        .filter(|p| p.prev_prompt_id == p.project_id)
-        .collect();
-
-    let mut format_vec: Vec<String> = vec![];
-    for fst_prompt in fst_prompts.iter() {
-        let child_vec = helper_print(prompts, fst_prompt, "  |").with_context(|| {
-            format!(
-                "Error processing first prompt with ID: {:?}",
-                fst_prompt.prompt_id
-            )
-        })?;
-        format_vec.extend(child_vec);
-    }
-
-    Ok(format_vec)
-}
-
 pub fn usr_prompt_chain(prompts: &[Prompt]) -> Vec<String> {
     let mut str_items: Vec<String> = Vec::new();
     for p in prompts.iter() {
@@ -94,124 +89,203 @@ pub fn usr_prompt_chain(prompts: &[Prompt]) -> Vec<String> {
     str_items
 }
 
+/// Path of `themes.toml` in the config directory.
+pub fn themes_path() -> Result<PathBuf> {
+    Ok(get_config_dir()?.join("themes.toml"))
+}
+
+/// Reads `themes.toml` and returns the colors of the theme named `theme_name`.
 pub fn extract_theme_colors(theme_name: &str) -> Result<ThemeColors> {
-    let config_dir = get_config_dir()?;
-    let themes_path = config_dir.join("themes.toml");
-
-    // Read the themes.toml file
-    let themes_content = fs::read_to_string(&themes_path).map_err(|e| {
-        log_error(&format!(
-            "Failed to read file {}: {}",
-            themes_path.to_string_lossy(),
-            e
-        ));
-        AppError::FileError(format!(
-            "Failed to read file {}: {}",
-            themes_path.to_string_lossy(),
-            e
-        ))
-    })?;
+    let mut themes = ThemeCache::default();
+    themes.refresh(&themes_path()?)?;
+    themes.colors(theme_name)
+}
 
-    // Parse TOML file into a generic Value
-    let themes_toml: Value = themes_content.parse::<Value>().map_err(|e| {
-        log_error(&format!("Failed to parse TOML: {}", e));
-        AppError::ParseError(format!("Failed to parse TOML: {}", e))
+/// Parses a `themes.toml` file, keeping each theme by name. The colors are
+/// checked when a theme is picked, so a broken theme does not hide the others.
+fn parse_themes(content: &str) -> Result<BTreeMap<String, ThemeSpec>> {
+    let file: ThemeFile = toml::from_str(content).map_err(|e| {
+        log_error(&format!("Failed to parse themes.toml: {}", e));
+        AppError::ParseError(format!("Failed to parse themes.toml: {}", e))
     })?;
 
-    // Locate the `themes` array in the TOML file
-    let default = Color::Rgb(0, 0, 0);
-    if let Some(themes) = themes_toml.get("themes").and_then(|t| t.as_array()) {
-        // Search for the theme with matching name
-        for theme in themes {
-            if let Some(name) = theme.get("name").and_then(|n| n.as_str()) {
-                if name == theme_name {
-                    return Ok(ThemeColors {
-                        background: hex_to_tui_color(
-                            theme
-                                .get("background")
-                                .and_then(|b| b.as_str())
-                                .unwrap_or("#"),
-                        )
-                        .unwrap_or(default),
-                        primary: hex_to_tui_color(
-                            theme.get("primary").and_then(|p| p.as_str()).unwrap_or("#"),
-                        )
-                        .unwrap_or(default),
-                        secondary: hex_to_tui_color(
-                            theme
-                                .get("secondary")
-                                .and_then(|s| s.as_str())
-                                .unwrap_or("#"),
-                        )
-                        .unwrap_or(default),
-                        accent: hex_to_tui_color(
-                            theme.get("accent").and_then(|a| a.as_str()).unwrap_or("#"),
-                        )
-                        .unwrap_or(default),
-                    });
-                }
-            }
+    let mut themes = BTreeMap::new();
+    for spec in file.themes {
+        let name = spec.name.clone();
+        if themes.insert(name.clone(), spec).is_some() {
+            log_error(&format!("Theme '{}' is defined twice", name));
+            return Err(AppError::ParseError(format!("Theme '{}' is defined twice", name)).into());
         }
     }
+    Ok(themes)
+}
 
-    Ok(ThemeColors {
-        background: default,
-        primary: default,
-        secondary: default,
-        accent: default,
+/// Parses a theme color: `#rrggbb`, `#rgb`, an ANSI color name such as `red`,
+/// `lightblue` or `darkgray`, a color index from `0` to `255`, or `reset` for
+/// the color of the terminal.
+pub fn parse_theme_color(value: &str) -> Result<Color> {
+    color_from_str(value).map_err(|reason| {
+        log_error(&format!("Invalid color '{}': {}", value, reason));
+        AppError::ParseError(format!("Invalid color '{}': {}", value, reason)).into()
     })
 }
 
-// Converts an RGB tuple to `ratatui::Color`
-fn hex_to_tui_color(hex: &str) -> Result<Color> {
-    let hex = hex.trim_start_matches('#');
-
-    let mut r: u8 = 0;
-    let mut g: u8 = 0;
-    let mut b: u8 = 0;
-    let hex_len = hex.len();
-    if hex_len >= 2 {
-        r = u8::from_str_radix(&hex[0..2], 16)
-            .map_err(|err| {
-                log_error(&format!(
-                    "Invalid red component in: {}, error: {}",
-                    hex, err
-                ));
-                AppError::ParseError(format!(
-                    "Invalid red component in: {}. Reason: {}",
-                    hex, err
-                ));
-            })
-            .unwrap();
+/// Parses a theme color like [`parse_theme_color`], explaining why it failed.
+fn color_from_str(value: &str) -> Result<Color, String> {
+    let Some(hex) = value.strip_prefix('#') else {
+        return Color::from_str(value).map_err(|_| {
+            String::from(
+                "expected #rrggbb, #rgb, a color index or a color name such as red, lightblue or darkgray",
+            )
+        });
+    };
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(String::from("hex colors only use the digits 0-9 and a-f"));
     }
-    if hex_len >= 4 {
-        g = u8::from_str_radix(&hex[2..4], 16)
-            .map_err(|err| {
-                log_error(&format!(
-                    "Invalid green component in: {}, error: {}",
-                    hex, err
-                ));
-                AppError::ParseError(format!(
-                    "Invalid green component in: {}. Reason: {}",
-                    hex, err
-                ));
-            })
-            .unwrap();
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).unwrap_or(0);
+    match hex.len() {
+        6 => Ok(Color::Rgb(
+            digit(0) * 16 + digit(1),
+            digit(2) * 16 + digit(3),
+            digit(4) * 16 + digit(5),
+        )),
+        3 => Ok(Color::Rgb(digit(0) * 17, digit(1) * 17, digit(2) * 17)),
+        len => Err(format!(
+            "hex colors have 6 digits (#rrggbb) or 3 (#rgb), not {}",
+            len
+        )),
     }
-    if hex_len == 6 {
-        b = u8::from_str_radix(&hex[4..6], 16)
-            .map_err(|err| {
+}
+
+/// Layout of `themes.toml`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    themes: Vec<ThemeSpec>,
+}
+
+/// A theme as written in `themes.toml`. The colors after `accent` are optional
+/// and default to one of the first four or to a fixed ANSI color.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeSpec {
+    name: String,
+    background: String,
+    primary: String,
+    secondary: String,
+    accent: String,
+    border: Option<String>,
+    selection: Option<String>,
+    highlight: Option<String>,
+    error: Option<String>,
+    diff_add: Option<String>,
+    diff_remove: Option<String>,
+    code: Option<String>,
+}
+
+impl ThemeSpec {
+    fn colors(&self) -> Result<ThemeColors> {
+        let color = |field: &str, value: &str| -> Result<Color> {
+            color_from_str(value).map_err(|reason| {
                 log_error(&format!(
-                    "Invalid blue component in: {}, error: {}",
-                    hex, err
+                    "Invalid {} color '{}' in theme '{}': {}",
+                    field, value, self.name, reason
                 ));
                 AppError::ParseError(format!(
-                    "Invalid blue component in: {}. Reason: {}",
-                    hex, err
-                ));
+                    "Invalid {} color '{}' in theme '{}': {}",
+                    field, value, self.name, reason
+                ))
+                .into()
             })
-            .unwrap();
+        };
+        let optional = |field: &str, value: &Option<String>, default: Color| -> Result<Color> {
+            value
+                .as_deref()
+                .map_or(Ok(default), |value| color(field, value))
+        };
+
+        // In the order of the file, so the first broken color is reported
+        let background = color("background", &self.background)?;
+        let primary = color("primary", &self.primary)?;
+        let secondary = color("secondary", &self.secondary)?;
+        let accent = color("accent", &self.accent)?;
+        Ok(ThemeColors {
+            background,
+            primary,
+            secondary,
+            accent,
+            border: optional("border", &self.border, primary)?,
+            selection: optional("selection", &self.selection, accent)?,
+            highlight: optional("highlight", &self.highlight, accent)?,
+            error: optional("error", &self.error, Color::Red)?,
+            diff_add: optional("diff_add", &self.diff_add, Color::Green)?,
+            diff_remove: optional("diff_remove", &self.diff_remove, Color::Red)?,
+            code: optional("code", &self.code, Color::Yellow)?,
+        })
+    }
+}
+
+/// The themes of `themes.toml`, read again when the file changes.
+#[derive(Debug, Default)]
+pub struct ThemeCache {
+    /// Whether the file was read at least once.
+    read: bool,
+    /// Modification time of the file when it was last read.
+    modified: Option<SystemTime>,
+    /// The themes of the last file that could be parsed.
+    themes: BTreeMap<String, ThemeSpec>,
+}
+
+impl ThemeCache {
+    /// Reads the themes at `path` again if the file changed since the last call.
+    /// An invalid file is reported once and the themes read before are kept.
+    ///
+    /// # Returns:
+    /// - `Ok(true)` if the themes were read again, `Ok(false)` if the file did not change.
+    pub fn refresh(&mut self, path: &Path) -> Result<bool> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        if self.read && modified == self.modified {
+            return Ok(false);
+        }
+        self.read = true;
+        self.modified = modified;
+
+        let content = fs::read_to_string(path).map_err(|e| {
+            log_error(&format!(
+                "Failed to read file {}: {}",
+                path.to_string_lossy(),
+                e
+            ));
+            AppError::FileError(format!(
+                "Failed to read file {}: {}",
+                path.to_string_lossy(),
+                e
+            ))
+        })?;
+        self.themes = parse_themes(&content)?;
+        Ok(true)
     }
 
-    Ok(Color::Rgb(r, g, b))
+    /// The names of the themes, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        self.themes.keys().map(|name| name.as_str()).collect()
+    }
+
+    /// The colors of the theme named `name`.
+    ///
+    /// # Returns:
+    /// - An error naming the color that could not be read, or listing the
+    ///   themes if there is none named `name`.
+    pub fn colors(&self, name: &str) -> Result<ThemeColors> {
+        let Some(spec) = self.themes.get(name) else {
+            log_error(&format!("Theme '{}' is not in themes.toml", name));
+            return Err(AppError::ParseError(format!(
+                "Theme '{}' is not in themes.toml, available themes: {}",
+                name,
+                self.names().join(", ")
+            ))
+            .into());
+        };
+        spec.colors()
+    }
 }
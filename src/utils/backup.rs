@@ -0,0 +1,296 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use chrono::{Local, NaiveDateTime};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteConnection},
+    Acquire, ConnectOptions, Connection, Executor, Sqlite,
+};
+
+use crate::utils::{
+    error::AppError,
+    logger::{log_error, log_info},
+};
+
+const SNAPSHOT_PREFIX: &str = "legatio-";
+const SNAPSHOT_EXTENSION: &str = "db";
+const STAMP_FORMAT: &str = "%Y%m%d-%H%M%S%3f";
+
+/// A snapshot of the database kept in the backups directory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    pub created_at: NaiveDateTime,
+    /// What triggered the snapshot, e.g. `startup` or `delete-project`.
+    pub reason: String,
+    /// Number of projects in the snapshot, `None` when it cannot be read.
+    pub projects: Option<i64>,
+}
+
+/// Get the directory holding the snapshots of the database at `db_file`,
+/// `backups/<name>` next to the database. Creates the directory if it doesn't exist.
+pub fn get_backup_dir(db_file: &Path) -> Result<PathBuf> {
+    let name = db_file.file_stem().unwrap_or_default();
+    let backup_dir = db_file
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join("backups")
+        .join(name);
+    fs::create_dir_all(&backup_dir).map_err(|err| {
+        log_error(&format!(
+            "Failed to create backup directory {}: {}",
+            backup_dir.to_string_lossy(),
+            err
+        ));
+        AppError::FileError(format!(
+            "Failed to create backup directory {}: {}",
+            backup_dir.to_string_lossy(),
+            err
+        ))
+    })?;
+    Ok(backup_dir)
+}
+
+/// Splits a snapshot file name into its creation date and reason.
+fn parse_snapshot_name(path: &Path) -> Option<(NaiveDateTime, String)> {
+    if path.extension()? != SNAPSHOT_EXTENSION {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?.strip_prefix(SNAPSHOT_PREFIX)?;
+    // The stamp itself holds a single dash: `20240131-235959123-reason`
+    let split = stem.match_indices('-').nth(1).map(|(idx, _)| idx)?;
+    let created_at = NaiveDateTime::parse_from_str(&stem[..split], STAMP_FORMAT).ok()?;
+    Some((created_at, stem[split + 1..].to_string()))
+}
+
+/// Writes a consistent copy of the database into `backup_dir`.
+///
+/// Uses `VACUUM INTO`, which reads the database inside a single transaction, so
+/// the snapshot can be taken while the application is running.
+///
+/// # Parameters:
+/// - `executor`: A connection to the database to back up.
+/// - `backup_dir`: The directory the snapshot is written to.
+/// - `reason`: What triggered the snapshot, kept in its file name.
+///
+/// # Returns:
+/// - `Ok(PathBuf)` with the path of the new snapshot.
+pub async fn create_snapshot<'e, E>(executor: E, backup_dir: &Path, reason: &str) -> Result<PathBuf>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let stamp = Local::now().naive_local().format(STAMP_FORMAT);
+    let snapshot_path = backup_dir.join(format!(
+        "{}{}-{}.{}",
+        SNAPSHOT_PREFIX, stamp, reason, SNAPSHOT_EXTENSION
+    ));
+
+    sqlx::query("VACUUM INTO $1;")
+        .bind(snapshot_path.to_string_lossy().to_string())
+        .execute(executor)
+        .await
+        .map_err(|err| {
+            log_error(&format!(
+                "FAILED :: VACUUM INTO {}, error: {}",
+                snapshot_path.to_string_lossy(),
+                err
+            ));
+            AppError::DatabaseError(format!(
+                "Failed to back up the database to {}. Reason: {}",
+                snapshot_path.to_string_lossy(),
+                err
+            ))
+        })?;
+
+    log_info(&format!(
+        "Database backed up to {}",
+        snapshot_path.to_string_lossy()
+    ));
+    Ok(snapshot_path)
+}
+
+/// Lists the snapshots of `backup_dir`, newest first.
+///
+/// Files that were not written by [`create_snapshot`] are ignored.
+pub async fn list_snapshots(backup_dir: &Path) -> Result<Vec<Snapshot>> {
+    let entries = fs::read_dir(backup_dir).map_err(|err| {
+        log_error(&format!(
+            "Failed to read backup directory {}: {}",
+            backup_dir.to_string_lossy(),
+            err
+        ));
+        AppError::FileError(format!(
+            "Failed to read backup directory {}: {}",
+            backup_dir.to_string_lossy(),
+            err
+        ))
+    })?;
+
+    let mut snapshots = vec![];
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        let Some((created_at, reason)) = parse_snapshot_name(&path) else {
+            continue;
+        };
+        let projects = count_projects(&path).await;
+        snapshots.push(Snapshot {
+            path,
+            created_at,
+            reason,
+            projects,
+        });
+    }
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+
+    Ok(snapshots)
+}
+
+/// Counts the projects of a snapshot without modifying it.
+async fn count_projects(path: &Path) -> Option<i64> {
+    let mut conn = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true)
+        .connect()
+        .await
+        .ok()?;
+    let count = sqlx::query_scalar("SELECT COUNT(*) FROM projects;")
+        .fetch_one(&mut conn)
+        .await
+        .ok();
+    let _ = conn.close().await;
+    count
+}
+
+/// Deletes the oldest snapshots of `backup_dir`, keeping the `retention` newest ones.
+///
+/// # Returns:
+/// - `Ok(usize)` with the number of deleted snapshots.
+pub async fn prune_snapshots(backup_dir: &Path, retention: usize) -> Result<usize> {
+    let snapshots = list_snapshots(backup_dir).await?;
+    let mut deleted = 0;
+    for snapshot in snapshots.iter().skip(retention) {
+        fs::remove_file(&snapshot.path).map_err(|err| {
+            log_error(&format!(
+                "Failed to delete snapshot {}: {}",
+                snapshot.path.to_string_lossy(),
+                err
+            ));
+            AppError::FileError(format!(
+                "Failed to delete snapshot {}: {}",
+                snapshot.path.to_string_lossy(),
+                err
+            ))
+        })?;
+        deleted += 1;
+    }
+    Ok(deleted)
+}
+
+/// Snapshots the database into its backups directory and drops the snapshots
+/// beyond `retention`. Nothing is written when `retention` is 0.
+///
+/// # Returns:
+/// - `Ok(Some(PathBuf))` with the path of the new snapshot.
+/// - `Ok(None)` when backups are disabled.
+pub async fn backup_database<'a, A>(
+    conn: A,
+    reason: &str,
+    retention: usize,
+) -> Result<Option<PathBuf>>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    if retention == 0 {
+        return Ok(None);
+    }
+    let mut conn = conn.acquire().await.map_err(|err| {
+        log_error(&format!("Failed to acquire connection. Reason: {}", err));
+        AppError::DatabaseError(format!("Failed to acquire connection. Reason: {}", err))
+    })?;
+
+    let db_file: String =
+        sqlx::query_scalar("SELECT file FROM pragma_database_list WHERE name = 'main';")
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|err| {
+                log_error(&format!("Failed to locate the database file: {}", err));
+                AppError::DatabaseError(format!("Failed to locate the database file: {}", err))
+            })?;
+    if db_file.is_empty() {
+        return Err(
+            AppError::DatabaseError(String::from("Cannot back up an in-memory database")).into(),
+        );
+    }
+
+    let backup_dir = get_backup_dir(Path::new(&db_file))?;
+    let snapshot = create_snapshot(&mut *conn, &backup_dir, reason).await?;
+    prune_snapshots(&backup_dir, retention).await?;
+    Ok(Some(snapshot))
+}
+
+/// Replaces the database at `db_path` with a snapshot.
+///
+/// The current database is snapshotted into `backup_dir` first, so a restore can
+/// itself be undone. No connection to `db_path` may be open.
+///
+/// # Returns:
+/// - `Ok(Option<PathBuf>)` with the snapshot of the replaced database, if there was one.
+pub async fn restore_snapshot(
+    snapshot: &Path,
+    db_path: &Path,
+    backup_dir: &Path,
+) -> Result<Option<PathBuf>> {
+    if parse_snapshot_name(snapshot).is_none() || !snapshot.is_file() {
+        return Err(AppError::FileError(format!(
+            "{} is not a Legatio snapshot",
+            snapshot.to_string_lossy()
+        ))
+        .into());
+    }
+
+    let previous = if db_path.exists() {
+        let mut conn: SqliteConnection = SqliteConnectOptions::new()
+            .filename(db_path)
+            .connect()
+            .await
+            .map_err(|err| {
+                log_error(&format!("Failed to connect to the database: {}", err));
+                AppError::DatabaseError(format!("Failed to connect to the database: {}", err))
+            })?;
+        let previous = create_snapshot(&mut conn, backup_dir, "before-restore").await;
+        let _ = conn.close().await;
+        Some(previous?)
+    } else {
+        None
+    };
+
+    // Journal files belong to the replaced database
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let journal = PathBuf::from(format!("{}{}", db_path.to_string_lossy(), suffix));
+        if journal.exists() {
+            let _ = fs::remove_file(journal);
+        }
+    }
+    fs::copy(snapshot, db_path).map_err(|err| {
+        log_error(&format!(
+            "Failed to restore {} to {}: {}",
+            snapshot.to_string_lossy(),
+            db_path.to_string_lossy(),
+            err
+        ));
+        AppError::FileError(format!(
+            "Failed to restore {} to {}: {}",
+            snapshot.to_string_lossy(),
+            db_path.to_string_lossy(),
+            err
+        ))
+    })?;
+
+    log_info(&format!(
+        "Database restored from {}",
+        snapshot.to_string_lossy()
+    ));
+    Ok(previous)
+}
@@ -1,60 +1,151 @@
 use crate::{
-    services::config::get_config_dir,
-    utils::logger::{log_error, log_info},
+    services::config::get_data_dir,
+    utils::{
+        backup::backup_database,
+        logger::{log_error, log_info},
+    },
 };
 use anyhow::Result;
-use sqlx::{migrate::MigrateDatabase, sqlite::SqlitePool, Sqlite};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteConnection, SqlitePool},
+    ConnectOptions, Connection, Executor, Sqlite,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use super::error::AppError;
 
-/// Get the path of the database file inside the Legatio directory.
-fn get_db_path() -> Result<String> {
-    let config_dir = get_config_dir()?;
-    let db_url = format!("sqlite://{}/legatio.db", config_dir.to_string_lossy());
-    Ok(db_url)
+/// Name of the database opened when none is chosen.
+pub const DEFAULT_DATABASE: &str = "legatio";
+
+/// Get the path of the database `name` inside `data_dir`.
+///
+/// # Returns:
+/// - `Ok(PathBuf)` with the path of `<data_dir>/<name>.db`.
+/// - `AppError::ParseError` when the name holds anything but letters, digits, `-` and `_`.
+pub fn database_file(data_dir: &Path, name: &str) -> Result<PathBuf> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(AppError::ParseError(format!(
+            "Invalid database name [{}], use letters, digits, '-' and '_'",
+            name
+        ))
+        .into());
+    }
+    Ok(data_dir.join(format!("{}.db", name)))
 }
 
-pub async fn get_db_pool() -> Result<SqlitePool, AppError> {
-    let db_url = &get_db_path().unwrap();
-    // Check if database exists, if not, create it
-    if !Sqlite::database_exists(db_url).await.unwrap_or(false) {
-        match Sqlite::create_database(db_url).await {
-            Ok(_) => log_info("Database created successfully."),
-            Err(error) => {
-                let error_msg = format!("Failed to create database: {}", error);
-                log_error(&error_msg);
-                return Err(AppError::DatabaseError(error_msg));
-            }
+/// Get the path of the database `name` inside the Legatio data directory.
+pub fn get_db_file(name: &str) -> Result<PathBuf> {
+    database_file(&get_data_dir()?, name)
+}
+
+/// Lists the names of the databases inside `data_dir`, sorted.
+pub fn list_databases(data_dir: &Path) -> Result<Vec<String>> {
+    let entries = fs::read_dir(data_dir).map_err(|err| {
+        log_error(&format!(
+            "Failed to read data directory {}: {}",
+            data_dir.to_string_lossy(),
+            err
+        ));
+        AppError::FileError(format!(
+            "Failed to read data directory {}: {}",
+            data_dir.to_string_lossy(),
+            err
+        ))
+    })?;
+
+    let mut names: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "db"))
+        .filter_map(|p| Some(p.file_stem()?.to_str()?.to_string()))
+        .filter(|name| database_file(data_dir, name).is_ok())
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Moves the database and backups left in `legacy_dir` by older releases into
+/// `data_dir`. Nothing is moved when `data_dir` already has a default database.
+///
+/// # Returns:
+/// - `Ok(true)` when a database was moved.
+pub fn move_legacy_database(legacy_dir: &Path, data_dir: &Path) -> Result<bool, AppError> {
+    let legacy_db = legacy_dir.join(format!("{}.db", DEFAULT_DATABASE));
+    let target_db = data_dir.join(format!("{}.db", DEFAULT_DATABASE));
+    if !legacy_db.is_file() || target_db.exists() {
+        return Ok(false);
+    }
+
+    let move_path = |from: &Path, to: &Path| -> Result<(), AppError> {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).map_err(|err| {
+                AppError::FileError(format!(
+                    "Failed to create directory {}: {}",
+                    parent.to_string_lossy(),
+                    err
+                ))
+            })?;
         }
+        fs::rename(from, to).map_err(|err| {
+            let error_msg = format!(
+                "Failed to move {} to {}: {}",
+                from.to_string_lossy(),
+                to.to_string_lossy(),
+                err
+            );
+            log_error(&error_msg);
+            AppError::FileError(error_msg)
+        })
+    };
 
-        // Connect to the database
-        let pool = match SqlitePool::connect(db_url).await {
-            Ok(pool) => pool,
-            Err(error) => {
-                let error_msg = format!("Failed to connect to the database: {}", error);
-                log_error(&error_msg);
-                return Err(AppError::DatabaseError(error_msg));
-            }
-        };
+    for suffix in ["", "-wal", "-shm"] {
+        let from = PathBuf::from(format!("{}{}", legacy_db.to_string_lossy(), suffix));
+        if from.exists() {
+            let to = PathBuf::from(format!("{}{}", target_db.to_string_lossy(), suffix));
+            move_path(&from, &to)?;
+        }
+    }
+    let legacy_backups = legacy_dir.join("backups");
+    let target_backups = data_dir.join("backups").join(DEFAULT_DATABASE);
+    if legacy_backups.is_dir() && !target_backups.exists() {
+        move_path(&legacy_backups, &target_backups)?;
+    }
 
-        // Create the required tables
-        let create_projects_table = r#"
+    log_info(&format!(
+        "Moved the database from {} to {}",
+        legacy_db.to_string_lossy(),
+        target_db.to_string_lossy()
+    ));
+    Ok(true)
+}
+
+/// Schema migrations, applied in order. A database whose `PRAGMA user_version`
+/// is `n` already has the first `n` migrations applied.
+const MIGRATIONS: &[&[&str]] = &[
+    // 1: Initial schema
+    &[
+        r#"
             CREATE TABLE IF NOT EXISTS projects (
                 project_id TEXT PRIMARY KEY,
                 project_path TEXT
             );
-        "#;
-
-        let create_scrolls_table = r#"
+        "#,
+        r#"
             CREATE TABLE IF NOT EXISTS scrolls (
                 scroll_id TEXT PRIMARY KEY,
                 scroll_path TEXT,
                 content TEXT,
                 project_id TEXT
             );
-        "#;
-
-        let create_prompts_table = r#"
+        "#,
+        r#"
             CREATE TABLE IF NOT EXISTS prompts (
                 prompt_id TEXT,
                 project_id TEXT,
@@ -62,28 +153,318 @@ pub async fn get_db_pool() -> Result<SqlitePool, AppError> {
                 output TEXT,
                 prev_prompt_id TEXT
             );
-        "#;
+        "#,
+    ],
+    // 2: Project display names, descriptions, colors and icons
+    &[
+        "ALTER TABLE projects ADD COLUMN display_name TEXT;",
+        "ALTER TABLE projects ADD COLUMN description TEXT;",
+        "ALTER TABLE projects ADD COLUMN color TEXT;",
+        "ALTER TABLE projects ADD COLUMN icon TEXT;",
+    ],
+    // 3: Foreign keys from scrolls and prompts to their project, deleted in cascade.
+    // Rows of projects that no longer exist and rows repeating an id cannot be
+    // kept; they are set aside in quarantine tables, reported by `fsck`.
+    &[
+        r#"
+            CREATE TABLE quarantined_scrolls (
+                scroll_id TEXT,
+                scroll_path TEXT,
+                content TEXT,
+                project_id TEXT,
+                reason TEXT NOT NULL
+            );
+        "#,
+        r#"
+            CREATE TABLE quarantined_prompts (
+                prompt_id TEXT,
+                project_id TEXT,
+                content TEXT,
+                output TEXT,
+                prev_prompt_id TEXT,
+                reason TEXT NOT NULL
+            );
+        "#,
+        r#"
+            INSERT INTO quarantined_scrolls (scroll_id, scroll_path, content, project_id, reason)
+            SELECT scroll_id, scroll_path, content, project_id,
+                CASE WHEN project_id IN (SELECT project_id FROM projects)
+                    THEN 'duplicate id' ELSE 'deleted project' END
+            FROM scrolls
+            WHERE rowid NOT IN (
+                SELECT MIN(rowid) FROM scrolls
+                WHERE project_id IN (SELECT project_id FROM projects)
+                GROUP BY scroll_id
+            );
+        "#,
+        r#"
+            INSERT INTO quarantined_prompts
+                (prompt_id, project_id, content, output, prev_prompt_id, reason)
+            SELECT prompt_id, project_id, content, output, prev_prompt_id,
+                CASE WHEN project_id IN (SELECT project_id FROM projects)
+                    THEN 'duplicate id' ELSE 'deleted project' END
+            FROM prompts
+            WHERE rowid NOT IN (
+                SELECT MIN(rowid) FROM prompts
+                WHERE project_id IN (SELECT project_id FROM projects)
+                GROUP BY prompt_id
+            );
+        "#,
+        r#"
+            CREATE TABLE scrolls_new (
+                scroll_id TEXT PRIMARY KEY,
+                scroll_path TEXT,
+                content TEXT,
+                project_id TEXT NOT NULL
+                    REFERENCES projects(project_id) ON DELETE CASCADE
+            );
+        "#,
+        r#"
+            INSERT INTO scrolls_new (scroll_id, scroll_path, content, project_id)
+            SELECT scroll_id, scroll_path, content, project_id FROM scrolls
+            WHERE rowid IN (
+                SELECT MIN(rowid) FROM scrolls
+                WHERE project_id IN (SELECT project_id FROM projects)
+                GROUP BY scroll_id
+            );
+        "#,
+        "DROP TABLE scrolls;",
+        "ALTER TABLE scrolls_new RENAME TO scrolls;",
+        "CREATE INDEX scrolls_project_id ON scrolls(project_id);",
+        r#"
+            CREATE TABLE prompts_new (
+                prompt_id TEXT PRIMARY KEY,
+                project_id TEXT NOT NULL
+                    REFERENCES projects(project_id) ON DELETE CASCADE,
+                content TEXT,
+                output TEXT,
+                prev_prompt_id TEXT
+            );
+        "#,
+        r#"
+            INSERT INTO prompts_new (prompt_id, project_id, content, output, prev_prompt_id)
+            SELECT prompt_id, project_id, content, output, prev_prompt_id FROM prompts
+            WHERE rowid IN (
+                SELECT MIN(rowid) FROM prompts
+                WHERE project_id IN (SELECT project_id FROM projects)
+                GROUP BY prompt_id
+            );
+        "#,
+        "DROP TABLE prompts;",
+        "ALTER TABLE prompts_new RENAME TO prompts;",
+        "CREATE INDEX prompts_project_id ON prompts(project_id);",
+        "CREATE INDEX prompts_prev_prompt_id ON prompts(prev_prompt_id);",
+    ],
+    // 4: Trash of soft-deleted prompts, grouped by deletion
+    &[
+        r#"
+            CREATE TABLE trash_batches (
+                batch_id TEXT PRIMARY KEY,
+                project_id TEXT NOT NULL
+                    REFERENCES projects(project_id) ON DELETE CASCADE,
+                deleted_at TEXT NOT NULL,
+                mode TEXT NOT NULL,
+                spliced_children TEXT NOT NULL DEFAULT '[]'
+            );
+        "#,
+        r#"
+            CREATE TABLE prompt_trash (
+                batch_id TEXT NOT NULL
+                    REFERENCES trash_batches(batch_id) ON DELETE CASCADE,
+                position INTEGER NOT NULL,
+                prompt_id TEXT NOT NULL,
+                project_id TEXT NOT NULL,
+                content TEXT,
+                output TEXT,
+                prev_prompt_id TEXT,
+                PRIMARY KEY (batch_id, prompt_id)
+            );
+        "#,
+    ],
+    // 5: Tags, stars and branch labels. They do not reference the prompts, so
+    // trashed prompts get theirs back when restored.
+    &[
+        r#"
+            CREATE TABLE prompt_tags (
+                project_id TEXT NOT NULL
+                    REFERENCES projects(project_id) ON DELETE CASCADE,
+                prompt_id TEXT NOT NULL,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (prompt_id, tag)
+            );
+        "#,
+        "CREATE INDEX prompt_tags_project_id ON prompt_tags(project_id);",
+        r#"
+            CREATE TABLE prompt_stars (
+                project_id TEXT NOT NULL
+                    REFERENCES projects(project_id) ON DELETE CASCADE,
+                prompt_id TEXT PRIMARY KEY
+            );
+        "#,
+        r#"
+            CREATE TABLE branch_labels (
+                project_id TEXT NOT NULL
+                    REFERENCES projects(project_id) ON DELETE CASCADE,
+                name TEXT NOT NULL,
+                prompt_id TEXT NOT NULL,
+                PRIMARY KEY (project_id, name)
+            );
+        "#,
+    ],
+    // 6: Last active project, branch and prompt, restored on startup
+    &[r#"
+        CREATE TABLE session (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            project_id TEXT,
+            branch TEXT,
+            prompt_id TEXT
+        );
+    "#],
+    // 7: Extra parents of merge prompts, the first parent stays in prev_prompt_id
+    &[r#"
+        CREATE TABLE prompt_parents (
+            project_id TEXT NOT NULL
+                REFERENCES projects(project_id) ON DELETE CASCADE,
+            prompt_id TEXT NOT NULL,
+            parent_id TEXT NOT NULL,
+            PRIMARY KEY (prompt_id, parent_id)
+        );
+    "#],
+];
+
+/// Tables holding the rows set aside by migration 3.
+pub const QUARANTINE_TABLES: &[&str] = &["quarantined_scrolls", "quarantined_prompts"];
+
+/// Whether the database has a table named `table`.
+pub async fn table_exists(conn: &mut SqliteConnection, table: &str) -> Result<bool, AppError> {
+    let exists: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = $1;",
+    )
+    .bind(table)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|error| {
+        let error_msg = format!("Failed to look for table {}: {}", table, error);
+        log_error(&error_msg);
+        AppError::DatabaseError(error_msg)
+    })?;
+    Ok(exists > 0)
+}
 
-        if let Err(error) = sqlx::query(create_projects_table).execute(&pool).await {
-            let error_msg = format!("Failed to create projects table: {}", error);
+/// Counts the rows of each quarantine table, skipping the tables that do not exist.
+///
+/// # Returns:
+/// - `Ok(Vec<(&str, usize)>)` with the tables holding rows and their number of rows.
+pub async fn count_quarantined(
+    conn: &mut SqliteConnection,
+) -> Result<Vec<(&'static str, usize)>, AppError> {
+    let mut counts = vec![];
+    for table in QUARANTINE_TABLES.iter() {
+        if !table_exists(conn, table).await? {
+            continue;
+        }
+        let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {};", table))
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|error| {
+                let error_msg = format!("Failed to count the rows of {}: {}", table, error);
+                log_error(&error_msg);
+                AppError::DatabaseError(error_msg)
+            })?;
+        if rows > 0 {
+            counts.push((*table, rows as usize));
+        }
+    }
+    Ok(counts)
+}
+
+/// Brings the database schema up to date by applying the pending migrations.
+///
+/// Each migration runs in its own transaction together with the bump of
+/// `PRAGMA user_version`, so a failing migration leaves the database untouched.
+///
+/// Migrations run on a dedicated connection before any pool is opened, as other
+/// open connections would keep preparing statements against the old schema.
+pub async fn run_migrations(conn: &mut SqliteConnection) -> Result<(), AppError> {
+    let version: i64 = sqlx::query_scalar("PRAGMA user_version;")
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|error| {
+            let error_msg = format!("Failed to read the schema version: {}", error);
             log_error(&error_msg);
-            return Err(AppError::DatabaseError(error_msg));
+            AppError::DatabaseError(error_msg)
+        })?;
+
+    for (idx, statements) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let target = idx + 1;
+        let mut tx = conn.begin().await.map_err(|error| {
+            let error_msg = format!("Failed to start migration {}: {}", target, error);
+            log_error(&error_msg);
+            AppError::DatabaseError(error_msg)
+        })?;
+
+        for statement in statements.iter() {
+            if let Err(error) = sqlx::query(statement).execute(&mut *tx).await {
+                let error_msg = format!("Failed to apply migration {}: {}", target, error);
+                log_error(&error_msg);
+                return Err(AppError::DatabaseError(error_msg));
+            }
         }
 
-        if let Err(error) = sqlx::query(create_scrolls_table).execute(&pool).await {
-            let error_msg = format!("Failed to create scrolls table: {}", error);
+        let bump = format!("PRAGMA user_version = {};", target);
+        if let Err(error) = sqlx::query(&bump).execute(&mut *tx).await {
+            let error_msg = format!("Failed to apply migration {}: {}", target, error);
             log_error(&error_msg);
             return Err(AppError::DatabaseError(error_msg));
         }
 
-        if let Err(error) = sqlx::query(create_prompts_table).execute(&pool).await {
-            let error_msg = format!("Failed to create prompts table: {}", error);
+        tx.commit().await.map_err(|error| {
+            let error_msg = format!("Failed to commit migration {}: {}", target, error);
             log_error(&error_msg);
-            return Err(AppError::DatabaseError(error_msg));
-        }     
+            AppError::DatabaseError(error_msg)
+        })?;
+        log_info(&format!("Database migrated to version {}.", target));
+    }
+
+    // Say what the upgrade could not keep
+    if (version as usize) < MIGRATIONS.len() {
+        for (table, rows) in count_quarantined(conn).await? {
+            log_error(&format!(
+                "{} row(s) that could not be migrated were moved to {}, run `legatio fsck` for details",
+                rows, table
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Opens the database file at `db_file`, creating it and upgrading its schema as needed.
+///
+/// An existing database is snapshotted before pending migrations are applied,
+/// keeping `retention` snapshots; the migrations are not applied if the snapshot fails.
+pub async fn open_db_pool(db_file: &Path, retention: usize) -> Result<SqlitePool, AppError> {
+    let options = SqliteConnectOptions::new()
+        .filename(db_file)
+        .create_if_missing(true);
+    let existed = db_file.exists();
+    if !existed {
+        log_info(&format!("Creating database {}.", db_file.to_string_lossy()));
     }
 
-    let pool = match SqlitePool::connect(db_url).await {
+    // Create or upgrade the required tables
+    let mut conn: SqliteConnection = options.connect().await.map_err(|error| {
+        let error_msg = format!("Failed to connect to the database: {}", error);
+        log_error(&error_msg);
+        AppError::DatabaseError(error_msg)
+    })?;
+    if existed {
+        snapshot_before_migrations(&mut conn, retention).await?;
+    }
+    run_migrations(&mut conn).await?;
+    let _ = conn.close().await;
+
+    let pool = match SqlitePool::connect_with(options).await {
         Ok(pool) => pool,
         Err(error) => {
             let error_msg = format!("Failed to connect to the database: {}", error);
@@ -95,17 +476,62 @@ pub async fn get_db_pool() -> Result<SqlitePool, AppError> {
     Ok(pool)
 }
 
-pub async fn delete_module(
-    pool: &SqlitePool,
+/// Snapshots the database with reason `pre-migrate-<n>`, `n` being the schema
+/// version it is about to be upgraded to, when migrations are pending.
+async fn snapshot_before_migrations(
+    conn: &mut SqliteConnection,
+    retention: usize,
+) -> Result<(), AppError> {
+    let version: i64 = sqlx::query_scalar("PRAGMA user_version;")
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|error| {
+            let error_msg = format!("Failed to read the schema version: {}", error);
+            log_error(&error_msg);
+            AppError::DatabaseError(error_msg)
+        })?;
+    if version as usize >= MIGRATIONS.len() {
+        return Ok(());
+    }
+
+    let reason = format!("pre-migrate-{}", MIGRATIONS.len());
+    backup_database(&mut *conn, &reason, retention)
+        .await
+        .map_err(|error| {
+            let error_msg = format!(
+                "Failed to back up the database before upgrading it: {}",
+                error
+            );
+            log_error(&error_msg);
+            AppError::DatabaseError(error_msg)
+        })?;
+    Ok(())
+}
+
+/// Opens the database `name` inside the Legatio data directory.
+pub async fn get_db_pool(name: &str, retention: usize) -> Result<SqlitePool, AppError> {
+    let db_file = get_db_file(name).map_err(|err| AppError::FileError(err.to_string()))?;
+    open_db_pool(&db_file, retention).await
+}
+
+pub async fn delete_module<'e, E>(
+    executor: E,
     table: &str,
     column_name: &str,
     column_value: &str,
-) -> Result<(), AppError> {
+) -> Result<(), AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
     // Construct the query dynamically
     let query = format!("DELETE FROM {} WHERE {} = ?", table, column_name);
 
     // Execute the query with the given value as a parameter
-    match sqlx::query(&query).bind(column_value).execute(pool).await {
+    match sqlx::query(&query)
+        .bind(column_value)
+        .execute(executor)
+        .await
+    {
         Ok(_) => Ok(()),
         Err(error) => {
             let error_msg = format!(
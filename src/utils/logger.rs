@@ -1,10 +1,11 @@
-use std::fs::{File, OpenOptions};
+use std::fs::{self, File, OpenOptions};
 use std::io::Write;
+use std::path::PathBuf;
 use std::sync::Mutex;
 
 use anyhow::Result;
 use chrono::Local;
-use log::{Level, Log, Metadata, Record, SetLoggerError};
+use log::{Log, Metadata, Record};
 
 use crate::services::config::get_config_dir;
 
@@ -55,8 +56,25 @@ impl Log for FileLogger {
     }
 }
 
-static LOGGER_READY: std::sync::Once = std::sync::Once::new();
-static mut LOGGER: Option<FileLogger> = None;
+/// Directory of the log files and crash reports, `logs/` in the config directory.
+pub fn log_dir() -> Result<PathBuf> {
+    let log_dir = get_config_dir()?.join("logs");
+    fs::create_dir_all(&log_dir)?;
+    Ok(log_dir)
+}
+
+/// Writes a crash report, such as a panic and its backtrace, to the log directory.
+///
+/// # Returns:
+/// - The path of the report.
+pub fn write_crash_log(report: &str) -> Result<PathBuf> {
+    let timestamp = Local::now().format("crash_%Y-%m-%d_%H-%M-%S.log");
+    let file_path = log_dir()?.join(timestamp.to_string());
+    fs::write(&file_path, report)?;
+    Ok(file_path)
+}
+
+static LOGGER: std::sync::OnceLock<FileLogger> = std::sync::OnceLock::new();
 
 /// Initialize the logger globally (only in development).
 pub async fn initialize_logger() -> Result<()> {
@@ -68,15 +86,13 @@ pub async fn initialize_logger() -> Result<()> {
         let file_path = config_path.join(timestamp.to_string());
 
         // Initialize the FileLogger globally.
-        unsafe {
-            LOGGER_READY.call_once(|| {
-                let logger = FileLogger::new(file_path.to_str().unwrap().to_string()).unwrap();
-                LOGGER = Some(logger);
-
+        if LOGGER.get().is_none() {
+            let logger = FileLogger::new(file_path.to_str().unwrap().to_string())?;
+            if LOGGER.set(logger).is_ok() {
                 // Set the logger for the global logging facade.
-                let _ = log::set_logger(LOGGER.as_ref().unwrap());
+                let _ = log::set_logger(LOGGER.get().unwrap());
                 log::set_max_level(log::LevelFilter::Trace);
-            });
+            }
         }
     }
     Ok(())
@@ -1,3 +1,4 @@
+pub mod backup;
 pub mod db_utils;
 pub mod error;
 pub mod logger;
@@ -1,10 +1,27 @@
+use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
-#[derive(Clone, FromRow, Debug)]
+/// Struct representing a Project.
+///
+/// A `Project` is rooted at a directory. The optional display name, description,
+/// color and icon only change how the project is presented.
+#[derive(Clone, Default, FromRow, Debug, Serialize, Deserialize)]
 pub struct Project {
     pub project_id: String,
     pub project_path: String,
+    #[sqlx(default)]
+    #[serde(default)]
+    pub display_name: Option<String>, // Name shown instead of the directory name
+    #[sqlx(default)]
+    #[serde(default)]
+    pub description: Option<String>,
+    #[sqlx(default)]
+    #[serde(default)]
+    pub color: Option<String>, // Named color (e.g. "cyan") or hex value (e.g. "#ff8800")
+    #[sqlx(default)]
+    #[serde(default)]
+    pub icon: Option<String>,
 }
 
 impl Project {
@@ -12,6 +29,15 @@ impl Project {
         Project {
             project_id: Uuid::new_v4().to_string(),
             project_path: path.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// The display name if set, otherwise the last component of the project path.
+    pub fn name(&self) -> &str {
+        match self.display_name.as_deref() {
+            Some(name) if !name.is_empty() => name,
+            _ => self.project_path.split('/').next_back().unwrap_or(""),
         }
     }
 }
@@ -20,7 +46,7 @@ impl Project {
 ///
 /// A `Scroll` is associated with a project, and it stores a unique ID, its file path,
 /// the file's content, and the project ID it belongs to.
-#[derive(Clone, Debug, FromRow)]
+#[derive(Clone, Debug, FromRow, Serialize, Deserialize)]
 pub struct Scroll {
     pub scroll_id: String,   // Unique identifier for the Scroll
     pub scroll_path: String, // The file path associated with the Scroll
@@ -39,7 +65,7 @@ impl Scroll {
     }
 }
 
-#[derive(Clone, FromRow, Debug)]
+#[derive(Clone, FromRow, Debug, Serialize, Deserialize)]
 pub struct Prompt {
     pub prompt_id: String,
     pub project_id: String,
@@ -59,3 +85,25 @@ impl Prompt {
         }
     }
 }
+
+/// Outcome of storing a prompt or scroll that may already exist.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StoreResult {
+    /// A new row was inserted under this id.
+    Inserted(String),
+    /// An identical row already existed under this id; nothing was inserted.
+    Reused(String),
+}
+
+impl StoreResult {
+    /// The id of the row holding the stored value.
+    pub fn id(&self) -> &str {
+        match self {
+            StoreResult::Inserted(id) | StoreResult::Reused(id) => id,
+        }
+    }
+
+    pub fn is_reused(&self) -> bool {
+        matches!(self, StoreResult::Reused(_))
+    }
+}
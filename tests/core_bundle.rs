@@ -0,0 +1,306 @@
+#[cfg(test)]
+mod tests {
+    use legatio::{
+        core::{
+            bundle::{build_bundle, import_bundle, read_bundle, write_bundle},
+            merge::store_merge,
+            project::{get_projects, store_project},
+            prompt::{get_prompts, store_prompt},
+            scroll::{get_scrolls, store_scroll},
+            tag::{get_marks, set_prompt_marks, PromptMarks},
+        },
+        utils::structs::{Project, Prompt, Scroll},
+    };
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::SqlitePool;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create database connection pool");
+
+        sqlx::query(
+            "CREATE TABLE projects (
+                project_id TEXT PRIMARY KEY,
+                project_path TEXT,
+                display_name TEXT,
+                description TEXT,
+                color TEXT,
+                icon TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE TABLE scrolls (
+                scroll_id TEXT PRIMARY KEY,
+                scroll_path TEXT,
+                content TEXT,
+                project_id TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE TABLE prompts (
+                prompt_id TEXT PRIMARY KEY,
+                project_id TEXT,
+                prev_prompt_id TEXT,
+                content TEXT,
+                output TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE TABLE prompt_tags (
+                project_id TEXT NOT NULL,
+                prompt_id TEXT NOT NULL,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (prompt_id, tag)
+            );
+            CREATE TABLE prompt_stars (
+                project_id TEXT NOT NULL,
+                prompt_id TEXT PRIMARY KEY
+            );
+            CREATE TABLE branch_labels (
+                project_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                prompt_id TEXT NOT NULL,
+                PRIMARY KEY (project_id, name)
+            );
+            CREATE TABLE prompt_parents (
+                project_id TEXT NOT NULL,
+                prompt_id TEXT NOT NULL,
+                parent_id TEXT NOT NULL,
+                PRIMARY KEY (prompt_id, parent_id)
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        pool
+    }
+
+    async fn seed_project(pool: &SqlitePool) -> Project {
+        let project = Project {
+            project_id: "project_1".to_string(),
+            project_path: "/home/alice/repo".to_string(),
+            ..Default::default()
+        };
+        store_project(pool, &project).await.unwrap();
+
+        store_scroll(
+            pool,
+            &Scroll {
+                scroll_id: "scroll_1".to_string(),
+                scroll_path: "/home/alice/repo/src/main.rs".to_string(),
+                content: "fn main() {}".to_string(),
+                project_id: "project_1".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let root = Prompt {
+            prompt_id: "prompt_1".to_string(),
+            project_id: "project_1".to_string(),
+            content: "Question".to_string(),
+            output: "Answer".to_string(),
+            prev_prompt_id: "project_1".to_string(),
+        };
+        let child = Prompt {
+            prompt_id: "prompt_2".to_string(),
+            project_id: "project_1".to_string(),
+            content: "Follow up".to_string(),
+            output: "More".to_string(),
+            prev_prompt_id: "prompt_1".to_string(),
+        };
+        store_prompt(pool, &root).await.unwrap();
+        store_prompt(pool, &child).await.unwrap();
+
+        project
+    }
+
+    #[tokio::test]
+    async fn test_build_bundle_uses_relative_paths() {
+        let pool = create_test_pool().await;
+        let project = seed_project(&pool).await;
+
+        let bundle = build_bundle(&pool, &project).await.unwrap();
+
+        assert_eq!(bundle.project.project_id, "project_1");
+        assert_eq!(bundle.prompts.len(), 2);
+        assert_eq!(bundle.scrolls.len(), 1);
+        assert_eq!(bundle.scrolls[0].scroll_path, "src/main.rs");
+    }
+
+    #[tokio::test]
+    async fn test_bundle_round_trip_to_new_machine() {
+        let source = create_test_pool().await;
+        let project = seed_project(&source).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("repo.legatio");
+        let bundle = build_bundle(&source, &project).await.unwrap();
+        write_bundle(&bundle, &file_path).unwrap();
+
+        let target = create_test_pool().await;
+        let bundle = read_bundle(&file_path).unwrap();
+        let report = import_bundle(&target, &bundle, "/home/bob/code/repo")
+            .await
+            .unwrap();
+
+        assert!(report.new_project);
+        assert_eq!(report.prompts_added, 2);
+        assert_eq!(report.scrolls_added, 1);
+
+        let projects = get_projects(&target).await.unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].project_path, "/home/bob/code/repo");
+
+        let scrolls = get_scrolls(&target, "project_1").await.unwrap();
+        assert_eq!(scrolls[0].scroll_path, "src/main.rs");
+
+        let prompts = get_prompts(&target, "project_1").await.unwrap();
+        let child = prompts.iter().find(|p| p.prompt_id == "prompt_2").unwrap();
+        assert_eq!(child.prev_prompt_id, "prompt_1");
+    }
+
+    #[tokio::test]
+    async fn test_import_bundle_skips_duplicates() {
+        let pool = create_test_pool().await;
+        let project = seed_project(&pool).await;
+        let bundle = build_bundle(&pool, &project).await.unwrap();
+
+        let report = import_bundle(&pool, &bundle, "/home/alice/moved")
+            .await
+            .unwrap();
+
+        assert!(!report.new_project);
+        assert_eq!(report.prompts_added, 0);
+        assert_eq!(report.prompts_skipped, 2);
+        assert_eq!(report.scrolls_skipped, 1);
+        assert_eq!(get_prompts(&pool, "project_1").await.unwrap().len(), 2);
+
+        let projects = get_projects(&pool).await.unwrap();
+        assert_eq!(projects[0].project_path, "/home/alice/moved");
+    }
+
+    #[tokio::test]
+    async fn test_import_bundle_rejects_taken_path() {
+        let pool = create_test_pool().await;
+        let project = seed_project(&pool).await;
+        let mut bundle = build_bundle(&pool, &project).await.unwrap();
+        bundle.project.project_id = "project_2".to_string();
+
+        let result = import_bundle(&pool, &bundle, "/home/alice/repo").await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bundle_carries_marks_to_reused_prompts() {
+        let source = create_test_pool().await;
+        let project = seed_project(&source).await;
+        let prompts = get_prompts(&source, "project_1").await.unwrap();
+        let child = prompts.iter().find(|p| p.prompt_id == "prompt_2").unwrap();
+        set_prompt_marks(
+            &source,
+            child,
+            &PromptMarks {
+                tags: vec!["review".to_string()],
+                starred: true,
+                branches: vec!["main".to_string()],
+            },
+        )
+        .await
+        .unwrap();
+        let merge = Prompt {
+            prompt_id: "prompt_3".to_string(),
+            project_id: "project_1".to_string(),
+            content: "Merge".to_string(),
+            output: "Merged".to_string(),
+            prev_prompt_id: "prompt_2".to_string(),
+        };
+        store_merge(&source, &merge, "prompt_1").await.unwrap();
+        let bundle = build_bundle(&source, &project).await.unwrap();
+        assert_eq!(bundle.parents.len(), 1);
+        assert_eq!(bundle.tags.len(), 1);
+
+        // The target already holds the same conversation under other ids
+        let target = create_test_pool().await;
+        store_project(
+            &target,
+            &Project {
+                project_id: "project_1".to_string(),
+                project_path: "/home/bob/repo".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        for (id, prev, content, output) in [
+            ("other_1", "project_1", "Question", "Answer"),
+            ("other_2", "other_1", "Follow up", "More"),
+        ] {
+            store_prompt(
+                &target,
+                &Prompt {
+                    prompt_id: id.to_string(),
+                    project_id: "project_1".to_string(),
+                    content: content.to_string(),
+                    output: output.to_string(),
+                    prev_prompt_id: prev.to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let report = import_bundle(&target, &bundle, "/home/bob/repo")
+            .await
+            .unwrap();
+        assert_eq!(report.prompts_added, 1);
+        assert_eq!(report.prompts_skipped, 2);
+
+        let prompts = get_prompts(&target, "project_1").await.unwrap();
+        let merge = prompts.iter().find(|p| p.prompt_id == "prompt_3").unwrap();
+        assert_eq!(merge.prev_prompt_id, "other_2");
+        let marks = get_marks(&target, "project_1").await.unwrap();
+        assert_eq!(marks.merged["prompt_3"], vec!["other_1".to_string()]);
+        assert_eq!(marks.tags["other_2"], vec!["review".to_string()]);
+        assert!(marks.starred.contains("other_2"));
+        assert_eq!(marks.branches.len(), 1);
+        assert_eq!(marks.branches[0].prompt_id, "other_2");
+    }
+
+    #[test]
+    fn test_read_bundle_without_marks() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("old.legatio");
+        std::fs::write(
+            &file_path,
+            r#"{
+                "version": 1,
+                "created_at": "2024-01-01T00:00:00+00:00",
+                "project": {"project_id": "project_1", "project_path": "/repo"},
+                "scrolls": [],
+                "prompts": []
+            }"#,
+        )
+        .unwrap();
+
+        let bundle = read_bundle(&file_path).unwrap();
+        assert!(bundle.parents.is_empty());
+        assert!(bundle.branches.is_empty());
+    }
+}
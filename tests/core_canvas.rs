@@ -17,6 +17,7 @@ mod tests {
         let project = Project {
             project_id: "test_project".to_string(),
             project_path: project_path.clone(),
+            ..Default::default()
         };
 
         let prompts = vec![Prompt {
@@ -49,6 +50,7 @@ mod tests {
         let project = Project {
             project_id: "test_project".to_string(),
             project_path: project_path.clone(),
+            ..Default::default()
         };
 
         // Create the `legatio.md` with mock content
@@ -75,6 +77,7 @@ mod tests {
         let project = Project {
             project_id: "test_project".to_string(),
             project_path: project_path.clone(),
+            ..Default::default()
         };
 
         // Create the `legatio.md` without the marker
@@ -101,6 +104,7 @@ mod tests {
         let project = Project {
             project_id: "test_project".to_string(),
             project_path: project_path.clone(),
+            ..Default::default()
         };
 
         // Act: Call the target function with empty inputs
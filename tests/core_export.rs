@@ -0,0 +1,137 @@
+#[cfg(test)]
+mod tests {
+    use legatio::{
+        core::export::{
+            export_html, export_json, export_markdown, export_nodes, write_export, ExportFormat,
+            ExportScope,
+        },
+        utils::structs::{Project, Prompt, Scroll},
+    };
+    use std::fs;
+
+    fn sample_project(path: &str) -> (Project, Vec<Prompt>, Vec<Scroll>) {
+        let project = Project {
+            project_id: "project_1".to_string(),
+            project_path: path.to_string(),
+            ..Default::default()
+        };
+        let root = Prompt {
+            prompt_id: "1".to_string(),
+            project_id: "project_1".to_string(),
+            content: "Root question".to_string(),
+            output: "Root answer".to_string(),
+            prev_prompt_id: "project_1".to_string(),
+        };
+        let left = Prompt {
+            prompt_id: "2".to_string(),
+            project_id: "project_1".to_string(),
+            content: "Left <question>".to_string(),
+            output: "Left answer".to_string(),
+            prev_prompt_id: "1".to_string(),
+        };
+        let right = Prompt {
+            prompt_id: "3".to_string(),
+            project_id: "project_1".to_string(),
+            content: "Right question".to_string(),
+            output: "Right answer".to_string(),
+            prev_prompt_id: "1".to_string(),
+        };
+        let scroll = Scroll {
+            scroll_id: "scroll_1".to_string(),
+            scroll_path: format!("{}/notes.md", path),
+            content: "Scroll content".to_string(),
+            project_id: "project_1".to_string(),
+        };
+        (project, vec![root, left, right], vec![scroll])
+    }
+
+    #[test]
+    fn test_export_nodes_branch_is_linear() {
+        let (_, prompts, _) = sample_project("/tmp/export_project");
+
+        let nodes = export_nodes(&prompts, ExportScope::Branch(&prompts[2]));
+
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].prompt.prompt_id, "1");
+        assert_eq!(nodes[0].children.len(), 1);
+        assert_eq!(nodes[0].children[0].prompt.prompt_id, "3");
+        assert!(nodes[0].children[0].children.is_empty());
+    }
+
+    #[test]
+    fn test_export_nodes_tree() {
+        let (_, prompts, _) = sample_project("/tmp/export_project");
+
+        let nodes = export_nodes(&prompts, ExportScope::Tree);
+
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].children.len(), 2);
+    }
+
+    #[test]
+    fn test_export_markdown() {
+        let (project, prompts, scrolls) = sample_project("/tmp/export_project");
+
+        let branch = export_markdown(
+            &project,
+            &prompts,
+            &scrolls,
+            ExportScope::Branch(&prompts[1]),
+        );
+        assert!(branch.starts_with("# export_project"));
+        assert!(branch.contains("## 1 Prompt\n\nRoot question"));
+        assert!(branch.contains("### 1.1 Prompt\n\nLeft <question>"));
+        assert!(!branch.contains("Right question"));
+        assert!(!branch.contains("Scroll content"));
+
+        let full = export_markdown(&project, &prompts, &scrolls, ExportScope::Project);
+        assert!(full.contains("### 1.2 Prompt\n\nRight question"));
+        assert!(full.contains("Scroll content"));
+    }
+
+    #[test]
+    fn test_export_html_escapes_and_nests() {
+        let (project, prompts, scrolls) = sample_project("/tmp/export_project");
+
+        let html = export_html(&project, &prompts, &scrolls, ExportScope::Tree);
+
+        assert!(html.contains("Left &lt;question&gt;"));
+        assert!(!html.contains("Left <question>"));
+        assert_eq!(html.matches("<details open>").count(), 3);
+        assert!(html.ends_with("</html>\n"));
+    }
+
+    #[test]
+    fn test_export_json() {
+        let (project, prompts, scrolls) = sample_project("/tmp/export_project");
+
+        let json = export_json(&project, &prompts, &scrolls, ExportScope::Project).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["scope"], "project");
+        assert_eq!(value["project"]["project_id"], "project_1");
+        assert_eq!(value["scrolls"].as_array().unwrap().len(), 1);
+        assert_eq!(value["prompts"][0]["prompt_id"], "1");
+        assert_eq!(value["prompts"][0]["children"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_write_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let (project, prompts, scrolls) = sample_project(&dir.path().to_string_lossy());
+
+        let path = write_export(
+            &project,
+            &prompts,
+            &scrolls,
+            ExportScope::Tree,
+            ExportFormat::Markdown,
+        )
+        .unwrap();
+
+        assert!(path.starts_with(dir.path()));
+        assert_eq!(path.extension().unwrap(), "md");
+        let content = fs::read_to_string(path).unwrap();
+        assert!(content.contains("Right answer"));
+    }
+}
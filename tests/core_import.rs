@@ -0,0 +1,244 @@
+#[cfg(test)]
+mod tests {
+    use legatio::{
+        core::{
+            import::{import_transcript, parse_transcript, transcript_to_prompts, Role},
+            prompt::get_prompts,
+        },
+        utils::structs::Project,
+    };
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::SqlitePool;
+    use std::fs;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create database connection pool");
+
+        sqlx::query(
+            "CREATE TABLE prompts (
+                prompt_id TEXT PRIMARY KEY,
+                project_id TEXT,
+                prev_prompt_id TEXT,
+                content TEXT,
+                output TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        pool
+    }
+
+    #[test]
+    fn test_parse_linear_transcript() {
+        let json = r#"[
+            {"role": "system", "content": "Be brief"},
+            {"role": "user", "content": "Hello"},
+            {"role": "assistant", "content": [{"type": "text", "text": "Hi!"}]},
+            {"role": "user", "content": "Bye"},
+            {"role": "assistant", "content": "Goodbye"}
+        ]"#;
+
+        let messages = parse_transcript(json).unwrap();
+        assert_eq!(messages.len(), 5);
+        assert_eq!(messages[0].role, Role::Other);
+        assert_eq!(messages[2].content, "Hi!");
+        assert_eq!(messages[2].parent.as_deref(), Some(messages[1].id.as_str()));
+
+        let prompts = transcript_to_prompts(&messages, "project_1", "project_1");
+        assert_eq!(prompts.len(), 2);
+        assert_eq!(prompts[0].content, "Hello");
+        assert_eq!(prompts[0].output, "Hi!");
+        assert_eq!(prompts[0].prev_prompt_id, "project_1");
+        assert_eq!(prompts[1].content, "Bye");
+        assert_eq!(prompts[1].output, "Goodbye");
+        assert_eq!(prompts[1].prev_prompt_id, prompts[0].prompt_id);
+    }
+
+    #[test]
+    fn test_parse_linear_transcript_with_ids() {
+        // Logs of the OpenAI API give every message an id but no parent
+        let json = r#"[
+            {"id": "msg_1", "role": "user", "content": "Hello"},
+            {"id": "msg_2", "role": "assistant", "content": "Hi!"},
+            {"id": "msg_3", "role": "user", "content": "Bye"},
+            {"id": "msg_4", "role": "assistant", "content": "Goodbye"}
+        ]"#;
+
+        let messages = parse_transcript(json).unwrap();
+        assert_eq!(messages[0].parent, None);
+        assert_eq!(messages[3].parent.as_deref(), Some("msg_3"));
+
+        let prompts = transcript_to_prompts(&messages, "project_1", "project_1");
+        assert_eq!(prompts.len(), 2);
+        assert_eq!(prompts[0].output, "Hi!");
+        assert_eq!(prompts[0].prev_prompt_id, "project_1");
+        assert_eq!(prompts[1].content, "Bye");
+        assert_eq!(prompts[1].output, "Goodbye");
+        assert_eq!(prompts[1].prev_prompt_id, prompts[0].prompt_id);
+    }
+
+    #[test]
+    fn test_parse_tree_transcript() {
+        let json = r#"{"messages": [
+            {"id": "a", "parent": null, "role": "user", "content": "Question"},
+            {"id": "b", "parent": "a", "role": "assistant", "content": "Answer 1"},
+            {"id": "c", "parent": "a", "role": "assistant", "content": "Answer 2"},
+            {"id": "d", "parent": "c", "role": "user", "content": "Follow up"}
+        ]}"#;
+
+        let messages = parse_transcript(json).unwrap();
+        let prompts = transcript_to_prompts(&messages, "project_1", "project_1");
+
+        assert_eq!(prompts.len(), 3);
+        let first = prompts.iter().find(|p| p.output == "Answer 1").unwrap();
+        let second = prompts.iter().find(|p| p.output == "Answer 2").unwrap();
+        let follow = prompts.iter().find(|p| p.content == "Follow up").unwrap();
+        assert_eq!(first.prev_prompt_id, "project_1");
+        assert_eq!(second.prev_prompt_id, "project_1");
+        assert_eq!(follow.prev_prompt_id, second.prompt_id);
+        assert_eq!(follow.output, "");
+    }
+
+    #[test]
+    fn test_parse_mapping_transcript() {
+        let json = r#"{"title": "Chat", "mapping": {
+            "root": {"id": "root", "message": null, "parent": null, "children": ["u1"]},
+            "u1": {"id": "u1", "parent": "root", "children": ["a1"],
+                   "message": {"author": {"role": "user"}, "content": {"parts": ["Ping"]}}},
+            "a1": {"id": "a1", "parent": "u1", "children": [],
+                   "message": {"author": {"role": "assistant"}, "content": {"parts": ["Pong"]}}}
+        }}"#;
+
+        let messages = parse_transcript(json).unwrap();
+        let prompts = transcript_to_prompts(&messages, "project_1", "parent_prompt");
+
+        assert_eq!(prompts.len(), 1);
+        assert_eq!(prompts[0].content, "Ping");
+        assert_eq!(prompts[0].output, "Pong");
+        assert_eq!(prompts[0].prev_prompt_id, "parent_prompt");
+    }
+
+    #[test]
+    fn test_parse_claude_transcript() {
+        let json = r#"{
+            "uuid": "c0ffee00-0000-4000-8000-000000000001",
+            "name": "Lexer",
+            "created_at": "2024-05-01T10:00:00.000000Z",
+            "chat_messages": [
+                {
+                    "uuid": "m1",
+                    "text": "Why does the lexer loop?",
+                    "content": [{"type": "text", "text": "Why does the lexer loop?"}],
+                    "sender": "human",
+                    "index": 0,
+                    "created_at": "2024-05-01T10:00:00.000000Z",
+                    "attachments": [],
+                    "files": [],
+                    "parent_message_uuid": "00000000-0000-4000-8000-000000000000"
+                },
+                {
+                    "uuid": "m2",
+                    "text": "It never consumes the newline.",
+                    "content": [{"type": "text", "text": "It never consumes the newline."}],
+                    "sender": "assistant",
+                    "index": 1,
+                    "created_at": "2024-05-01T10:00:05.000000Z",
+                    "attachments": [],
+                    "files": [],
+                    "parent_message_uuid": "m1"
+                },
+                {
+                    "uuid": "m3",
+                    "text": "Show the fix",
+                    "content": [{"type": "text", "text": "Show the fix"}],
+                    "sender": "human",
+                    "index": 2,
+                    "created_at": "2024-05-01T10:01:00.000000Z",
+                    "attachments": [],
+                    "files": [],
+                    "parent_message_uuid": "m2"
+                },
+                {
+                    "uuid": "m4",
+                    "text": "Advance past it.",
+                    "content": [{"type": "text", "text": "Advance past it."}],
+                    "sender": "assistant",
+                    "index": 3,
+                    "created_at": "2024-05-01T10:01:04.000000Z",
+                    "attachments": [],
+                    "files": [],
+                    "parent_message_uuid": "m3"
+                }
+            ]
+        }"#;
+
+        let messages = parse_transcript(json).unwrap();
+        assert_eq!(
+            messages.iter().map(|m| m.role).collect::<Vec<_>>(),
+            vec![Role::User, Role::Assistant, Role::User, Role::Assistant]
+        );
+
+        let prompts = transcript_to_prompts(&messages, "project_1", "project_1");
+        assert_eq!(prompts.len(), 2);
+        assert_eq!(prompts[0].content, "Why does the lexer loop?");
+        assert_eq!(prompts[0].output, "It never consumes the newline.");
+        assert_eq!(prompts[0].prev_prompt_id, "project_1");
+        assert_eq!(prompts[1].content, "Show the fix");
+        assert_eq!(prompts[1].prev_prompt_id, prompts[0].prompt_id);
+    }
+
+    #[test]
+    fn test_parse_invalid_transcript() {
+        assert!(parse_transcript("not json").is_err());
+        assert!(parse_transcript(r#"{"foo": 1}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_import_transcript() {
+        let pool = create_test_pool().await;
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("chat.json");
+        fs::write(
+            &file_path,
+            r#"[{"role": "user", "content": "Q1"}, {"role": "assistant", "content": "A1"},
+                {"role": "user", "content": "Q2"}, {"role": "assistant", "content": "A2"}]"#,
+        )
+        .unwrap();
+
+        let project = Project {
+            project_id: "project_1".to_string(),
+            project_path: dir.path().to_string_lossy().to_string(),
+            ..Default::default()
+        };
+
+        let imported = import_transcript(&pool, &project, &file_path.to_string_lossy(), None)
+            .await
+            .unwrap();
+        assert_eq!(imported.len(), 2);
+
+        let stored = get_prompts(&pool, "project_1").await.unwrap();
+        assert_eq!(stored.len(), 2);
+        let root = stored.iter().find(|p| p.content == "Q1").unwrap();
+        let leaf = stored.iter().find(|p| p.content == "Q2").unwrap();
+        assert_eq!(root.prev_prompt_id, "project_1");
+        assert_eq!(leaf.prev_prompt_id, root.prompt_id);
+
+        // A transcript without user or assistant messages is refused
+        fs::write(
+            &file_path,
+            r#"[{"speaker": "me", "content": "Q1"}, {"speaker": "it", "content": "A1"}]"#,
+        )
+        .unwrap();
+        assert!(
+            import_transcript(&pool, &project, &file_path.to_string_lossy(), None)
+                .await
+                .is_err()
+        );
+        assert_eq!(get_prompts(&pool, "project_1").await.unwrap().len(), 2);
+    }
+}
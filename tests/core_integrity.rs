@@ -0,0 +1,279 @@
+#[cfg(test)]
+mod tests {
+    use legatio::{
+        core::{
+            integrity::{check_integrity, repair_integrity, IntegrityIssue, RepairSummary},
+            prompt::get_prompts,
+        },
+        services::cli::{parse_args, Args, Command},
+    };
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::SqlitePool;
+    use std::fs;
+    use std::path::PathBuf;
+
+    async fn create_test_pool(project_path: &str) -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create database connection pool");
+
+        // No keys nor foreign keys, as in databases created by older releases
+        sqlx::query(
+            "CREATE TABLE projects (project_id TEXT, project_path TEXT);
+             CREATE TABLE scrolls (
+                scroll_id TEXT, scroll_path TEXT, content TEXT, project_id TEXT
+             );
+             CREATE TABLE prompts (
+                prompt_id TEXT, project_id TEXT, content TEXT, output TEXT, prev_prompt_id TEXT
+             );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query("INSERT INTO projects VALUES ('project', $1);")
+            .bind(project_path)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        pool
+    }
+
+    async fn insert_prompt(pool: &SqlitePool, id: &str, project: &str, prev: &str) {
+        sqlx::query("INSERT INTO prompts VALUES ($1, $2, 'Q', 'A', $3);")
+            .bind(id)
+            .bind(project)
+            .bind(prev)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn insert_scroll(pool: &SqlitePool, id: &str, project: &str, path: &str) {
+        sqlx::query("INSERT INTO scrolls VALUES ($1, $2, '', $3);")
+            .bind(id)
+            .bind(path)
+            .bind(project)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_check_healthy_database() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("notes.md"), "notes").unwrap();
+        let pool = create_test_pool(&dir.path().to_string_lossy()).await;
+
+        insert_prompt(&pool, "1", "project", "project").await;
+        insert_prompt(&pool, "2", "project", "1").await;
+        insert_scroll(&pool, "scroll_1", "project", "notes.md").await;
+
+        assert!(check_integrity(&pool).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_check_quarantined_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = create_test_pool(&dir.path().to_string_lossy()).await;
+        sqlx::query(
+            "CREATE TABLE quarantined_prompts (
+                prompt_id TEXT, project_id TEXT, content TEXT, output TEXT,
+                prev_prompt_id TEXT, reason TEXT NOT NULL
+             );
+             INSERT INTO quarantined_prompts
+                VALUES ('1', 'deleted', 'Q', 'A', 'deleted', 'deleted project');",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // Reported, but left for the user to recover
+        let issues = check_integrity(&pool).await.unwrap();
+        let quarantined = IntegrityIssue::QuarantinedRows {
+            table: String::from("quarantined_prompts"),
+            count: 1,
+        };
+        assert_eq!(issues, vec![quarantined.clone()]);
+        assert!(!quarantined.is_repairable());
+    }
+
+    #[tokio::test]
+    async fn test_check_and_repair_issues() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = create_test_pool(&dir.path().to_string_lossy()).await;
+
+        insert_prompt(&pool, "1", "project", "project").await;
+        insert_prompt(&pool, "2", "project", "gone").await;
+        insert_prompt(&pool, "3", "project", "4").await;
+        insert_prompt(&pool, "4", "project", "3").await;
+        insert_prompt(&pool, "5", "project", "4").await;
+        insert_prompt(&pool, "1", "project", "project").await;
+        insert_prompt(&pool, "6", "deleted", "deleted").await;
+        insert_scroll(&pool, "scroll_1", "project", "missing.md").await;
+        insert_scroll(&pool, "scroll_2", "deleted", "other.md").await;
+
+        let issues = check_integrity(&pool).await.unwrap();
+        assert!(issues.contains(&IntegrityIssue::OrphanPrompt {
+            prompt_id: "2".to_string(),
+            project_id: "project".to_string(),
+            missing_parent: "gone".to_string(),
+        }));
+        assert!(issues.contains(&IntegrityIssue::PromptCycle {
+            project_id: "project".to_string(),
+            prompt_ids: vec!["3".to_string(), "4".to_string()],
+        }));
+        assert!(issues.contains(&IntegrityIssue::DuplicatePromptId {
+            prompt_id: "1".to_string(),
+            count: 2,
+        }));
+        assert!(issues.contains(&IntegrityIssue::PromptOfDeletedProject {
+            prompt_id: "6".to_string(),
+            project_id: "deleted".to_string(),
+        }));
+        assert!(issues.contains(&IntegrityIssue::ScrollOfDeletedProject {
+            scroll_id: "scroll_2".to_string(),
+            project_id: "deleted".to_string(),
+        }));
+        assert!(issues.contains(&IntegrityIssue::MissingScrollFile {
+            scroll_id: "scroll_1".to_string(),
+            project_id: "project".to_string(),
+            path: "missing.md".to_string(),
+        }));
+        assert_eq!(issues.len(), 6);
+
+        let summary = repair_integrity(&pool, &issues).await.unwrap();
+        assert_eq!(
+            summary,
+            RepairSummary {
+                rerooted: 2,
+                renamed: 1,
+                deleted: 2,
+            }
+        );
+
+        let prompts = get_prompts(&pool, "project").await.unwrap();
+        assert_eq!(prompts.len(), 6);
+        let parent = |id: &str| {
+            prompts
+                .iter()
+                .find(|p| p.prompt_id == id)
+                .unwrap()
+                .prev_prompt_id
+                .clone()
+        };
+        assert_eq!(parent("2"), "project");
+        assert_eq!(parent("3"), "project");
+        assert_eq!(parent("4"), "3");
+        assert!(get_prompts(&pool, "deleted").await.unwrap().is_empty());
+
+        // Only the missing scroll file is left
+        let issues = check_integrity(&pool).await.unwrap();
+        assert_eq!(issues.len(), 1);
+        assert!(!issues[0].is_repairable());
+    }
+
+    #[tokio::test]
+    async fn test_check_and_repair_dangling_marks() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = create_test_pool(&dir.path().to_string_lossy()).await;
+        sqlx::query(
+            "CREATE TABLE prompt_trash (batch_id TEXT, prompt_id TEXT);
+             CREATE TABLE prompt_tags (project_id TEXT, prompt_id TEXT, tag TEXT);
+             CREATE TABLE prompt_stars (project_id TEXT, prompt_id TEXT);
+             CREATE TABLE prompt_parents (project_id TEXT, prompt_id TEXT, parent_id TEXT);
+             INSERT INTO prompt_trash VALUES ('batch', 'trashed');
+             INSERT INTO prompt_tags VALUES
+                ('project', '1', 'good'), ('project', 'trashed', 'old'), ('project', 'gone', 'lost');
+             INSERT INTO prompt_stars VALUES ('project', 'gone');
+             INSERT INTO prompt_parents VALUES ('project', '1', 'purged');",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        insert_prompt(&pool, "1", "project", "project").await;
+
+        // Marks of trashed prompts are kept for their restore
+        let dangling = |table: &str, prompt_id: &str| IntegrityIssue::DanglingMark {
+            table: table.to_string(),
+            prompt_id: prompt_id.to_string(),
+        };
+        let issues = check_integrity(&pool).await.unwrap();
+        assert_eq!(
+            issues,
+            vec![
+                dangling("prompt_tags", "gone"),
+                dangling("prompt_stars", "gone"),
+                dangling("prompt_parents", "purged"),
+            ]
+        );
+        assert!(issues.iter().all(IntegrityIssue::is_repairable));
+
+        let summary = repair_integrity(&pool, &issues).await.unwrap();
+        assert_eq!(summary.deleted, 3);
+        assert!(check_integrity(&pool).await.unwrap().is_empty());
+        let tags: Vec<String> = sqlx::query_scalar("SELECT prompt_id FROM prompt_tags;")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(tags, vec!["1", "trashed"]);
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let command = |a: &[&str]| parse_args(args(a)).map(|parsed| parsed.command);
+
+        assert_eq!(command(&[]).unwrap(), Command::Tui);
+        assert_eq!(command(&["fsck"]).unwrap(), Command::Fsck { repair: false });
+        assert_eq!(
+            command(&["fsck", "--repair"]).unwrap(),
+            Command::Fsck { repair: true }
+        );
+        assert_eq!(
+            command(&["restore"]).unwrap(),
+            Command::Restore { snapshot: None }
+        );
+        assert_eq!(
+            command(&["restore", "2"]).unwrap(),
+            Command::Restore { snapshot: Some(2) }
+        );
+        assert!(command(&["restore", "0"]).is_err());
+        assert!(command(&["restore", "2", "3"]).is_err());
+        assert_eq!(command(&["--help"]).unwrap(), Command::Help);
+        assert!(command(&["fsck", "--force"]).is_err());
+        assert!(command(&["frobnicate"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_options() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            parse_args(args(&[
+                "--data-dir",
+                "/tmp/legatio",
+                "fsck",
+                "--db",
+                "work"
+            ]))
+            .unwrap(),
+            Args {
+                data_dir: Some(PathBuf::from("/tmp/legatio")),
+                database: Some(String::from("work")),
+                command: Command::Fsck { repair: false },
+            }
+        );
+        assert_eq!(
+            parse_args(args(&[])).unwrap(),
+            Args {
+                data_dir: None,
+                database: None,
+                command: Command::Tui,
+            }
+        );
+        assert!(parse_args(args(&["--db"])).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests {
+    use legatio::{
+        core::{
+            merge::{branch_tips, format_branch, merge_request, store_merge},
+            prompt::{get_prompts, prompt_chain},
+            tag::{get_marks, BranchLabel, ProjectMarks},
+        },
+        utils::structs::Prompt,
+    };
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::SqlitePool;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create database connection pool");
+
+        sqlx::query(
+            "CREATE TABLE prompts (
+                prompt_id TEXT PRIMARY KEY,
+                project_id TEXT,
+                prev_prompt_id TEXT,
+                content TEXT,
+                output TEXT
+             );
+             CREATE TABLE prompt_tags (project_id TEXT, prompt_id TEXT, tag TEXT);
+             CREATE TABLE prompt_stars (project_id TEXT, prompt_id TEXT PRIMARY KEY);
+             CREATE TABLE branch_labels (project_id TEXT, name TEXT, prompt_id TEXT);
+             CREATE TABLE prompt_parents (
+                project_id TEXT, prompt_id TEXT, parent_id TEXT, PRIMARY KEY (prompt_id, parent_id)
+             );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        pool
+    }
+
+    fn prompt(id: &str, prev: &str) -> Prompt {
+        Prompt {
+            prompt_id: id.to_string(),
+            project_id: "project".to_string(),
+            prev_prompt_id: prev.to_string(),
+            content: format!("Prompt {}", id),
+            output: format!("Output {}", id),
+        }
+    }
+
+    #[test]
+    fn test_branch_tips() {
+        // project
+        // └── 1
+        //     ├── 2
+        //     └── 3
+        //         └── 4
+        let prompts = vec![
+            prompt("1", "project"),
+            prompt("2", "1"),
+            prompt("3", "1"),
+            prompt("4", "3"),
+        ];
+        let mut marks = ProjectMarks::default();
+        marks.branches.push(BranchLabel {
+            project_id: String::from("project"),
+            name: String::from("idea"),
+            prompt_id: String::from("3"),
+        });
+
+        let tips = branch_tips(&prompts, &marks);
+        let ids: Vec<&str> = tips.iter().map(|p| p.prompt_id.as_str()).collect();
+        assert_eq!(ids, vec!["3", "2", "4"]);
+    }
+
+    #[test]
+    fn test_merge_request() {
+        let prompts = vec![prompt("1", "project"), prompt("2", "1")];
+        let branch = format_branch(&prompt_chain(&prompts, &prompts[1]));
+        assert_eq!(
+            branch,
+            "### Prompt\n\nPrompt 1\n\n### Output\n\nOutput 1\n\n\
+             ### Prompt\n\nPrompt 2\n\n### Output\n\nOutput 2\n"
+        );
+
+        let full = merge_request(None, &branch);
+        assert!(full.contains("## Other branch\n\n### Prompt\n\nPrompt 1"));
+        assert!(full.ends_with("say which ideas you kept and why."));
+
+        let summaries = merge_request(Some("First summary"), "Second summary");
+        assert!(summaries.contains("## First branch\n\nFirst summary"));
+        assert!(summaries.contains("## Second branch\n\nSecond summary"));
+    }
+
+    #[tokio::test]
+    async fn test_store_merge() {
+        let pool = create_test_pool().await;
+        let merge = prompt("merge", "left");
+
+        let stored = store_merge(&pool, &merge, "right").await.unwrap();
+        assert!(!stored.is_reused());
+        let prompts = get_prompts(&pool, "project").await.unwrap();
+        assert_eq!(prompts[0].prev_prompt_id, "left");
+
+        let marks = get_marks(&pool, "project").await.unwrap();
+        assert_eq!(marks.merged["merge"], vec![String::from("right")]);
+
+        // Merging the same answer again reuses the prompt and its parents
+        let again = Prompt {
+            prompt_id: String::from("other"),
+            ..merge
+        };
+        let stored = store_merge(&pool, &again, "right").await.unwrap();
+        assert_eq!(stored.id(), "merge");
+        let marks = get_marks(&pool, "project").await.unwrap();
+        assert_eq!(marks.merged.len(), 1);
+        assert_eq!(marks.merged["merge"].len(), 1);
+    }
+}
@@ -1,8 +1,13 @@
 #[cfg(test)]
 mod tests {
     use legatio::{
-        core::project::{
-            build_select_project, delete_project, format_project_title, get_projects, store_project,
+        core::{
+            project::{
+                build_select_project, delete_project, format_project_title, get_projects,
+                parse_project_details, project_details_template, relocate_project, store_project,
+                update_project_details,
+            },
+            scroll::get_scrolls,
         },
         utils::{logger::initialize_logger, structs::Project},
     };
@@ -10,7 +15,7 @@ mod tests {
     use sqlx::SqlitePool;
 
     async fn create_test_pool() -> SqlitePool {
-        initialize_logger().await;
+        let _ = initialize_logger().await;
 
         SqlitePoolOptions::new()
             .connect("sqlite::memory:")
@@ -25,7 +30,11 @@ mod tests {
         sqlx::query(
             "CREATE TABLE projects (
                 project_id TEXT PRIMARY KEY,
-                project_path TEXT
+                project_path TEXT,
+                display_name TEXT,
+                description TEXT,
+                color TEXT,
+                icon TEXT
             );",
         )
         .execute(&pool)
@@ -35,6 +44,7 @@ mod tests {
         let project = Project {
             project_id: "project_1".to_string(),
             project_path: "/path/to/project_1".to_string(),
+            ..Default::default()
         };
 
         let result = store_project(&pool, &project).await;
@@ -57,7 +67,11 @@ mod tests {
         sqlx::query(
             "CREATE TABLE projects (
                 project_id TEXT PRIMARY KEY,
-                project_path TEXT
+                project_path TEXT,
+                display_name TEXT,
+                description TEXT,
+                color TEXT,
+                icon TEXT
             );",
         )
         .execute(&pool)
@@ -67,6 +81,7 @@ mod tests {
         let project = Project {
             project_id: "project_1".to_string(),
             project_path: "/path/to/project_1".to_string(),
+            ..Default::default()
         };
 
         store_project(&pool, &project).await.unwrap();
@@ -89,7 +104,11 @@ mod tests {
         sqlx::query(
             "CREATE TABLE projects (
                 project_id TEXT PRIMARY KEY,
-                project_path TEXT
+                project_path TEXT,
+                display_name TEXT,
+                description TEXT,
+                color TEXT,
+                icon TEXT
             );",
         )
         .execute(&pool)
@@ -119,7 +138,11 @@ mod tests {
         sqlx::query(
             "CREATE TABLE projects (
                 project_id TEXT PRIMARY KEY,
-                project_path TEXT
+                project_path TEXT,
+                display_name TEXT,
+                description TEXT,
+                color TEXT,
+                icon TEXT
             );",
         )
         .execute(&pool)
@@ -147,6 +170,25 @@ mod tests {
         .await
         .unwrap();
 
+        // Trash, marks and merge parents, without foreign keys to delete them in cascade
+        let tables = [
+            "prompt_trash",
+            "trash_batches",
+            "prompt_tags",
+            "prompt_stars",
+            "branch_labels",
+            "prompt_parents",
+        ];
+        for table in tables {
+            sqlx::query(&format!(
+                "CREATE TABLE {table} (project_id TEXT);
+                 INSERT INTO {table} (project_id) VALUES ('project_1'), ('project_2');"
+            ))
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
         sqlx::query(
             "INSERT INTO projects (project_id, project_path) VALUES ('project_1', '/project1');",
         )
@@ -159,6 +201,76 @@ mod tests {
 
         let projects = get_projects(&pool).await.unwrap();
         assert!(projects.is_empty());
+        for table in tables {
+            let rows: Vec<String> = sqlx::query_scalar(&format!("SELECT project_id FROM {table};"))
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+            assert_eq!(rows, vec!["project_2"], "{}", table);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delete_project_rolls_back_on_failure() {
+        let pool = create_test_pool().await;
+
+        sqlx::query(
+            "CREATE TABLE projects (
+                project_id TEXT PRIMARY KEY,
+                project_path TEXT,
+                display_name TEXT,
+                description TEXT,
+                color TEXT,
+                icon TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE TABLE prompts (
+                prompt_id TEXT PRIMARY KEY,
+                project_id TEXT,
+                prev_prompt_id TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        for table in [
+            "prompt_trash",
+            "trash_batches",
+            "prompt_tags",
+            "prompt_stars",
+            "branch_labels",
+            "prompt_parents",
+        ] {
+            sqlx::query(&format!("CREATE TABLE {table} (project_id TEXT);"))
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        sqlx::query(
+            "INSERT INTO projects (project_id, project_path) VALUES ('project_1', '/project1');
+             INSERT INTO prompts (prompt_id, project_id) VALUES ('prompt_1', 'project_1');",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // The scrolls table is missing, so the deletion fails midway
+        assert!(delete_project(&pool, "project_1").await.is_err());
+
+        let projects = get_projects(&pool).await.unwrap();
+        assert_eq!(projects.len(), 1);
+        let prompts: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM prompts;")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(prompts, 1);
     }
 
     #[test]
@@ -166,6 +278,7 @@ mod tests {
         let project = Some(Project {
             project_id: "project_1".to_string(),
             project_path: "/path/to/project_1".to_string(),
+            ..Default::default()
         });
 
         let result = format_project_title(&project);
@@ -181,10 +294,12 @@ mod tests {
             Project {
                 project_id: "project_1".to_string(),
                 project_path: "/path/to/project_1".to_string(),
+                ..Default::default()
             },
             Project {
                 project_id: "project_2".to_string(),
                 project_path: "/path/to/project_2".to_string(),
+                ..Default::default()
             },
         ];
 
@@ -194,4 +309,178 @@ mod tests {
         assert!(str_items.contains(&" -[ project_1 ]-".to_string()));
         assert!(str_items.contains(&" -[ project_2 ]-".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_relocate_project() {
+        let pool = create_test_pool().await;
+
+        sqlx::query(
+            "CREATE TABLE projects (
+                project_id TEXT PRIMARY KEY,
+                project_path TEXT,
+                display_name TEXT,
+                description TEXT,
+                color TEXT,
+                icon TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE TABLE scrolls (
+                scroll_id TEXT PRIMARY KEY,
+                scroll_path TEXT,
+                content TEXT,
+                project_id TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let new_root = tempfile::tempdir().unwrap();
+        std::fs::write(new_root.path().join("kept.md"), "kept").unwrap();
+        let new_path = new_root.path().to_string_lossy().to_string();
+
+        sqlx::query(
+            "INSERT INTO projects (project_id, project_path)
+             VALUES ('project_1', '/old/repo'), ('project_2', '/other');",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO scrolls (scroll_id, scroll_path, content, project_id)
+             VALUES ('scroll_1', '/old/repo/kept.md', 'kept', 'project_1'),
+                    ('scroll_2', '/old/repo/gone.md', 'gone', 'project_1');",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let projects = get_projects(&pool).await.unwrap();
+
+        // A path owned by another project is refused
+        assert!(relocate_project(&pool, &projects[0], "/other")
+            .await
+            .is_err());
+
+        let (relocated, missing) = relocate_project(&pool, &projects[0], &new_path)
+            .await
+            .unwrap();
+        assert_eq!(relocated.project_path, new_path);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].scroll_id, "scroll_2");
+
+        let projects = get_projects(&pool).await.unwrap();
+        assert_eq!(projects[0].project_path, new_path);
+
+        // Scrolls are kept, with paths relative to the project
+        let scrolls = get_scrolls(&pool, "project_1").await.unwrap();
+        assert_eq!(scrolls.len(), 2);
+        assert_eq!(scrolls[0].scroll_path, "kept.md");
+    }
+
+    #[test]
+    fn test_build_select_project_details() {
+        let projects = vec![
+            Project {
+                project_id: "project_1".to_string(),
+                project_path: "/work/backend".to_string(),
+                display_name: Some("Billing API".to_string()),
+                description: Some("Invoices and payments".to_string()),
+                color: Some("cyan".to_string()),
+                icon: Some("$".to_string()),
+            },
+            Project {
+                project_id: "project_2".to_string(),
+                project_path: "/home/backend".to_string(),
+                ..Default::default()
+            },
+            Project {
+                project_id: "project_3".to_string(),
+                project_path: "/tmp/backend".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let (_, str_items) = build_select_project(&projects);
+
+        assert_eq!(str_items[0], " -[ $ Billing API ]- Invoices and payments");
+        // Projects sharing a name show their path
+        assert_eq!(str_items[1], " -[ backend ]- (/home/backend)");
+        assert_eq!(str_items[2], " -[ backend ]- (/tmp/backend)");
+
+        assert_eq!(
+            format_project_title(&Some(projects[0].clone())),
+            "[ Current Project: $ Billing API ]"
+        );
+    }
+
+    #[test]
+    fn test_parse_project_details() {
+        let project = Project {
+            project_id: "project_1".to_string(),
+            project_path: "/work/backend".to_string(),
+            description: Some("Old description".to_string()),
+            ..Default::default()
+        };
+
+        let template = project_details_template(&project);
+        assert!(template.contains("description = \"Old description\""));
+
+        let edited = template
+            .replace(
+                "display_name = \"\"",
+                "display_name = \"Billing \\\"API\\\"\"",
+            )
+            .replace("description = \"Old description\"", "description = \"  \"")
+            .replace("color = \"\"", "color = \"#ff8800\"");
+        let updated = parse_project_details(&project, &edited).unwrap();
+        assert_eq!(updated.project_path, "/work/backend");
+        assert_eq!(updated.display_name.as_deref(), Some("Billing \"API\""));
+        assert_eq!(updated.description, None);
+        assert_eq!(updated.color.as_deref(), Some("#ff8800"));
+        assert_eq!(updated.icon, None);
+
+        // The template round-trips unchanged details
+        let same = parse_project_details(&updated, &project_details_template(&updated)).unwrap();
+        assert_eq!(same.display_name, updated.display_name);
+
+        assert!(parse_project_details(&project, "color = \"not-a-color\"").is_err());
+        assert!(parse_project_details(&project, "display_name = ").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_update_project_details() {
+        let pool = create_test_pool().await;
+
+        sqlx::query(
+            "CREATE TABLE projects (
+                project_id TEXT PRIMARY KEY,
+                project_path TEXT,
+                display_name TEXT,
+                description TEXT,
+                color TEXT,
+                icon TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let mut project = Project::new("/work/backend");
+        store_project(&pool, &project).await.unwrap();
+
+        project.display_name = Some("Billing API".to_string());
+        project.icon = Some("$".to_string());
+        update_project_details(&pool, &project).await.unwrap();
+
+        let projects = get_projects(&pool).await.unwrap();
+        assert_eq!(projects[0].name(), "Billing API");
+        assert_eq!(projects[0].icon.as_deref(), Some("$"));
+        assert_eq!(projects[0].description, None);
+    }
 }
@@ -2,9 +2,11 @@
 mod tests {
     use legatio::{
         core::prompt::{
-            delete_prompt, get_prompts, prompt_chain, store_prompt, system_prompt, update_prompt,
+            copy_prompt, delete_prompt, get_prompts, move_prompt, move_targets, prompt_chain,
+            prompt_descendants, store_prompt, store_prompts, system_prompt, update_prompt,
+            PromptScope,
         },
-        utils::structs::{Prompt, Scroll},
+        utils::structs::{Prompt, Scroll, StoreResult},
     };
     use sqlx::sqlite::SqlitePoolOptions;
     use sqlx::SqlitePool;
@@ -94,6 +96,110 @@ mod tests {
         assert_eq!(stored_prompts.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_store_prompt_scoped_to_project_and_parent() {
+        let pool = create_test_pool().await;
+
+        sqlx::query(
+            "CREATE TABLE prompts (
+                prompt_id TEXT PRIMARY KEY,
+                project_id TEXT,
+                prev_prompt_id TEXT,
+                content TEXT,
+                output TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let prompt = |id: &str, project: &str, prev: &str| Prompt {
+            prompt_id: id.to_string(),
+            project_id: project.to_string(),
+            prev_prompt_id: prev.to_string(),
+            content: "content".to_string(),
+            output: "output".to_string(),
+        };
+
+        assert_eq!(
+            store_prompt(&pool, &prompt("1", "project_1", "project_1"))
+                .await
+                .unwrap(),
+            StoreResult::Inserted("1".to_string())
+        );
+        // Same content in another project and under another parent
+        assert_eq!(
+            store_prompt(&pool, &prompt("2", "project_2", "project_2"))
+                .await
+                .unwrap(),
+            StoreResult::Inserted("2".to_string())
+        );
+        assert_eq!(
+            store_prompt(&pool, &prompt("3", "project_1", "1"))
+                .await
+                .unwrap(),
+            StoreResult::Inserted("3".to_string())
+        );
+        // Same content under the same parent reuses the existing prompt
+        assert_eq!(
+            store_prompt(&pool, &prompt("4", "project_1", "project_1"))
+                .await
+                .unwrap(),
+            StoreResult::Reused("1".to_string())
+        );
+
+        assert_eq!(get_prompts(&pool, "project_1").await.unwrap().len(), 2);
+        assert_eq!(get_prompts(&pool, "project_2").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_store_prompts_links_children_to_reused_prompt() {
+        let pool = create_test_pool().await;
+
+        sqlx::query(
+            "CREATE TABLE prompts (
+                prompt_id TEXT PRIMARY KEY,
+                project_id TEXT,
+                prev_prompt_id TEXT,
+                content TEXT,
+                output TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let prompt = |id: &str, prev: &str, content: &str| Prompt {
+            prompt_id: id.to_string(),
+            project_id: "project_1".to_string(),
+            prev_prompt_id: prev.to_string(),
+            content: content.to_string(),
+            output: "output".to_string(),
+        };
+        store_prompt(&pool, &prompt("1", "project_1", "Q1"))
+            .await
+            .unwrap();
+
+        // Children listed before their parent, whose content is already stored
+        let stored = store_prompts(
+            &pool,
+            &[prompt("b", "a", "Q2"), prompt("a", "project_1", "Q1")],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].1, StoreResult::Reused("1".to_string()));
+        assert_eq!(stored[1].1, StoreResult::Inserted("b".to_string()));
+        assert_eq!(stored[1].0.prev_prompt_id, "1");
+
+        let prompts = get_prompts(&pool, "project_1").await.unwrap();
+        assert_eq!(prompts.len(), 2);
+        assert!(prompts
+            .iter()
+            .any(|p| p.prompt_id == "b" && p.prev_prompt_id == "1"));
+    }
+
     #[tokio::test]
     async fn test_get_prompts() {
         let pool = create_test_pool().await;
@@ -274,6 +380,30 @@ mod tests {
         assert_eq!(chain[2].prompt_id, "1");
     }
 
+    #[test]
+    fn test_prompt_descendants() {
+        let prompt = |id: &str, prev: &str| Prompt {
+            prompt_id: id.to_string(),
+            project_id: "project".to_string(),
+            prev_prompt_id: prev.to_string(),
+            content: format!("Prompt {}", id),
+            output: format!("Output {}", id),
+        };
+        let prompts = vec![
+            prompt("1", "project"),
+            prompt("2", "1"),
+            prompt("3", "1"),
+            prompt("4", "3"),
+            prompt("5", "project"),
+        ];
+
+        let descendants = prompt_descendants(&prompts, &prompts[0]);
+        let ids: Vec<&str> = descendants.iter().map(|p| p.prompt_id.as_str()).collect();
+        assert_eq!(ids, vec!["2", "3", "4"]);
+
+        assert!(prompt_descendants(&prompts, &prompts[4]).is_empty());
+    }
+
     #[tokio::test]
     async fn test_prompt_integration() {
         let pool = SqlitePoolOptions::new()
@@ -346,4 +476,140 @@ mod tests {
             "Expected no remaining prompts after deletion"
         );
     }
+
+    async fn create_tree_pool() -> SqlitePool {
+        let pool = create_test_pool().await;
+        sqlx::query(
+            "CREATE TABLE prompts (
+                prompt_id TEXT PRIMARY KEY,
+                project_id TEXT,
+                prev_prompt_id TEXT,
+                content TEXT,
+                output TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // project
+        // ├── 1
+        // │   ├── 2
+        // │   └── 3
+        // │       └── 4
+        // └── 5
+        for (id, prev) in [
+            ("1", "project"),
+            ("2", "1"),
+            ("3", "1"),
+            ("4", "3"),
+            ("5", "project"),
+        ] {
+            sqlx::query(
+                "INSERT INTO prompts (prompt_id, project_id, prev_prompt_id, content, output)
+                 VALUES ($1, 'project', $2, $3, $4)",
+            )
+            .bind(id)
+            .bind(prev)
+            .bind(format!("Prompt {}", id))
+            .bind(format!("Output {}", id))
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        pool
+    }
+
+    async fn parent_of(pool: &SqlitePool, project_id: &str, id: &str) -> String {
+        get_prompts(pool, project_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|p| p.prompt_id == id)
+            .unwrap()
+            .prev_prompt_id
+    }
+
+    #[tokio::test]
+    async fn test_move_prompt() {
+        let pool = create_tree_pool().await;
+        let prompts = get_prompts(&pool, "project").await.unwrap();
+        let find = |id: &str| prompts.iter().find(|p| p.prompt_id == id).unwrap().clone();
+
+        // A subtree cannot move below itself
+        let targets = move_targets(&prompts, &find("3"), PromptScope::Subtree);
+        let ids: Vec<&str> = targets.iter().map(|p| p.prompt_id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "2", "5"]);
+        assert!(move_prompt(&pool, &find("3"), "4", PromptScope::Subtree)
+            .await
+            .is_err());
+        assert!(
+            move_prompt(&pool, &find("3"), "other", PromptScope::Subtree)
+                .await
+                .is_err()
+        );
+
+        move_prompt(&pool, &find("3"), "5", PromptScope::Subtree)
+            .await
+            .unwrap();
+        assert_eq!(parent_of(&pool, "project", "3").await, "5");
+        assert_eq!(parent_of(&pool, "project", "4").await, "3");
+
+        // Moving a single prompt leaves its children with its old parent
+        let prompts = get_prompts(&pool, "project").await.unwrap();
+        let three = prompts.iter().find(|p| p.prompt_id == "3").unwrap();
+        move_prompt(&pool, three, "4", PromptScope::Single)
+            .await
+            .unwrap();
+        assert_eq!(parent_of(&pool, "project", "4").await, "5");
+        assert_eq!(parent_of(&pool, "project", "3").await, "4");
+
+        // Detaching makes a new root
+        let prompts = get_prompts(&pool, "project").await.unwrap();
+        let four = prompts.iter().find(|p| p.prompt_id == "4").unwrap();
+        move_prompt(&pool, four, "project", PromptScope::Subtree)
+            .await
+            .unwrap();
+        assert_eq!(parent_of(&pool, "project", "4").await, "project");
+        assert_eq!(parent_of(&pool, "project", "3").await, "4");
+    }
+
+    #[tokio::test]
+    async fn test_copy_prompt() {
+        let pool = create_tree_pool().await;
+        let prompts = get_prompts(&pool, "project").await.unwrap();
+        let one = prompts.iter().find(|p| p.prompt_id == "1").unwrap();
+
+        // Copies keep their shape under new ids
+        let copies = copy_prompt(&pool, one, PromptScope::Subtree, "other", "other")
+            .await
+            .unwrap();
+        assert_eq!(copies.len(), 4);
+        assert_eq!(copies[0].content, "Prompt 1");
+        assert_eq!(copies[0].prev_prompt_id, "other");
+        assert!(copies.iter().all(|c| c.project_id == "other"));
+        assert!(copies
+            .iter()
+            .all(|c| prompts.iter().all(|p| p.prompt_id != c.prompt_id)));
+        let copied = get_prompts(&pool, "other").await.unwrap();
+        let four = copied.iter().find(|p| p.content == "Prompt 4").unwrap();
+        let chain = prompt_chain(&copied, four);
+        let contents: Vec<&str> = chain.iter().map(|p| p.content.as_str()).collect();
+        assert_eq!(contents, vec!["Prompt 4", "Prompt 3", "Prompt 1"]);
+        assert_eq!(get_prompts(&pool, "project").await.unwrap().len(), 5);
+
+        // Cherry-pick a single prompt onto another branch
+        let four = prompts.iter().find(|p| p.prompt_id == "4").unwrap();
+        let copies = copy_prompt(&pool, four, PromptScope::Single, "project", "5")
+            .await
+            .unwrap();
+        assert_eq!(copies.len(), 1);
+        assert_eq!(parent_of(&pool, "project", &copies[0].prompt_id).await, "5");
+
+        // Parents must exist in the target project
+        assert!(copy_prompt(&pool, four, PromptScope::Single, "other", "5")
+            .await
+            .is_err());
+    }
 }
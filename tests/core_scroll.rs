@@ -2,11 +2,13 @@
 mod tests {
     use legatio::{
         core::scroll::{
-            delete_scroll, get_scrolls, read_file, store_scroll, update_scroll_content,
+            delete_scroll, get_scrolls, missing_scrolls, read_file, read_project_file,
+            relative_scroll_path, relativize_scroll_paths, resolve_scroll_path, store_scroll,
+            update_scroll_content,
         },
         utils::error::AppError,
         utils::logger::initialize_logger,
-        utils::structs::Scroll,
+        utils::structs::{Project, Scroll, StoreResult},
     };
     use sqlx::sqlite::SqlitePoolOptions;
     use sqlx::SqlitePool;
@@ -14,7 +16,7 @@ mod tests {
 
     // Utility function to create an in-memory SQLite pool for testing
     async fn create_test_pool() -> SqlitePool {
-        initialize_logger().await;
+        let _ = initialize_logger().await;
 
         SqlitePoolOptions::new()
             .connect("sqlite::memory:")
@@ -95,6 +97,53 @@ mod tests {
         assert_eq!(stored_scrolls.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_store_scroll_scoped_to_project() {
+        let pool = create_test_pool().await;
+
+        sqlx::query(
+            "CREATE TABLE scrolls (
+                scroll_id TEXT PRIMARY KEY,
+                scroll_path TEXT,
+                content TEXT,
+                project_id TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let scroll = |id: &str, project: &str| Scroll {
+            scroll_id: id.to_string(),
+            scroll_path: "notes.md".to_string(),
+            content: "content".to_string(),
+            project_id: project.to_string(),
+        };
+
+        assert_eq!(
+            store_scroll(&pool, &scroll("scroll_1", "project_1"))
+                .await
+                .unwrap(),
+            StoreResult::Inserted("scroll_1".to_string())
+        );
+        // The same relative path in another project is another file
+        assert_eq!(
+            store_scroll(&pool, &scroll("scroll_2", "project_2"))
+                .await
+                .unwrap(),
+            StoreResult::Inserted("scroll_2".to_string())
+        );
+        assert_eq!(
+            store_scroll(&pool, &scroll("scroll_3", "project_1"))
+                .await
+                .unwrap(),
+            StoreResult::Reused("scroll_1".to_string())
+        );
+
+        assert_eq!(get_scrolls(&pool, "project_1").await.unwrap().len(), 1);
+        assert_eq!(get_scrolls(&pool, "project_2").await.unwrap().len(), 1);
+    }
+
     #[tokio::test]
     async fn test_get_scrolls() {
         let pool = create_test_pool().await;
@@ -195,7 +244,7 @@ mod tests {
         fs::write(file_path, new_content).unwrap();
 
         // Update the scroll in the database
-        let updated_scroll = update_scroll_content(&pool, &scroll).await.unwrap();
+        let updated_scroll = update_scroll_content(&pool, "/tmp", &scroll).await.unwrap();
 
         // Verify the updated scroll
         assert_eq!(updated_scroll.content, new_content);
@@ -250,4 +299,111 @@ mod tests {
 
         fs::remove_file(file_path).unwrap();
     }
+
+    #[test]
+    fn test_relative_and_resolved_paths() {
+        assert_eq!(
+            relative_scroll_path("/home/user/repo", "/home/user/repo/src/lib.rs"),
+            Some("src/lib.rs".to_string())
+        );
+        assert_eq!(
+            relative_scroll_path("/home/user/repo", "/home/user/other/lib.rs"),
+            None
+        );
+        assert_eq!(
+            resolve_scroll_path("/home/user/repo", "src/lib.rs"),
+            std::path::PathBuf::from("/home/user/repo/src/lib.rs")
+        );
+        assert_eq!(
+            resolve_scroll_path("/home/user/repo", "/etc/hosts"),
+            std::path::PathBuf::from("/etc/hosts")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_relative_scrolls_follow_project() {
+        let pool = create_test_pool().await;
+
+        sqlx::query(
+            "CREATE TABLE scrolls (
+                scroll_id TEXT PRIMARY KEY,
+                scroll_path TEXT,
+                content TEXT,
+                project_id TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let old_root = tempfile::tempdir().unwrap();
+        fs::write(old_root.path().join("notes.md"), "Old content").unwrap();
+        let project = Project {
+            project_id: "project_1".to_string(),
+            project_path: old_root.path().to_string_lossy().to_string(),
+            ..Default::default()
+        };
+
+        // New scrolls inside the project are stored relative to it
+        let file_path = old_root.path().join("notes.md");
+        let scroll = read_project_file(&project, &file_path.to_string_lossy()).unwrap();
+        assert_eq!(scroll.scroll_path, "notes.md");
+        store_scroll(&pool, &scroll).await.unwrap();
+
+        // Move the project somewhere else
+        let new_root = tempfile::tempdir().unwrap();
+        fs::write(new_root.path().join("notes.md"), "New content").unwrap();
+        let moved = Project {
+            project_path: new_root.path().to_string_lossy().to_string(),
+            ..project.clone()
+        };
+
+        assert!(missing_scrolls(&pool, &moved).await.unwrap().is_empty());
+        let updated = update_scroll_content(&pool, &moved.project_path, &scroll)
+            .await
+            .unwrap();
+        assert_eq!(updated.content, "New content");
+        assert_eq!(updated.scroll_path, "notes.md");
+    }
+
+    #[tokio::test]
+    async fn test_relativize_scroll_paths() {
+        let pool = create_test_pool().await;
+
+        sqlx::query(
+            "CREATE TABLE scrolls (
+                scroll_id TEXT PRIMARY KEY,
+                scroll_path TEXT,
+                content TEXT,
+                project_id TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "INSERT INTO scrolls (scroll_id, scroll_path, content, project_id)
+             VALUES ('scroll_1', '/repo/src/a.rs', 'a', 'project_1'),
+                    ('scroll_2', '/elsewhere/b.rs', 'b', 'project_1'),
+                    ('scroll_3', 'src/c.rs', 'c', 'project_1');",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let project = Project {
+            project_id: "project_1".to_string(),
+            project_path: "/repo".to_string(),
+            ..Default::default()
+        };
+
+        let updated = relativize_scroll_paths(&pool, &project).await.unwrap();
+        assert_eq!(updated, 1);
+
+        let scrolls = get_scrolls(&pool, "project_1").await.unwrap();
+        assert_eq!(scrolls[0].scroll_path, "src/a.rs");
+        assert_eq!(scrolls[1].scroll_path, "/elsewhere/b.rs");
+        assert_eq!(scrolls[2].scroll_path, "src/c.rs");
+    }
 }
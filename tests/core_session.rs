@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+    use legatio::core::session::{get_session, store_session, Session};
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::SqlitePool;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create database connection pool");
+
+        sqlx::query(
+            "CREATE TABLE session (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                project_id TEXT,
+                branch TEXT,
+                prompt_id TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_store_and_get_session() {
+        let pool = create_test_pool().await;
+        assert_eq!(get_session(&pool).await.unwrap(), Session::default());
+
+        let session = Session {
+            project_id: Some(String::from("project")),
+            branch: Some(String::from("main")),
+            prompt_id: Some(String::from("1")),
+        };
+        store_session(&pool, &session).await.unwrap();
+        assert_eq!(get_session(&pool).await.unwrap(), session);
+
+        // A single session is kept
+        let detached = Session {
+            branch: None,
+            prompt_id: Some(String::from("2")),
+            ..session
+        };
+        store_session(&pool, &detached).await.unwrap();
+        assert_eq!(get_session(&pool).await.unwrap(), detached);
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM session;")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+}
@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod tests {
+    use legatio::{
+        core::tag::{
+            advance_branch, get_marks, parse_prompt_marks, prompt_marks_template, set_prompt_marks,
+            validate_mark_name, PromptMarks,
+        },
+        utils::structs::Prompt,
+    };
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::SqlitePool;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create database connection pool");
+
+        sqlx::query(
+            "CREATE TABLE prompt_tags (
+                project_id TEXT, prompt_id TEXT, tag TEXT, PRIMARY KEY (prompt_id, tag)
+             );
+             CREATE TABLE prompt_stars (project_id TEXT, prompt_id TEXT PRIMARY KEY);
+             CREATE TABLE branch_labels (
+                project_id TEXT, name TEXT, prompt_id TEXT, PRIMARY KEY (project_id, name)
+             );
+             CREATE TABLE prompt_parents (
+                project_id TEXT, prompt_id TEXT, parent_id TEXT, PRIMARY KEY (prompt_id, parent_id)
+             );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        pool
+    }
+
+    fn prompt(id: &str, prev: &str) -> Prompt {
+        Prompt {
+            prompt_id: id.to_string(),
+            project_id: "project".to_string(),
+            prev_prompt_id: prev.to_string(),
+            content: format!("Prompt {}", id),
+            output: format!("Output {}", id),
+        }
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_set_and_get_marks() {
+        let pool = create_test_pool().await;
+        let prompts = vec![prompt("1", "project"), prompt("2", "1"), prompt("3", "1")];
+
+        let marks = PromptMarks {
+            tags: names(&["rust", "good"]),
+            starred: true,
+            branches: names(&["main"]),
+        };
+        set_prompt_marks(&pool, &prompts[1], &marks).await.unwrap();
+        set_prompt_marks(
+            &pool,
+            &prompts[2],
+            &PromptMarks {
+                tags: names(&["good"]),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let project_marks = get_marks(&pool, "project").await.unwrap();
+        assert_eq!(
+            project_marks.prompt("2"),
+            PromptMarks {
+                tags: names(&["good", "rust"]),
+                starred: true,
+                branches: names(&["main"]),
+            }
+        );
+        assert_eq!(project_marks.prompt("1"), PromptMarks::default());
+        assert_eq!(project_marks.all_tags(), names(&["good", "rust"]));
+        assert_eq!(project_marks.tagged(&prompts, "good").len(), 2);
+        assert_eq!(project_marks.tagged(&prompts, "rust")[0].prompt_id, "2");
+        assert_eq!(project_marks.starred_prompts(&prompts).len(), 1);
+        assert_eq!(project_marks.label("2"), " * [main] #good #rust");
+        assert_eq!(project_marks.label("1"), "");
+
+        // Naming another prompt `main` moves the label
+        set_prompt_marks(
+            &pool,
+            &prompts[2],
+            &PromptMarks {
+                branches: names(&["main"]),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let project_marks = get_marks(&pool, "project").await.unwrap();
+        let heads = project_marks.heads(&prompts);
+        assert_eq!(heads.len(), 1);
+        assert_eq!(heads[0].0.name, "main");
+        assert_eq!(heads[0].1.prompt_id, "3");
+        assert!(project_marks.prompt("3").tags.is_empty());
+
+        // Labels of prompts that are gone are not heads
+        assert!(project_marks.heads(&prompts[..2]).is_empty());
+    }
+
+    #[test]
+    fn test_prompt_marks_template_round_trip() {
+        let marks = PromptMarks {
+            tags: names(&["good", "c++"]),
+            starred: true,
+            branches: names(&["main", "experiment-2"]),
+        };
+        let template = prompt_marks_template(&prompt("1", "project"), &marks);
+        assert!(template.contains("Prompt 1"));
+        assert_eq!(parse_prompt_marks(&template).unwrap(), marks);
+
+        let parsed = parse_prompt_marks("tags = [\" good \", \"good\"]").unwrap();
+        assert_eq!(
+            parsed,
+            PromptMarks {
+                tags: names(&["good"]),
+                ..Default::default()
+            }
+        );
+        assert!(parse_prompt_marks("tags = [\"two words\"]").is_err());
+        assert!(parse_prompt_marks("branches = \"main\"").is_err());
+    }
+
+    #[test]
+    fn test_validate_mark_name() {
+        assert_eq!(validate_mark_name(" good ").unwrap(), "good");
+        assert!(validate_mark_name("").is_err());
+        assert!(validate_mark_name("#good").is_err());
+        assert!(validate_mark_name("[main]").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_advance_branch() {
+        let pool = create_test_pool().await;
+        let prompts = vec![prompt("1", "project"), prompt("2", "1"), prompt("3", "2")];
+        let marks = PromptMarks {
+            branches: names(&["main"]),
+            ..Default::default()
+        };
+        set_prompt_marks(&pool, &prompts[0], &marks).await.unwrap();
+
+        assert!(advance_branch(&pool, "project", "main", "1", "2")
+            .await
+            .unwrap());
+        let project_marks = get_marks(&pool, "project").await.unwrap();
+        assert_eq!(project_marks.branch_of("2"), Some(String::from("main")));
+        assert_eq!(project_marks.branch_of("1"), None);
+
+        // Only the current head moves the branch
+        assert!(!advance_branch(&pool, "project", "main", "1", "3")
+            .await
+            .unwrap());
+        assert!(!advance_branch(&pool, "project", "other", "2", "3")
+            .await
+            .unwrap());
+        let project_marks = get_marks(&pool, "project").await.unwrap();
+        assert_eq!(project_marks.heads(&prompts)[0].1.prompt_id, "2");
+    }
+}
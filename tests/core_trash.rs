@@ -0,0 +1,233 @@
+#[cfg(test)]
+mod tests {
+    use legatio::{
+        core::{
+            prompt::get_prompts,
+            tag::{get_marks, set_prompt_marks, PromptMarks},
+            trash::{
+                empty_trash, format_trash_entry, get_trash, purge_trash, restore_trash,
+                trash_prompt, DeleteMode,
+            },
+        },
+        utils::structs::Prompt,
+    };
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::SqlitePool;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create database connection pool");
+
+        sqlx::query(
+            "CREATE TABLE prompts (
+                prompt_id TEXT PRIMARY KEY,
+                project_id TEXT,
+                prev_prompt_id TEXT,
+                content TEXT,
+                output TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE TABLE trash_batches (
+                batch_id TEXT PRIMARY KEY,
+                project_id TEXT,
+                deleted_at TEXT,
+                mode TEXT,
+                spliced_children TEXT
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE TABLE prompt_trash (
+                batch_id TEXT,
+                position INTEGER,
+                prompt_id TEXT,
+                project_id TEXT,
+                content TEXT,
+                output TEXT,
+                prev_prompt_id TEXT,
+                PRIMARY KEY (batch_id, prompt_id)
+            );",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            "CREATE TABLE prompt_tags (project_id TEXT, prompt_id TEXT, tag TEXT);
+             CREATE TABLE prompt_stars (project_id TEXT, prompt_id TEXT PRIMARY KEY);
+             CREATE TABLE branch_labels (project_id TEXT, name TEXT, prompt_id TEXT);
+             CREATE TABLE prompt_parents (project_id TEXT, prompt_id TEXT, parent_id TEXT);",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // project
+        // └── 1
+        //     ├── 2
+        //     └── 3
+        //         └── 4
+        for (id, prev) in [("1", "project"), ("2", "1"), ("3", "1"), ("4", "3")] {
+            sqlx::query(
+                "INSERT INTO prompts (prompt_id, project_id, prev_prompt_id, content, output)
+                 VALUES ($1, 'project', $2, $3, $4)",
+            )
+            .bind(id)
+            .bind(prev)
+            .bind(format!("Prompt {}", id))
+            .bind(format!("Output {}", id))
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        pool
+    }
+
+    async fn find(pool: &SqlitePool, id: &str) -> Option<Prompt> {
+        get_prompts(pool, "project")
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|p| p.prompt_id == id)
+    }
+
+    #[tokio::test]
+    async fn test_trash_and_restore_splice() {
+        let pool = create_test_pool().await;
+        let prompt = find(&pool, "1").await.unwrap();
+
+        let entry = trash_prompt(&pool, &prompt, DeleteMode::Splice)
+            .await
+            .unwrap();
+        assert_eq!(entry.prompts.len(), 1);
+
+        // The children move up to the project root
+        assert!(find(&pool, "1").await.is_none());
+        assert_eq!(find(&pool, "2").await.unwrap().prev_prompt_id, "project");
+        assert_eq!(find(&pool, "3").await.unwrap().prev_prompt_id, "project");
+        assert_eq!(find(&pool, "4").await.unwrap().prev_prompt_id, "3");
+
+        let restored = restore_trash(&pool, &entry.batch.batch_id).await.unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(find(&pool, "1").await.unwrap().prev_prompt_id, "project");
+        assert_eq!(find(&pool, "2").await.unwrap().prev_prompt_id, "1");
+        assert_eq!(find(&pool, "3").await.unwrap().prev_prompt_id, "1");
+        assert!(get_trash(&pool, "project").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_trash_and_restore_subtree() {
+        let pool = create_test_pool().await;
+        let prompt = find(&pool, "3").await.unwrap();
+
+        let entry = trash_prompt(&pool, &prompt, DeleteMode::Subtree)
+            .await
+            .unwrap();
+        assert_eq!(entry.prompts.len(), 2);
+        assert_eq!(get_prompts(&pool, "project").await.unwrap().len(), 2);
+
+        let trash = get_trash(&pool, "project").await.unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].prompts[0].prompt_id, "3");
+        assert!(format_trash_entry(&trash[0]).contains("2 prompt(s), subtree :: Prompt 3"));
+
+        restore_trash(&pool, &entry.batch.batch_id).await.unwrap();
+        assert_eq!(find(&pool, "3").await.unwrap().prev_prompt_id, "1");
+        assert_eq!(find(&pool, "4").await.unwrap().prev_prompt_id, "3");
+    }
+
+    #[tokio::test]
+    async fn test_restore_without_parent() {
+        let pool = create_test_pool().await;
+        let child = find(&pool, "3").await.unwrap();
+        let parent = find(&pool, "1").await.unwrap();
+
+        let entry = trash_prompt(&pool, &child, DeleteMode::Subtree)
+            .await
+            .unwrap();
+        let parent_entry = trash_prompt(&pool, &parent, DeleteMode::Subtree)
+            .await
+            .unwrap();
+        purge_trash(&pool, &parent_entry.batch.batch_id)
+            .await
+            .unwrap();
+
+        // The parent is gone for good, so the branch starts at the project root
+        restore_trash(&pool, &entry.batch.batch_id).await.unwrap();
+        assert_eq!(find(&pool, "3").await.unwrap().prev_prompt_id, "project");
+        assert_eq!(find(&pool, "4").await.unwrap().prev_prompt_id, "3");
+        assert!(find(&pool, "1").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_purge_and_empty_trash() {
+        let pool = create_test_pool().await;
+        let first = find(&pool, "2").await.unwrap();
+        let second = find(&pool, "4").await.unwrap();
+
+        let entry = trash_prompt(&pool, &first, DeleteMode::Splice)
+            .await
+            .unwrap();
+        trash_prompt(&pool, &second, DeleteMode::Splice)
+            .await
+            .unwrap();
+        assert_eq!(get_trash(&pool, "project").await.unwrap().len(), 2);
+
+        purge_trash(&pool, &entry.batch.batch_id).await.unwrap();
+        assert_eq!(get_trash(&pool, "project").await.unwrap().len(), 1);
+        assert!(restore_trash(&pool, &entry.batch.batch_id).await.is_err());
+
+        empty_trash(&pool, "project").await.unwrap();
+        assert!(get_trash(&pool, "project").await.unwrap().is_empty());
+        assert_eq!(get_prompts(&pool, "project").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_trash_keeps_marks_until_purged() {
+        let pool = create_test_pool().await;
+        let marks = PromptMarks {
+            tags: vec![String::from("good")],
+            starred: true,
+            branches: vec![String::from("main")],
+        };
+        let prompt = find(&pool, "4").await.unwrap();
+        set_prompt_marks(&pool, &prompt, &marks).await.unwrap();
+        // 4 is merged into 2 and is itself a merge of 2
+        sqlx::query(
+            "INSERT INTO prompt_parents (project_id, prompt_id, parent_id)
+             VALUES ('project', '2', '4'), ('project', '4', '2');",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // Restored prompts get their marks back
+        let entry = trash_prompt(&pool, &prompt, DeleteMode::Splice)
+            .await
+            .unwrap();
+        restore_trash(&pool, &entry.batch.batch_id).await.unwrap();
+        let project_marks = get_marks(&pool, "project").await.unwrap();
+        assert_eq!(project_marks.prompt("4"), marks);
+
+        // Purged prompts lose them
+        let entry = trash_prompt(&pool, &prompt, DeleteMode::Splice)
+            .await
+            .unwrap();
+        purge_trash(&pool, &entry.batch.batch_id).await.unwrap();
+        assert_eq!(
+            get_marks(&pool, "project").await.unwrap(),
+            Default::default()
+        );
+    }
+}
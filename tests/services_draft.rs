@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+    use legatio::services::{
+        config::set_data_dir,
+        draft::{remove_draft, save_draft, save_in_flight, take_draft, InFlight},
+    };
+
+    #[test]
+    fn test_drafts() {
+        let dir = tempfile::tempdir().unwrap();
+        set_data_dir(dir.path().to_path_buf());
+
+        // Nothing to save or restore yet
+        assert!(save_in_flight().is_none());
+        assert_eq!(take_draft("project").unwrap(), None);
+
+        let path = save_draft("project", "Why does\nthe parser fail?").unwrap();
+        assert_eq!(path, dir.path().join("drafts").join("project.md"));
+        assert_eq!(
+            take_draft("project").unwrap().as_deref(),
+            Some("Why does\nthe parser fail?")
+        );
+        // A draft is restored once
+        assert_eq!(take_draft("project").unwrap(), None);
+
+        // The draft of a deleted project is removed, a missing one is not an error
+        let path = save_draft("project", "Unsent").unwrap();
+        remove_draft("project").unwrap();
+        assert!(!path.exists());
+        remove_draft("project").unwrap();
+
+        // A question waiting for the model is saved until its answer is stored
+        let in_flight = InFlight::start("other", "Which lexer?");
+        let path = save_in_flight().unwrap();
+        assert!(path.ends_with("other.md"));
+        assert_eq!(
+            take_draft("other").unwrap().as_deref(),
+            Some("Which lexer?")
+        );
+        drop(in_flight);
+
+        let in_flight = InFlight::start("other", "Which lexer?");
+        drop(in_flight);
+        assert!(save_in_flight().is_none());
+    }
+}
@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use legatio::services::input::TextInput;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn type_text(input: &mut TextInput, text: &str) {
+        for c in text.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                c => KeyCode::Char(c),
+            };
+            assert!(input.handle_key(key(code)));
+        }
+    }
+
+    #[test]
+    fn test_typing() {
+        let mut input = TextInput::default();
+        assert!(input.is_blank());
+
+        type_text(&mut input, "Why does\nthe parser fail?");
+        assert_eq!(input.text(), "Why does\nthe parser fail?");
+        assert_eq!(input.cursor(), (1, 16));
+
+        // Backspace at the start of a line joins it to the previous one
+        input.handle_key(key(KeyCode::Home));
+        input.handle_key(key(KeyCode::Backspace));
+        assert_eq!(input.text(), "Why doesthe parser fail?");
+        assert_eq!(input.cursor(), (0, 8));
+        type_text(&mut input, " ");
+
+        // Enter in the middle of a line splits it
+        input.handle_key(key(KeyCode::End));
+        for _ in 0.."fail?".len() {
+            input.handle_key(key(KeyCode::Left));
+        }
+        input.handle_key(key(KeyCode::Enter));
+        assert_eq!(input.text(), "Why does the parser \nfail?");
+
+        // Delete at the end of a line joins the next one
+        input.handle_key(key(KeyCode::Up));
+        input.handle_key(key(KeyCode::End));
+        input.handle_key(key(KeyCode::Delete));
+        assert_eq!(input.text(), "Why does the parser fail?");
+    }
+
+    #[test]
+    fn test_multibyte() {
+        let mut input = TextInput::default();
+        type_text(&mut input, "café ☕");
+        input.handle_key(key(KeyCode::Left));
+        input.handle_key(key(KeyCode::Backspace));
+        assert_eq!(input.text(), "café☕");
+        input.handle_key(key(KeyCode::Left));
+        input.handle_key(key(KeyCode::Delete));
+        assert_eq!(input.text(), "caf☕");
+    }
+
+    #[test]
+    fn test_actions() {
+        let mut input = TextInput::default();
+        let control = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+
+        // Control keys and Esc are left to the key bindings
+        assert!(!input.handle_key(control('s')));
+        assert!(!input.handle_key(key(KeyCode::Esc)));
+        assert!(input.is_blank());
+
+        input.set_text("draft\nquestion");
+        assert_eq!(input.cursor(), (1, 8));
+        input.clear();
+        assert_eq!(input.text(), "");
+    }
+}
@@ -0,0 +1,176 @@
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use legatio::services::keys::{InputEvent, KeyChord, KeyConfig, KeyMap, KeyScope};
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_parse_chord() {
+        let chord = KeyChord::parse("ctrl-s").unwrap();
+        assert_eq!(chord.code, KeyCode::Char('s'));
+        assert_eq!(chord.modifiers, KeyModifiers::CONTROL);
+        assert_eq!(chord.to_string(), "Ctrl-s");
+
+        assert_eq!(
+            KeyChord::parse("Alt-Enter").unwrap().to_string(),
+            "Alt-Enter"
+        );
+        assert_eq!(KeyChord::parse("f2").unwrap().code, KeyCode::F(2));
+        assert_eq!(KeyChord::parse("space").unwrap().to_string(), "Space");
+        assert_eq!(KeyChord::parse("ctrl--").unwrap().code, KeyCode::Char('-'));
+        // Shifted letters are their capital
+        assert_eq!(
+            KeyChord::parse("shift-g").unwrap(),
+            KeyChord::parse("G").unwrap()
+        );
+        let event = KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(KeyChord::from_event(&event), KeyChord::parse("G").unwrap());
+
+        assert!(KeyChord::parse("hyper-s").is_err());
+        assert!(KeyChord::parse("f13").is_err());
+        assert!(KeyChord::parse("").is_err());
+    }
+
+    #[test]
+    fn test_default_bindings() {
+        let keys = KeyMap::default();
+        assert_eq!(
+            keys.action(KeyScope::Projects, &key('s')),
+            InputEvent::Select
+        );
+        assert_eq!(
+            keys.action(KeyScope::Ask, &key('b')),
+            InputEvent::SwitchBranch
+        );
+        assert_eq!(
+            keys.action(KeyScope::Scrolls, &key('b')),
+            InputEvent::SwitchBranch
+        );
+        assert_eq!(
+            keys.action(KeyScope::Confirm, &key('y')),
+            InputEvent::Confirm
+        );
+        assert_eq!(keys.action(KeyScope::Prompts, &key('z')), InputEvent::NoOp);
+        // Modifiers are part of the chord
+        let control_s = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert_eq!(
+            keys.action(KeyScope::Projects, &control_s),
+            InputEvent::NoOp
+        );
+
+        let help = keys.help(KeyScope::Confirm);
+        assert_eq!(help, vec!["[y] Yes", "[n] No"]);
+
+        // The question box sends, opens the editor and closes on its own keys
+        assert_eq!(
+            keys.action(KeyScope::Question, &control_s),
+            InputEvent::Submit
+        );
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(keys.action(KeyScope::Question, &esc), InputEvent::Cancel);
+        assert_eq!(keys.action(KeyScope::Question, &key('s')), InputEvent::NoOp);
+    }
+
+    #[test]
+    fn test_overrides() {
+        let mut config = KeyConfig::default();
+        config.prompts.insert("merge".into(), "ctrl-g".into());
+        config.prompts.insert("trash".into(), "none".into());
+        config.confirm.insert("confirm".into(), "enter".into());
+        let keys = KeyMap::new(&config).unwrap();
+
+        let control_g = KeyEvent::new(KeyCode::Char('g'), KeyModifiers::CONTROL);
+        assert_eq!(
+            keys.action(KeyScope::Prompts, &control_g),
+            InputEvent::Merge
+        );
+        assert_eq!(keys.action(KeyScope::Prompts, &key('g')), InputEvent::NoOp);
+        assert_eq!(keys.chord(KeyScope::Prompts, InputEvent::Trash), None);
+        // Other states keep their defaults
+        assert_eq!(keys.action(KeyScope::Ask, &key('a')), InputEvent::AskModel);
+
+        let help = keys.help(KeyScope::Prompts);
+        assert!(help.contains(&"[Ctrl-g] Merge Two Branches".to_string()));
+        assert!(!help.iter().any(|h| h.ends_with("Trash")));
+        assert_eq!(keys.help(KeyScope::Confirm)[0], "[Enter] Yes");
+    }
+
+    #[test]
+    fn test_invalid_bindings() {
+        let invalid = |scope: &str, action: &str, chord: &str| {
+            let mut config = KeyConfig::default();
+            let table = match scope {
+                "projects" => &mut config.projects,
+                "prompts" => &mut config.prompts,
+                _ => &mut config.ask,
+            };
+            table.insert(action.into(), chord.into());
+            KeyMap::new(&config).unwrap_err().to_string()
+        };
+
+        // Two actions on one key
+        let error = invalid("projects", "new", "s");
+        assert!(error.contains("'s' is bound to both select and new"));
+        // Keys of the list or the tree
+        assert!(invalid("prompts", "merge", "j").contains("already used"));
+        assert!(invalid("prompts", "merge", "enter").contains("already used"));
+        assert!(invalid("ask", "merge", "ctrl-c").contains("Unknown action"));
+        assert!(invalid("ask", "quit", "ctrl-c").contains("already used"));
+        // Unknown actions, actions of another state and unknown keys
+        assert!(invalid("ask", "fly", "z").contains("Unknown action 'fly'"));
+        assert!(invalid("ask", "trash", "t").contains("Unknown action 'trash'"));
+        assert!(invalid("projects", "quit", "ctrl-shift-f99").contains("unknown key"));
+
+        // Keys typed in the question box
+        let mut config = KeyConfig::default();
+        config.question.insert("submit".into(), "S".into());
+        assert!(KeyMap::new(&config)
+            .unwrap_err()
+            .to_string()
+            .contains("already used"));
+        config.question.insert("submit".into(), "alt-enter".into());
+        assert!(KeyMap::new(&config).is_ok());
+    }
+
+    #[test]
+    fn test_actions() {
+        let keys = KeyMap::default();
+        let actions = keys.actions(KeyScope::Scrolls);
+        assert_eq!(actions[0].0, InputEvent::Help);
+        assert_eq!(actions[0].1, KeyChord::parse("?").ok());
+        assert_eq!(actions[1].0, InputEvent::Palette);
+
+        // Actions without a key are listed but left out of the help text
+        let refresh = actions
+            .iter()
+            .find(|(action, ..)| *action == InputEvent::Refresh)
+            .unwrap();
+        assert_eq!(refresh.1, None);
+        assert!(!keys
+            .help(KeyScope::Scrolls)
+            .iter()
+            .any(|h| h.contains("Reload")));
+
+        let mut config = KeyConfig::default();
+        config.scrolls.insert("refresh".into(), "ctrl-r".into());
+        let keys = KeyMap::new(&config).unwrap();
+        let control_r = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert_eq!(
+            keys.action(KeyScope::Scrolls, &control_r),
+            InputEvent::Refresh
+        );
+        assert_eq!(keys.actions(KeyScope::Scrolls).len(), actions.len());
+    }
+
+    #[test]
+    fn test_key_config_toml() {
+        let config: KeyConfig = toml::from_str("[prompts]\nmerge = \"ctrl-g\"\n").unwrap();
+        assert_eq!(config.prompts["merge"], "ctrl-g");
+        assert!(config.projects.is_empty());
+        assert!(!config.is_empty());
+        assert!(KeyConfig::default().is_empty());
+    }
+}
@@ -0,0 +1,192 @@
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{
+        KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    };
+    use legatio::services::list::{ListAction, SelectItem, SelectList};
+    use ratatui::{
+        backend::TestBackend,
+        style::Style,
+        widgets::{Block, Borders},
+        Terminal,
+    };
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    fn items(names: &[&str]) -> Vec<SelectItem> {
+        names
+            .iter()
+            .map(|name| SelectItem::new(&format!("id-{}", name), name))
+            .collect()
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut list = SelectList::new(items(&["alpha", "beta", "gamma"]));
+        assert_eq!(list.selected_id(), Some("id-alpha"));
+
+        assert_eq!(
+            list.handle_key(key(KeyCode::Char('j'))),
+            ListAction::Handled
+        );
+        assert_eq!(list.selected_id(), Some("id-beta"));
+        list.handle_key(key(KeyCode::Down));
+        list.handle_key(key(KeyCode::Down));
+        // The selection stops at the last item
+        assert_eq!(list.selected_id(), Some("id-gamma"));
+
+        list.handle_key(key(KeyCode::Char('k')));
+        assert_eq!(list.selected_id(), Some("id-beta"));
+        list.handle_key(key(KeyCode::Home));
+        assert_eq!(list.selected_id(), Some("id-alpha"));
+        list.handle_key(key(KeyCode::End));
+        assert_eq!(list.selected_id(), Some("id-gamma"));
+
+        assert_eq!(
+            list.handle_key(key(KeyCode::Enter)),
+            ListAction::Select(String::from("id-gamma"))
+        );
+        // Other keys are left to the application
+        assert_eq!(
+            list.handle_key(key(KeyCode::Char('s'))),
+            ListAction::Ignored
+        );
+    }
+
+    #[test]
+    fn test_mouse() {
+        let mut list = SelectList::new(items(&["alpha", "beta", "gamma"]));
+        let mut terminal = Terminal::new(TestBackend::new(20, 10)).unwrap();
+        terminal
+            .draw(|f| {
+                let block = Block::default().borders(Borders::ALL);
+                list.render(f, f.area(), block, Style::default(), Style::default())
+            })
+            .unwrap();
+
+        // A click highlights the item under the pointer, a second click opens it
+        let click = mouse(MouseEventKind::Down(MouseButton::Left), 3, 2);
+        assert_eq!(list.handle_mouse(click), ListAction::Handled);
+        assert_eq!(list.selected_id(), Some("id-beta"));
+        assert_eq!(
+            list.handle_mouse(click),
+            ListAction::Select(String::from("id-beta"))
+        );
+
+        // The wheel moves the selection
+        list.handle_mouse(mouse(MouseEventKind::ScrollDown, 3, 2));
+        assert_eq!(list.selected_id(), Some("id-gamma"));
+        list.handle_mouse(mouse(MouseEventKind::ScrollUp, 3, 8));
+        assert_eq!(list.selected_id(), Some("id-beta"));
+
+        // Clicks under the last item or on the border do nothing
+        let below = mouse(MouseEventKind::Down(MouseButton::Left), 3, 6);
+        assert_eq!(list.handle_mouse(below), ListAction::Handled);
+        assert_eq!(list.selected_id(), Some("id-beta"));
+        let border = mouse(MouseEventKind::Down(MouseButton::Left), 0, 1);
+        assert_eq!(list.handle_mouse(border), ListAction::Ignored);
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut list = SelectList::new(items(&["alpha", "beta", "alphabet"]));
+
+        list.handle_key(key(KeyCode::Char('/')));
+        assert!(list.is_filtering());
+        for c in "alp".chars() {
+            list.handle_key(key(KeyCode::Char(c)));
+        }
+        assert_eq!(list.filter(), "alp");
+        assert_eq!(list.visible_ids(), vec!["id-alpha", "id-alphabet"]);
+
+        // While filtering, j and k are typed into the filter
+        list.handle_key(key(KeyCode::Char('j')));
+        assert_eq!(list.filter(), "alpj");
+        assert!(list.visible_ids().is_empty());
+        assert_eq!(list.selected_id(), None);
+
+        list.handle_key(key(KeyCode::Backspace));
+        assert_eq!(list.visible_ids().len(), 2);
+
+        // Esc clears the filter in a pane
+        assert_eq!(list.handle_key(key(KeyCode::Esc)), ListAction::Handled);
+        assert!(!list.is_filtering());
+        assert_eq!(list.filter(), "");
+        assert_eq!(list.visible_ids().len(), 3);
+    }
+
+    #[test]
+    fn test_set_items_keeps_selection() {
+        let mut list = SelectList::new(items(&["alpha", "beta", "gamma"]));
+        assert!(list.select_id("id-beta"));
+
+        // An item added above the selection does not move it
+        list.set_items(items(&["delta", "alpha", "beta", "gamma"]));
+        assert_eq!(list.selected_id(), Some("id-beta"));
+
+        // Once the item is gone the first one is highlighted
+        list.set_items(items(&["alpha", "gamma"]));
+        assert_eq!(list.selected_id(), Some("id-alpha"));
+
+        assert!(!list.select_id("id-missing"));
+        list.set_items(vec![]);
+        assert_eq!(list.selected_id(), None);
+        assert_eq!(list.handle_key(key(KeyCode::Enter)), ListAction::Handled);
+    }
+
+    #[test]
+    fn test_picker() {
+        let mut list = SelectList::picker(items(&["alpha", "beta"]));
+        assert!(list.is_filtering());
+
+        // A picker filters as soon as the user types
+        list.handle_key(key(KeyCode::Char('b')));
+        assert_eq!(list.visible_ids(), vec!["id-beta"]);
+        assert_eq!(
+            list.handle_key(key(KeyCode::Enter)),
+            ListAction::Select(String::from("id-beta"))
+        );
+        assert_eq!(list.handle_key(key(KeyCode::Esc)), ListAction::Cancel);
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut list = SelectList::picker(items(&["main.rs", "lib.rs"]));
+        list.set_filter("lib");
+        assert_eq!(list.visible_ids(), vec!["id-lib.rs"]);
+
+        // Items fed later are ranked with the matches already shown
+        list.extend(items(&["README.md", "src/lib.rs", "liberty.txt"]));
+        let visible = list.visible_ids();
+        assert_eq!(visible.len(), 3);
+        assert!(!visible.contains(&"id-README.md"));
+        assert_eq!(list.selected_id(), Some("id-lib.rs"));
+
+        // The same ranking as matching every item at once
+        let mut all = SelectList::picker(items(&[
+            "main.rs",
+            "lib.rs",
+            "README.md",
+            "src/lib.rs",
+            "liberty.txt",
+        ]));
+        all.set_filter("lib");
+        assert_eq!(all.visible_ids(), visible);
+
+        list.set_filter("");
+        list.extend(items(&["Cargo.toml"]));
+        assert_eq!(list.visible_ids().len(), 6);
+        assert_eq!(list.visible_ids()[5], "id-Cargo.toml");
+    }
+}
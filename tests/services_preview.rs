@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+    use legatio::{
+        services::preview::{markdown_lines, CodeColors, Preview},
+        utils::structs::Prompt,
+    };
+    use ratatui::{
+        backend::TestBackend,
+        style::{Color, Modifier, Style},
+        widgets::{Block, Borders},
+        Terminal,
+    };
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn texts(lines: &[ratatui::text::Line]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_markdown_lines() {
+        let lines = markdown_lines(
+            "# Title\n\nSome *soft*\nwrapped **text**.\n\n- one\n- two\n  1. nested\n\n> quoted\n",
+            &CodeColors::default(),
+        );
+        assert_eq!(
+            texts(&lines),
+            vec![
+                "# Title",
+                "",
+                "Some soft wrapped text.",
+                "",
+                "•  one",
+                "•  two",
+                "   1. nested",
+                "",
+                "│ quoted",
+            ]
+        );
+
+        // Headings and emphasis keep their style
+        assert!(lines[0].spans[0]
+            .style
+            .add_modifier
+            .contains(Modifier::BOLD));
+        let soft = lines[2].spans.iter().find(|s| s.content == "soft").unwrap();
+        assert!(soft.style.add_modifier.contains(Modifier::ITALIC));
+    }
+
+    #[test]
+    fn test_markdown_code_block() {
+        let lines = markdown_lines(
+            "Run:\n\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n",
+            &CodeColors::default(),
+        );
+        assert_eq!(
+            texts(&lines),
+            vec![
+                "Run:",
+                "",
+                "  fn main() {",
+                "      println!(\"hi\");",
+                "  }",
+            ]
+        );
+        // The code is split into highlighted spans
+        assert!(lines[2].spans.len() > 2);
+    }
+
+    #[test]
+    fn test_markdown_diff() {
+        let colors = CodeColors {
+            code: Color::Cyan,
+            diff_add: Color::Blue,
+            diff_remove: Color::Magenta,
+        };
+        let lines = markdown_lines(
+            "Use `parse`:\n\n```diff\n-let a = 1;\n+let a = 2;\n let b = 3;\n```\n",
+            &colors,
+        );
+        let code = lines[0]
+            .spans
+            .iter()
+            .find(|s| s.content == "parse")
+            .unwrap();
+        assert_eq!(code.style.fg, Some(Color::Cyan));
+        // Added and removed lines take the colors of the theme
+        let color = |line: &ratatui::text::Line| line.spans.last().unwrap().style.fg;
+        assert_eq!(color(&lines[2]), Some(Color::Magenta));
+        assert_eq!(color(&lines[3]), Some(Color::Blue));
+        assert_ne!(color(&lines[4]), Some(Color::Blue));
+    }
+
+    #[test]
+    fn test_preview_search() {
+        let mut prompt = Prompt::new("project", "Which *parser*?", "", "project");
+        prompt.output = (0..50)
+            .map(|i| format!("Line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let mut preview = Preview::default();
+        preview.set_prompt(Some(&prompt));
+        assert_eq!(preview.lines()[0].to_string(), " Prompt ");
+        assert_eq!(preview.scroll(), 0);
+
+        // Ctrl-d pages down, Ctrl-u back up
+        preview.handle_key(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL));
+        assert!(preview.scroll() > 0);
+        preview.handle_key(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        assert_eq!(preview.scroll(), 0);
+
+        // Searching takes every key and jumps to the first match
+        assert!(preview.handle_key(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL)));
+        for c in "line 4".chars() {
+            assert!(preview.handle_key(key(KeyCode::Char(c))));
+        }
+        preview.handle_key(key(KeyCode::Enter));
+        assert!(!preview.is_searching());
+        let matches = preview.matches().to_vec();
+        assert_eq!(matches.len(), 11);
+        assert_eq!(preview.scroll(), matches[0]);
+
+        // n and N cycle through the matches
+        preview.handle_key(key(KeyCode::Char('n')));
+        assert_eq!(preview.scroll(), matches[1]);
+        preview.handle_key(key(KeyCode::Char('N')));
+        preview.handle_key(key(KeyCode::Char('N')));
+        assert_eq!(preview.scroll(), matches[10]);
+
+        // Another prompt starts at the top without matches
+        preview.set_prompt(Some(&Prompt::new("project", "Other", "", "project")));
+        assert!(preview.matches().is_empty());
+        assert!(!preview.handle_key(key(KeyCode::Char('n'))));
+    }
+
+    #[test]
+    fn test_preview_wheel() {
+        let mut prompt = Prompt::new("project", "Which parser?", "", "project");
+        prompt.output = (0..50)
+            .map(|i| format!("Line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let mut preview = Preview::default();
+        preview.set_prompt(Some(&prompt));
+        let mut terminal = Terminal::new(TestBackend::new(30, 12)).unwrap();
+        terminal
+            .draw(|f| {
+                let block = Block::default().borders(Borders::ALL);
+                preview.render(f, f.area(), block, Style::default(), Style::default())
+            })
+            .unwrap();
+
+        let wheel = |kind, column, row| MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        };
+        assert!(preview.handle_mouse(wheel(MouseEventKind::ScrollDown, 5, 5)));
+        assert_eq!(preview.scroll(), 3);
+        assert!(preview.handle_mouse(wheel(MouseEventKind::ScrollUp, 5, 5)));
+        assert_eq!(preview.scroll(), 0);
+        // Outside the preview the wheel is left to the application
+        assert!(!preview.handle_mouse(wheel(MouseEventKind::ScrollDown, 5, 20)));
+        assert_eq!(preview.scroll(), 0);
+    }
+}
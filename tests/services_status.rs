@@ -0,0 +1,57 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use legatio::{
+        services::status::{Level, StatusBar, TOAST_DURATION},
+        utils::error::AppError,
+    };
+    use ratatui::style::Color;
+
+    #[test]
+    fn test_notifications() {
+        let mut status = StatusBar::default();
+        assert_eq!(status.line("Projects", Color::Red).to_string(), " Projects");
+
+        status.info("No directory selected");
+        let err = anyhow::Error::from(AppError::FileError(String::from("scroll.rs is missing")));
+        status.error(&err);
+        status.error(&err);
+
+        // Newest first, repeats are counted once
+        let log = status.log();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].level, Level::Error);
+        assert_eq!(log[0].count, 2);
+        assert_eq!(
+            log[0].message,
+            "File operation failed: scroll.rs is missing"
+        );
+        assert_eq!(log[1].message, "No directory selected");
+        assert!(log[0].log_line(Color::Red).to_string().ends_with("(x2)"));
+
+        assert_eq!(
+            status.line("Projects", Color::Red).to_string(),
+            " error: File operation failed: scroll.rs is missing (x2)"
+        );
+    }
+
+    #[test]
+    fn test_toast_expiry() {
+        let mut status = StatusBar::default();
+        let now = Instant::now();
+        assert!(!status.expire(now));
+
+        status.warn("Could not create legatio.md");
+        assert!(status.toast(Instant::now()).is_some());
+        assert!(!status.expire(Instant::now()));
+
+        // Once hidden, the toast stays in the log only
+        let later = Instant::now() + TOAST_DURATION;
+        assert!(status.toast(later).is_none());
+        assert!(status.expire(later));
+        assert!(!status.expire(later));
+        assert_eq!(status.line("Scrolls", Color::Red).to_string(), " Scrolls");
+        assert_eq!(status.log().len(), 1);
+    }
+}
@@ -0,0 +1,195 @@
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use legatio::{
+        core::tag::ProjectMarks,
+        services::{list::ListAction, tree::PromptTree},
+        utils::structs::Prompt,
+    };
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn prompt(id: &str, prev: &str, content: &str) -> Prompt {
+        Prompt {
+            prompt_id: id.to_string(),
+            project_id: "project".to_string(),
+            content: content.to_string(),
+            output: "output".to_string(),
+            prev_prompt_id: prev.to_string(),
+        }
+    }
+
+    /// root -> (a -> (a1, a2), b), plus a second root
+    fn create_tree() -> PromptTree {
+        let mut tree = PromptTree::default();
+        tree.set_prompts(
+            vec![
+                prompt("root", "project", "First question"),
+                prompt("a", "root", "Try the parser\nwith two lines"),
+                prompt("b", "root", "Try the lexer"),
+                prompt("a1", "a", "Parser follow-up"),
+                prompt("a2", "a", "Parser alternative"),
+                prompt("other", "project", "Unrelated question"),
+            ],
+            ProjectMarks::default(),
+        );
+        tree
+    }
+
+    fn ids(tree: &PromptTree) -> Vec<&str> {
+        tree.rows().iter().map(|r| r.prompt_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_rows() {
+        let tree = create_tree();
+        assert_eq!(ids(&tree), vec!["root", "a", "a1", "a2", "b", "other"]);
+
+        let rows = tree.rows();
+        assert_eq!(rows[0].depth, 0);
+        assert_eq!(rows[0].children, 2);
+        assert_eq!(rows[1].depth, 1);
+        assert_eq!(rows[1].children, 2);
+        assert_eq!(rows[2].depth, 2);
+        assert_eq!(rows[2].children, 0);
+        assert_eq!(rows[5].depth, 0);
+    }
+
+    #[test]
+    fn test_orphans() {
+        // Prompts left without their parent are shown as roots with their replies
+        let mut tree = PromptTree::default();
+        tree.set_prompts(
+            vec![
+                prompt("root", "project", "First question"),
+                prompt("orphan", "missing", "Lost question"),
+                prompt("reply", "orphan", "Lost follow-up"),
+            ],
+            ProjectMarks::default(),
+        );
+        assert_eq!(ids(&tree), vec!["root", "orphan", "reply"]);
+        assert_eq!(tree.rows()[1].depth, 0);
+        assert_eq!(tree.rows()[2].depth, 1);
+
+        tree.set_current(Some("reply"));
+        assert!(tree.rows()[1].on_path);
+        assert!(!tree.rows()[0].on_path);
+        tree.handle_key(key(KeyCode::Left));
+        tree.handle_key(key(KeyCode::Left));
+        assert_eq!(tree.selected_id(), Some("orphan"));
+    }
+
+    #[test]
+    fn test_collapse_and_expand() {
+        let mut tree = create_tree();
+        assert!(tree.select_id("a"));
+
+        // Left collapses the subtree, then goes to the parent
+        tree.handle_key(key(KeyCode::Left));
+        assert_eq!(ids(&tree), vec!["root", "a", "b", "other"]);
+        let row = &tree.rows()[1];
+        assert!(row.collapsed);
+        assert_eq!(row.hidden, 2);
+        assert_eq!(tree.selected_id(), Some("a"));
+        tree.handle_key(key(KeyCode::Left));
+        assert_eq!(tree.selected_id(), Some("root"));
+
+        // Selecting a hidden prompt reveals it
+        assert!(tree.select_id("a2"));
+        assert_eq!(ids(&tree), vec!["root", "a", "a1", "a2", "b", "other"]);
+
+        // Space folds the whole tree of a root
+        tree.select_id("root");
+        tree.handle_key(key(KeyCode::Char(' ')));
+        assert_eq!(ids(&tree), vec!["root", "other"]);
+        assert_eq!(tree.rows()[0].hidden, 4);
+
+        // Right expands, then moves to the first reply
+        tree.handle_key(key(KeyCode::Right));
+        assert_eq!(ids(&tree).len(), 6);
+        tree.handle_key(key(KeyCode::Right));
+        assert_eq!(tree.selected_id(), Some("a"));
+    }
+
+    #[test]
+    fn test_siblings() {
+        let mut tree = create_tree();
+        tree.select_id("a");
+
+        tree.handle_key(key(KeyCode::Char('J')));
+        assert_eq!(tree.selected_id(), Some("b"));
+        // b is the last reply of root
+        tree.handle_key(key(KeyCode::Char('J')));
+        assert_eq!(tree.selected_id(), Some("b"));
+        tree.handle_key(key(KeyCode::Char('K')));
+        assert_eq!(tree.selected_id(), Some("a"));
+
+        tree.handle_key(key(KeyCode::Char('j')));
+        assert_eq!(tree.selected_id(), Some("a1"));
+        assert_eq!(
+            tree.handle_key(key(KeyCode::Enter)),
+            ListAction::Select(String::from("a1"))
+        );
+    }
+
+    #[test]
+    fn test_current_path() {
+        let mut tree = create_tree();
+        tree.select_id("root");
+        tree.handle_key(key(KeyCode::Char(' ')));
+
+        // The current prompt is revealed, selected and its chain highlighted
+        tree.set_current(Some("a2"));
+        assert_eq!(tree.selected_id(), Some("a2"));
+        let on_path: Vec<&str> = tree
+            .rows()
+            .iter()
+            .filter(|r| r.on_path)
+            .map(|r| r.prompt_id.as_str())
+            .collect();
+        assert_eq!(on_path, vec!["root", "a", "a2"]);
+
+        // Refreshing the prompts keeps the selection
+        tree.handle_key(key(KeyCode::Char('k')));
+        let mut prompts = vec![prompt("new", "b", "Lexer follow-up")];
+        prompts.extend(tree.rows().iter().map(|r| {
+            let prev = match r.prompt_id.as_str() {
+                "root" | "other" => "project",
+                "a" | "b" => "root",
+                _ => "a",
+            };
+            prompt(&r.prompt_id, prev, "content")
+        }));
+        tree.set_prompts(prompts, ProjectMarks::default());
+        assert_eq!(tree.selected_id(), Some("a1"));
+        assert_eq!(
+            tree.rows()
+                .iter()
+                .find(|r| r.prompt_id == "b")
+                .unwrap()
+                .children,
+            1
+        );
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut tree = create_tree();
+        tree.select_id("a");
+        tree.handle_key(key(KeyCode::Left));
+
+        tree.handle_key(key(KeyCode::Char('/')));
+        for c in "follow".chars() {
+            tree.handle_key(key(KeyCode::Char(c)));
+        }
+        // Matches are shown with their ancestors, even inside collapsed subtrees
+        assert_eq!(ids(&tree), vec!["root", "a", "a1"]);
+        assert_eq!(tree.selected_id(), Some("root"));
+
+        tree.handle_key(key(KeyCode::Esc));
+        assert!(!tree.is_filtering());
+        assert_eq!(ids(&tree), vec!["root", "a", "b", "other"]);
+    }
+}
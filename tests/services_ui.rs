@@ -5,7 +5,12 @@ mod tests {
         services::ui::*,
         utils::structs::{Project, Prompt},
     };
+    use ratatui::style::Color;
     use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+    use std::{
+        fs::{self, File},
+        time::{Duration, SystemTime},
+    };
 
     /// Helper function to create a mock SQLite in-memory database and connection pool.
     async fn create_test_pool() -> SqlitePool {
@@ -36,6 +41,7 @@ mod tests {
         let project = Project {
             project_id: "project1".to_string(),
             project_path: "/fake/project/path".to_string(),
+            ..Default::default()
         };
 
         // Act: Fetch scrolls for the project
@@ -48,67 +54,6 @@ mod tests {
         assert_eq!(scrolls[0], "scroll_test");
     }
 
-    // Testing helper_print recursive formatting with multiple nested prompts
-    #[test]
-    fn test_helper_print() {
-        // Arrange: Create a chain of nested prompts
-        let prompt1 = Prompt::new("project1", "Root Prompt", "Root Output", "root");
-        let prompt2 = Prompt::new(
-            "project1",
-            "Child Prompt",
-            "Child Output",
-            &prompt1.prompt_id,
-        );
-        let prompt3 = Prompt::new(
-            "project1",
-            "Grandchild Prompt",
-            "Grandchild Output",
-            &prompt2.prompt_id,
-        );
-        let prompts = vec![prompt1.clone(), prompt2.clone(), prompt3.clone()];
-
-        // Act: Format the prompts recursively
-        let result = helper_print(&prompts, &prompt1, "  |");
-
-        // Assert: Verify the formatting is correct at each level of recursion
-        assert!(result.is_ok());
-        let formatted_prompts = result.unwrap();
-        assert!(formatted_prompts.contains(&"  |> Prompt: Root Prompt".to_string()));
-        assert!(formatted_prompts.contains(&"  |  |> Prompt: Child Prompt".to_string()));
-        assert!(formatted_prompts.contains(&"  |  |  |> Prompt: Grandchild Prompt".to_string()));
-    }
-
-    // Testing recursive prompt formatting via usr_prompts() function
-    #[tokio::test]
-    async fn test_usr_prompts() {
-        // Arrange: Create mock prompts for a project
-        let prompt1 = Prompt::new("project1", "Root Prompt", "Root Output", "project1");
-        let prompt2 = Prompt::new(
-            "project1",
-            "Child Prompt",
-            "Child Output",
-            &prompt1.prompt_id,
-        );
-        let prompt3 = Prompt::new(
-            "project1",
-            "Grandchild Prompt",
-            "Grandchild Output",
-            &prompt2.prompt_id,
-        );
-        let prompts = vec![prompt1.clone(), prompt2.clone(), prompt3.clone()];
-
-        // Act: Format all prompts recursively
-        let formatted_prompts = usr_prompts(&prompts).await;
-
-        // Assert: Verify all prompts are correctly formatted and included
-        assert!(formatted_prompts.is_ok());
-        let formatted = formatted_prompts.unwrap();
-        assert_eq!(formatted.len(), 9); // "Indentation + Prompt + Output" for each prompt
-        assert!(formatted.contains(&"  |> Prompt: Root Prompt".to_string()));
-        assert!(formatted.contains(&"  |  |> Prompt: Child Prompt".to_string()));
-        assert!(formatted.contains(&"  |  |  |> Prompt: Grandchild Prompt".to_string()));
-    }
-
     #[test]
     fn test_usr_prompt_chain() {
         // Arrange: Create a chain of prompts
@@ -173,4 +118,103 @@ mod tests {
         assert_eq!(retrieved_prompt.output, "Test Output");
         assert_eq!(retrieved_prompt.prev_prompt_id, "root");
     }
+
+    #[test]
+    fn test_parse_theme_color() {
+        assert_eq!(
+            parse_theme_color("#1a5fb4").unwrap(),
+            Color::Rgb(0x1a, 0x5f, 0xb4)
+        );
+        assert_eq!(parse_theme_color("#fa0").unwrap(), Color::Rgb(255, 170, 0));
+        assert_eq!(parse_theme_color("lightblue").unwrap(), Color::LightBlue);
+        assert_eq!(parse_theme_color("dark gray").unwrap(), Color::DarkGray);
+        assert_eq!(parse_theme_color("208").unwrap(), Color::Indexed(208));
+        assert_eq!(parse_theme_color("reset").unwrap(), Color::Reset);
+
+        // Errors say what was expected
+        let err = parse_theme_color("#ff").unwrap_err().to_string();
+        assert!(err.contains("'#ff'") && err.contains("not 2"), "{}", err);
+        let err = parse_theme_color("#gg0000").unwrap_err().to_string();
+        assert!(err.contains("0-9 and a-f"), "{}", err);
+        let err = parse_theme_color("blurple").unwrap_err().to_string();
+        assert!(err.contains("color name"), "{}", err);
+    }
+
+    #[test]
+    fn test_theme_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("themes.toml");
+        let write = |content: &str, age: u64| {
+            fs::write(&path, content).unwrap();
+            // Distinct modification times, whatever the resolution of the file system
+            let modified = SystemTime::now() - Duration::from_secs(age);
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        };
+
+        write(
+            "[[themes]]\nname = \"Night\"\nbackground = \"#000000\"\nprimary = \"white\"\n\
+             secondary = \"#ccc\"\naccent = \"#e6b450\"\nerror = \"magenta\"\n\n\
+             [[themes]]\nname = \"Broken\"\nbackground = \"#\"\nprimary = \"#fff\"\n\
+             secondary = \"#fff\"\naccent = \"#90\"\n",
+            20,
+        );
+        let mut themes = ThemeCache::default();
+        assert!(themes.refresh(&path).unwrap());
+        assert!(!themes.refresh(&path).unwrap());
+        assert_eq!(themes.names(), vec!["Broken", "Night"]);
+
+        // Missing colors default to the main ones
+        let night = themes.colors("Night").unwrap();
+        assert_eq!(night.border, Color::White);
+        assert_eq!(night.selection, Color::Rgb(0xe6, 0xb4, 0x50));
+        assert_eq!(night.error, Color::Magenta);
+        assert_eq!(night.diff_add, Color::Green);
+
+        // A broken theme names the color at fault and leaves the others usable
+        let err = themes.colors("Broken").unwrap_err().to_string();
+        assert!(
+            err.contains("background color '#' in theme 'Broken'"),
+            "{}",
+            err
+        );
+        let err = themes.colors("Day").unwrap_err().to_string();
+        assert!(err.contains("available themes: Broken, Night"), "{}", err);
+
+        // A change is picked up, a broken file keeps the themes read before
+        write(
+            "[[themes]]\nname = \"Night\"\nbackgrond = \"#000000\"\n",
+            10,
+        );
+        let err = themes.refresh(&path).unwrap_err().to_string();
+        assert!(err.contains("backgrond"), "{}", err);
+        assert!(!themes.refresh(&path).unwrap());
+        assert_eq!(themes.colors("Night").unwrap(), night);
+
+        write(
+            "[[themes]]\nname = \"Night\"\nbackground = \"#000000\"\nprimary = \"red\"\n\
+             secondary = \"#ccc\"\naccent = \"#e6b450\"\n",
+            0,
+        );
+        assert!(themes.refresh(&path).unwrap());
+        assert_eq!(themes.colors("Night").unwrap().primary, Color::Red);
+    }
+
+    #[test]
+    fn test_default_themes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("themes.toml");
+        fs::write(&path, include_str!("../defaults/themes.toml")).unwrap();
+
+        let mut themes = ThemeCache::default();
+        themes.refresh(&path).unwrap();
+        assert!(themes.names().contains(&"Bashtop Adapta"));
+        for name in themes.names() {
+            assert!(themes.colors(name).is_ok(), "{}", name);
+        }
+    }
 }
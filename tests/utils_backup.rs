@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod tests {
+    use legatio::utils::{
+        backup::{
+            backup_database, create_snapshot, get_backup_dir, list_snapshots, prune_snapshots,
+            restore_snapshot,
+        },
+        db_utils::open_db_pool,
+        logger::initialize_logger,
+    };
+    use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+    use sqlx::{ConnectOptions, Connection};
+    use std::{fs, path::Path};
+
+    async fn create_test_connection(db_path: &Path) -> SqliteConnection {
+        let _ = initialize_logger().await;
+
+        let mut conn = SqliteConnectOptions::new()
+            .filename(db_path)
+            .create_if_missing(true)
+            .connect()
+            .await
+            .expect("Failed to create database connection");
+
+        sqlx::query("CREATE TABLE IF NOT EXISTS projects (project_id TEXT, project_path TEXT);")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        conn
+    }
+
+    async fn add_project(conn: &mut SqliteConnection, id: &str) {
+        sqlx::query("INSERT INTO projects VALUES ($1, '/tmp');")
+            .bind(id)
+            .execute(conn)
+            .await
+            .unwrap();
+    }
+
+    async fn count_projects(db_path: &Path) -> i64 {
+        let mut conn = create_test_connection(db_path).await;
+        let count = sqlx::query_scalar("SELECT COUNT(*) FROM projects;")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        conn.close().await.unwrap();
+        count
+    }
+
+    #[tokio::test]
+    async fn test_create_and_list_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        let backup_dir = dir.path().join("backups");
+        fs::create_dir(&backup_dir).unwrap();
+        // Unrelated files are not snapshots
+        fs::write(backup_dir.join("notes.txt"), "notes").unwrap();
+
+        let mut conn = create_test_connection(&dir.path().join("legatio.db")).await;
+        add_project(&mut conn, "project_1").await;
+        let first = create_snapshot(&mut conn, &backup_dir, "startup")
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        add_project(&mut conn, "project_2").await;
+        let second = create_snapshot(&mut conn, &backup_dir, "delete-project")
+            .await
+            .unwrap();
+
+        let snapshots = list_snapshots(&backup_dir).await.unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[0].path, second);
+        assert_eq!(snapshots[0].reason, "delete-project");
+        assert_eq!(snapshots[0].projects, Some(2));
+        assert_eq!(snapshots[1].path, first);
+        assert_eq!(snapshots[1].reason, "startup");
+        assert_eq!(snapshots[1].projects, Some(1));
+        assert!(snapshots[0].created_at > snapshots[1].created_at);
+    }
+
+    #[tokio::test]
+    async fn test_prune_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        let backup_dir = dir.path().join("backups");
+        fs::create_dir(&backup_dir).unwrap();
+
+        let mut conn = create_test_connection(&dir.path().join("legatio.db")).await;
+        let mut created = vec![];
+        for _ in 0..4 {
+            created.push(
+                create_snapshot(&mut conn, &backup_dir, "startup")
+                    .await
+                    .unwrap(),
+            );
+            // Snapshot names are stamped to the millisecond
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        assert_eq!(prune_snapshots(&backup_dir, 2).await.unwrap(), 2);
+        let kept: Vec<_> = list_snapshots(&backup_dir)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| s.path)
+            .collect();
+        assert_eq!(kept, vec![created[3].clone(), created[2].clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_restore_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let backup_dir = dir.path().join("backups");
+        fs::create_dir(&backup_dir).unwrap();
+        let db_path = dir.path().join("legatio.db");
+
+        let mut conn = create_test_connection(&db_path).await;
+        add_project(&mut conn, "project_1").await;
+        let snapshot = create_snapshot(&mut conn, &backup_dir, "startup")
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        add_project(&mut conn, "project_2").await;
+        add_project(&mut conn, "project_3").await;
+        conn.close().await.unwrap();
+
+        let previous = restore_snapshot(&snapshot, &db_path, &backup_dir)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(count_projects(&db_path).await, 1);
+
+        // The replaced database was kept and can be restored in turn
+        let snapshots = list_snapshots(&backup_dir).await.unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[0].path, previous);
+        assert_eq!(snapshots[0].reason, "before-restore");
+        assert_eq!(snapshots[0].projects, Some(3));
+
+        // Only snapshots can be restored
+        let other = dir.path().join("other.db");
+        fs::write(&other, "").unwrap();
+        assert!(restore_snapshot(&other, &db_path, &backup_dir)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_backup_database() {
+        let _ = initialize_logger().await;
+        let dir = tempfile::tempdir().unwrap();
+        let db_file = dir.path().join("work.db");
+        let pool = open_db_pool(&db_file, 5).await.unwrap();
+
+        // Disabled backups write nothing
+        assert!(backup_database(&pool, "startup", 0)
+            .await
+            .unwrap()
+            .is_none());
+
+        for _ in 0..3 {
+            backup_database(&pool, "startup", 2).await.unwrap().unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        // Snapshots are kept next to the database they belong to
+        let backup_dir = get_backup_dir(&db_file).unwrap();
+        assert_eq!(backup_dir, dir.path().join("backups").join("work"));
+        let snapshots = list_snapshots(&backup_dir).await.unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[0].projects, Some(0));
+    }
+}
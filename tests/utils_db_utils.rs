@@ -0,0 +1,264 @@
+#[cfg(test)]
+mod tests {
+    use legatio::{
+        core::{
+            project::{delete_project, get_projects},
+            prompt::get_prompts,
+            scroll::get_scrolls,
+        },
+        utils::{
+            backup::{get_backup_dir, list_snapshots},
+            db_utils::{
+                count_quarantined, database_file, list_databases, move_legacy_database,
+                open_db_pool, run_migrations,
+            },
+            logger::initialize_logger,
+        },
+    };
+    use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqlitePoolOptions};
+    use sqlx::{ConnectOptions, Connection, SqlitePool};
+    use std::fs;
+
+    fn connect_options(dir: &tempfile::TempDir) -> SqliteConnectOptions {
+        SqliteConnectOptions::new()
+            .filename(dir.path().join("legatio.db"))
+            .create_if_missing(true)
+    }
+
+    async fn create_test_connection(dir: &tempfile::TempDir) -> SqliteConnection {
+        let _ = initialize_logger().await;
+
+        connect_options(dir)
+            .connect()
+            .await
+            .expect("Failed to create database connection")
+    }
+
+    async fn create_test_pool(dir: &tempfile::TempDir) -> SqlitePool {
+        SqlitePoolOptions::new()
+            .connect_with(connect_options(dir))
+            .await
+            .expect("Failed to create database connection pool")
+    }
+
+    async fn user_version(conn: &mut SqliteConnection) -> i64 {
+        sqlx::query_scalar("PRAGMA user_version;")
+            .fetch_one(conn)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_run_migrations_on_empty_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut conn = create_test_connection(&dir).await;
+
+        run_migrations(&mut conn).await.unwrap();
+        let version = user_version(&mut conn).await;
+        assert!(version >= 2);
+
+        // Running again is a no-op
+        run_migrations(&mut conn).await.unwrap();
+        assert_eq!(user_version(&mut conn).await, version);
+    }
+
+    #[tokio::test]
+    async fn test_run_migrations_upgrades_existing_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut conn = create_test_connection(&dir).await;
+
+        // Schema created by releases without migrations
+        sqlx::query(
+            "CREATE TABLE projects (
+                project_id TEXT PRIMARY KEY,
+                project_path TEXT
+            );",
+        )
+        .execute(&mut conn)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO projects VALUES ('project_1', '/work/backend');")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        run_migrations(&mut conn).await.unwrap();
+
+        let pool = create_test_pool(&dir).await;
+
+        let projects = get_projects(&pool).await.unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].name(), "backend");
+        assert_eq!(projects[0].display_name, None);
+    }
+
+    #[tokio::test]
+    async fn test_foreign_keys_cascade_deletes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut conn = create_test_connection(&dir).await;
+
+        sqlx::query(
+            "CREATE TABLE projects (project_id TEXT PRIMARY KEY, project_path TEXT);
+             CREATE TABLE scrolls (
+                scroll_id TEXT PRIMARY KEY, scroll_path TEXT, content TEXT, project_id TEXT
+             );
+             CREATE TABLE prompts (
+                prompt_id TEXT, project_id TEXT, content TEXT, output TEXT, prev_prompt_id TEXT
+             );
+             INSERT INTO projects VALUES ('project_1', '/work/backend');
+             INSERT INTO scrolls VALUES ('scroll_1', 'notes.md', 'notes', 'project_1');
+             INSERT INTO scrolls VALUES ('scroll_2', 'lost.md', 'lost', 'deleted_project');
+             INSERT INTO prompts VALUES ('prompt_1', 'project_1', 'Q', 'A', 'project_1');
+             INSERT INTO prompts VALUES ('prompt_2', 'deleted_project', 'Q', 'A', 'x');
+             INSERT INTO prompts VALUES ('prompt_1', 'project_1', 'Copy', 'A', 'project_1');",
+        )
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+        run_migrations(&mut conn).await.unwrap();
+
+        // Rows of deleted projects and repeated ids are set aside by the migration
+        let orphans: i64 = sqlx::query_scalar(
+            "SELECT (SELECT COUNT(*) FROM scrolls WHERE project_id = 'deleted_project')
+                  + (SELECT COUNT(*) FROM prompts WHERE project_id = 'deleted_project');",
+        )
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+        assert_eq!(orphans, 0);
+        let quarantined: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT prompt_id, content, reason FROM quarantined_prompts ORDER BY prompt_id;",
+        )
+        .fetch_all(&mut conn)
+        .await
+        .unwrap();
+        assert_eq!(
+            quarantined,
+            vec![
+                (
+                    String::from("prompt_1"),
+                    String::from("Copy"),
+                    String::from("duplicate id")
+                ),
+                (
+                    String::from("prompt_2"),
+                    String::from("Q"),
+                    String::from("deleted project")
+                ),
+            ]
+        );
+        assert_eq!(
+            count_quarantined(&mut conn).await.unwrap(),
+            vec![("quarantined_scrolls", 1), ("quarantined_prompts", 2)]
+        );
+
+        // Rows pointing to an unknown project are refused
+        let result = sqlx::query(
+            "INSERT INTO prompts (prompt_id, project_id) VALUES ('prompt_3', 'unknown');",
+        )
+        .execute(&mut conn)
+        .await;
+        assert!(result.is_err());
+
+        // Deleting the project removes its scrolls and prompts
+        sqlx::query("DELETE FROM projects WHERE project_id = 'project_1';")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        let pool = create_test_pool(&dir).await;
+        assert!(get_scrolls(&pool, "project_1").await.unwrap().is_empty());
+        assert!(get_prompts(&pool, "project_1").await.unwrap().is_empty());
+        assert!(delete_project(&pool, "project_1").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_open_db_pool_creates_database() {
+        let _ = initialize_logger().await;
+        let dir = tempfile::tempdir().unwrap();
+        let db_file = database_file(dir.path(), "work").unwrap();
+        assert_eq!(db_file, dir.path().join("work.db"));
+
+        let pool = open_db_pool(&db_file, 5).await.unwrap();
+        assert!(db_file.is_file());
+        assert!(get_projects(&pool).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_open_db_pool_snapshots_before_migrating() {
+        let _ = initialize_logger().await;
+        let dir = tempfile::tempdir().unwrap();
+        let db_file = dir.path().join("legatio.db");
+
+        // Schema created by releases without migrations
+        let mut conn = create_test_connection(&dir).await;
+        sqlx::query(
+            "CREATE TABLE projects (project_id TEXT PRIMARY KEY, project_path TEXT);
+             INSERT INTO projects VALUES ('project_1', '/work/backend');",
+        )
+        .execute(&mut conn)
+        .await
+        .unwrap();
+        let _ = conn.close().await;
+
+        let pool = open_db_pool(&db_file, 5).await.unwrap();
+        pool.close().await;
+        let backup_dir = get_backup_dir(&db_file).unwrap();
+        let snapshots = list_snapshots(&backup_dir).await.unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert!(snapshots[0].reason.starts_with("pre-migrate-"));
+        assert_eq!(snapshots[0].projects, Some(1));
+
+        // An up to date database is opened as is
+        let pool = open_db_pool(&db_file, 5).await.unwrap();
+        pool.close().await;
+        assert_eq!(list_snapshots(&backup_dir).await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_list_databases() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("work.db"), "").unwrap();
+        fs::write(dir.path().join("personal.db"), "").unwrap();
+        fs::write(dir.path().join("personal.db-wal"), "").unwrap();
+        fs::write(dir.path().join("notes.txt"), "").unwrap();
+        fs::create_dir(dir.path().join("backups")).unwrap();
+
+        assert_eq!(
+            list_databases(dir.path()).unwrap(),
+            vec![String::from("personal"), String::from("work")]
+        );
+        assert!(database_file(dir.path(), "../work").is_err());
+        assert!(database_file(dir.path(), "").is_err());
+    }
+
+    #[test]
+    fn test_move_legacy_database() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let data_dir = tempfile::tempdir().unwrap();
+        fs::write(config_dir.path().join("legatio.db"), "db").unwrap();
+        fs::create_dir(config_dir.path().join("backups")).unwrap();
+        fs::write(config_dir.path().join("backups").join("snapshot.db"), "").unwrap();
+
+        assert!(move_legacy_database(config_dir.path(), data_dir.path()).unwrap());
+        assert!(!config_dir.path().join("legatio.db").exists());
+        assert_eq!(
+            fs::read_to_string(data_dir.path().join("legatio.db")).unwrap(),
+            "db"
+        );
+        assert!(data_dir
+            .path()
+            .join("backups")
+            .join("legatio")
+            .join("snapshot.db")
+            .is_file());
+
+        // A database already in the data directory is never overwritten
+        fs::write(config_dir.path().join("legatio.db"), "stale").unwrap();
+        assert!(!move_legacy_database(config_dir.path(), data_dir.path()).unwrap());
+        assert_eq!(
+            fs::read_to_string(data_dir.path().join("legatio.db")).unwrap(),
+            "db"
+        );
+    }
+}